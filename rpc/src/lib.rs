@@ -0,0 +1,149 @@
+//! RPC interface for the Secret Social Recovery pallet.
+//!
+//! Exposes the `SecretSocialRecoveryApi` runtime API over JSON-RPC, so front-ends can query the
+//! recovery state of an account and build the payload a friend signs for `approve_recovery`.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::error::ErrorObjectOwned};
+use serde::{de::DeserializeOwned, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+
+pub use secret_social_recovery_runtime_api::{
+	RecoveryConfig, RecoveryStatus, SecretSocialRecoveryApi as SecretSocialRecoveryRuntimeApi,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Recovery RPC methods.
+#[rpc(client, server)]
+pub trait SecretSocialRecoveryApi<BlockHash, AccountId, BlockNumber> {
+	/// The recovery config of `account`, if it is recoverable.
+	#[method(name = "recovery_getConfig")]
+	fn config(&self, account: AccountId, at: Option<BlockHash>) -> RpcResult<Option<RecoveryConfig<BlockNumber>>>;
+
+	/// The rescuers with an active recovery for `lost`.
+	#[method(name = "recovery_getActive")]
+	fn active(&self, lost: AccountId, at: Option<BlockHash>) -> RpcResult<Vec<AccountId>>;
+
+	/// The progress of the recovery of `lost` by `rescuer`, if it has been initiated.
+	#[method(name = "recovery_getStatus")]
+	fn status(
+		&self,
+		lost: AccountId,
+		rescuer: AccountId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<RecoveryStatus<BlockNumber>>>;
+
+	/// The exact bytes a friend of `group` must sign to approve the recovery of `lost` by
+	/// `rescuer`, if it has been initiated.
+	#[method(name = "recovery_approvalPayload")]
+	fn approval_payload(
+		&self,
+		lost: AccountId,
+		rescuer: AccountId,
+		group: u16,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<Bytes>>;
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+/// An implementation of recovery specific RPC methods.
+pub struct SecretSocialRecovery<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> SecretSocialRecovery<C, B> {
+	/// Create new `SecretSocialRecovery` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		SecretSocialRecovery {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block> SecretSocialRecovery<C, Block>
+where
+	Block: BlockT,
+	C: HeaderBackend<Block>,
+{
+	/// The block to query, defaulting to the best block.
+	fn block_hash(&self, at: Option<<Block as BlockT>::Hash>) -> <Block as BlockT>::Hash {
+		at.unwrap_or_else(|| self.client.info().best_hash)
+	}
+}
+
+/// Map a runtime api failure to an RPC error.
+fn runtime_error(message: &str, error: impl std::fmt::Debug) -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(Error::RuntimeError.into(), message, Some(format!("{:?}", error)))
+}
+
+impl<C, Block, AccountId, BlockNumber>
+	SecretSocialRecoveryApiServer<<Block as BlockT>::Hash, AccountId, BlockNumber> for SecretSocialRecovery<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: SecretSocialRecoveryRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+	BlockNumber: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+	fn config(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<RecoveryConfig<BlockNumber>>> {
+		let api = self.client.runtime_api();
+		api.recovery_config(self.block_hash(at), account)
+			.map_err(|e| runtime_error("Unable to query recovery config.", e))
+	}
+
+	fn active(&self, lost: AccountId, at: Option<<Block as BlockT>::Hash>) -> RpcResult<Vec<AccountId>> {
+		let api = self.client.runtime_api();
+		api.active_recoveries_for(self.block_hash(at), lost)
+			.map_err(|e| runtime_error("Unable to query active recoveries.", e))
+	}
+
+	fn status(
+		&self,
+		lost: AccountId,
+		rescuer: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<RecoveryStatus<BlockNumber>>> {
+		let api = self.client.runtime_api();
+		api.recovery_status(self.block_hash(at), lost, rescuer)
+			.map_err(|e| runtime_error("Unable to query recovery status.", e))
+	}
+
+	fn approval_payload(
+		&self,
+		lost: AccountId,
+		rescuer: AccountId,
+		group: u16,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<Bytes>> {
+		let api = self.client.runtime_api();
+		api.approval_payload(self.block_hash(at), lost, rescuer, group)
+			.map(|payload| payload.map(Bytes))
+			.map_err(|e| runtime_error("Unable to build approval payload.", e))
+	}
+}
@@ -0,0 +1,165 @@
+use super::*;
+
+use codec::Encode;
+use sp_api::ApiRef;
+use sp_blockchain::{BlockStatus, Info};
+use sp_core::hexdisplay::HexDisplay;
+use sp_runtime::{
+	generic::Header,
+	traits::{BlakeTwo256, NumberFor},
+	OpaqueExtrinsic,
+};
+
+type Block = sp_runtime::generic::Block<Header<u64, BlakeTwo256>, OpaqueExtrinsic>;
+type Hash = <Block as BlockT>::Hash;
+
+const LOST: u64 = 1;
+const RESCUER: u64 = 2;
+const OTHER_RESCUER: u64 = 3;
+
+/// A local client serving a fixed recovery of `LOST` by `RESCUER` and `OTHER_RESCUER`.
+struct TestApi;
+
+/// The runtime API of `TestApi`.
+struct TestRuntimeApi;
+
+impl ProvideRuntimeApi<Block> for TestApi {
+	type Api = TestRuntimeApi;
+
+	fn runtime_api(&self) -> ApiRef<Self::Api> {
+		TestRuntimeApi.into()
+	}
+}
+
+sp_api::mock_impl_runtime_apis! {
+	impl SecretSocialRecoveryRuntimeApi<Block, u64, u64> for TestRuntimeApi {
+		fn recovery_config(account: u64) -> Option<RecoveryConfig<u64>> {
+			if account == LOST {
+				Some(RecoveryConfig::default())
+			} else {
+				None
+			}
+		}
+
+		fn active_recoveries_for(lost: u64) -> Vec<u64> {
+			if lost == LOST {
+				vec![RESCUER, OTHER_RESCUER]
+			} else {
+				vec![]
+			}
+		}
+
+		fn recovery_status(lost: u64, rescuer: u64) -> Option<RecoveryStatus<u64>> {
+			if lost == LOST && rescuer == RESCUER {
+				Some(RecoveryStatus {
+					approvals: vec![1],
+					thresholds: vec![2],
+					threshold_reached: false,
+					claimable_at: 11,
+				})
+			} else {
+				None
+			}
+		}
+
+		fn proxied_account(_rescuer: u64) -> Option<u64> {
+			None
+		}
+
+		fn approval_payload(lost: u64, rescuer: u64, group: u16) -> Option<Vec<u8>> {
+			if lost == LOST && rescuer == RESCUER {
+				Some(approval_payload(lost, rescuer, group))
+			} else {
+				None
+			}
+		}
+	}
+}
+
+impl HeaderBackend<Block> for TestApi {
+	fn header(&self, _hash: Hash) -> sp_blockchain::Result<Option<<Block as BlockT>::Header>> {
+		Ok(None)
+	}
+
+	fn info(&self) -> Info<Block> {
+		Info {
+			best_hash: Default::default(),
+			best_number: 0,
+			genesis_hash: Default::default(),
+			finalized_hash: Default::default(),
+			finalized_number: 0,
+			finalized_state: None,
+			number_leaves: 1,
+			block_gap: None,
+		}
+	}
+
+	fn status(&self, _hash: Hash) -> sp_blockchain::Result<BlockStatus> {
+		Ok(BlockStatus::InChain)
+	}
+
+	fn number(&self, _hash: Hash) -> sp_blockchain::Result<Option<NumberFor<Block>>> {
+		Ok(Some(0))
+	}
+
+	fn hash(&self, _number: NumberFor<Block>) -> sp_blockchain::Result<Option<Hash>> {
+		Ok(Some(Default::default()))
+	}
+}
+
+/// The payload of the recovery of `LOST` by `RESCUER`, initiated at block 1.
+fn approval_payload(lost: u64, rescuer: u64, group: u16) -> Vec<u8> {
+	(&b"secret-social-recovery/approve"[..], Hash::default(), lost, rescuer, group, 1u64).encode()
+}
+
+fn rpc() -> SecretSocialRecovery<TestApi, Block> {
+	SecretSocialRecovery::new(Arc::new(TestApi))
+}
+
+#[test]
+fn config_works() {
+	let rpc = rpc();
+	assert_eq!(rpc.config(LOST, None).unwrap(), Some(RecoveryConfig::default()));
+	assert_eq!(rpc.config(RESCUER, None).unwrap(), None);
+}
+
+#[test]
+fn active_works() {
+	let rpc = rpc();
+	assert_eq!(rpc.active(LOST, None).unwrap(), vec![RESCUER, OTHER_RESCUER]);
+	assert_eq!(rpc.active(RESCUER, Some(Default::default())).unwrap(), vec![]);
+}
+
+#[test]
+fn status_works() {
+	let rpc = rpc();
+	let status = rpc.status(LOST, RESCUER, None).unwrap().unwrap();
+	assert_eq!(status.approvals, vec![1]);
+	assert_eq!(status.thresholds, vec![2]);
+	assert!(!status.threshold_reached);
+	assert_eq!(status.claimable_at, 11);
+	assert_eq!(rpc.status(LOST, OTHER_RESCUER, None).unwrap(), None);
+}
+
+#[test]
+fn approval_payload_works() {
+	let rpc = rpc();
+	assert_eq!(
+		rpc.approval_payload(LOST, RESCUER, 1, None).unwrap(),
+		Some(Bytes(approval_payload(LOST, RESCUER, 1)))
+	);
+	// the payload binds the group
+	assert_ne!(approval_payload(LOST, RESCUER, 0), approval_payload(LOST, RESCUER, 1));
+	// and there is none for a recovery that wasn't initiated
+	assert_eq!(rpc.approval_payload(LOST, OTHER_RESCUER, 0, None).unwrap(), None);
+}
+
+#[tokio::test]
+async fn approval_payload_is_served_over_json_rpc() {
+	let module = <SecretSocialRecovery<TestApi, Block> as SecretSocialRecoveryApiServer<Hash, u64, u64>>::into_rpc(rpc());
+
+	let payload: Option<Bytes> = module.call("recovery_approvalPayload", [LOST, RESCUER, 0]).await.unwrap();
+	let expected = approval_payload(LOST, RESCUER, 0);
+	assert_eq!(payload, Some(Bytes(expected.clone())));
+	assert_eq!(serde_json::to_string(&payload).unwrap(), format!(r#""0x{}""#, HexDisplay::from(&expected)));
+}
@@ -0,0 +1,31 @@
+//! Runtime API definition for the Secret Social Recovery pallet.
+//!
+//! Lets wallets query the recovery state of an account without reimplementing the storage key
+//! hashing of the pallet. A runtime implements it with the pallet's helpers:
+//! `recovery_config`, `active_recoveries_for`, `recovery_status`, `proxy` and `approval_payload`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+pub use secret_social_recovery::{RecoveryConfig, RecoveryStatus};
+
+sp_api::decl_runtime_apis! {
+	pub trait SecretSocialRecoveryApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// The recovery configuration of `account`, if it is recoverable.
+		fn recovery_config(account: AccountId) -> Option<RecoveryConfig<BlockNumber>>;
+		/// The accounts currently trying to rescue `lost`.
+		fn active_recoveries_for(lost: AccountId) -> Vec<AccountId>;
+		/// The progress of the recovery of `lost` by `rescuer`, if it has been initiated.
+		fn recovery_status(lost: AccountId, rescuer: AccountId) -> Option<RecoveryStatus<BlockNumber>>;
+		/// The recovered account `rescuer` can make calls on behalf of, if any.
+		fn proxied_account(rescuer: AccountId) -> Option<AccountId>;
+		/// The exact bytes a friend of `group` must sign to approve the recovery of `lost` by
+		/// `rescuer`, if it has been initiated.
+		fn approval_payload(lost: AccountId, rescuer: AccountId, group: u16) -> Option<Vec<u8>>;
+	}
+}
@@ -0,0 +1,546 @@
+//! Benchmarks for the secret social recovery pallet.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_benchmarking::v1::{account, benchmarks, impl_benchmark_test_suite};
+use frame_support::traits::{Currency, ReservableCurrency};
+use frame_system::{pallet_prelude::BlockNumberFor, RawOrigin};
+use curve25519_dalek::{constants::RISTRETTO_BASEPOINT_POINT, scalar::Scalar};
+use sp_core::crypto::KeyTypeId;
+use sp_io::hashing::blake2_256;
+use sp_runtime::traits::{Bounded, Saturating};
+
+/// The key type the friends of the benchmarks sign approvals with.
+const FRIEND_KEY_TYPE: KeyTypeId = KeyTypeId(*b"ssrf");
+
+const SEED: u32 = 0;
+/// The deepest merkle proof benchmarked, i.e. a tree of 1024 friends.
+const MAX_PROOF_DEPTH: u32 = 10;
+
+/// A friend able to sign approvals through the keystore, along with its account.
+fn signing_friend<T: Config>(index: u32) -> (sr25519::Public, T::AccountId) {
+	let seed = blake2_256(&(b"friend", index).encode()).to_vec();
+	let public = sp_io::crypto::sr25519_generate(FRIEND_KEY_TYPE, Some(seed));
+	let account = T::AccountId::decode(&mut &public.0[..])
+		.expect("sr25519 public keys are valid account ids; qed");
+	(public, account)
+}
+
+/// A merkle tree of `2^depth` accounts, with `first` as its first leaf.
+fn merkle_tree<T: Config>(first: T::AccountId, depth: u32) -> FriendsTree<T::AccountId> {
+	let mut leaves = vec![first];
+	for i in 1..(1u32 << depth) {
+		leaves.push(account("leaf", i, SEED));
+	}
+	FriendsTree::new(leaves)
+}
+
+/// A friend of a BLS group.
+fn bls_friend(index: u32) -> bls::BlsSecretKey {
+	bls::BlsSecretKey::from_seed(&blake2_256(&(b"bls friend", index).encode()))
+}
+
+/// A merkle tree of the keys of `friends`, filled up to `2^depth` leaves with other keys.
+fn bls_tree(friends: &[bls::BlsSecretKey], depth: u32) -> FriendsTree<bls::BlsPublicKey> {
+	let mut leaves: Vec<_> = friends.iter().map(bls::BlsSecretKey::public_key).collect();
+	for i in friends.len() as u32..(1u32 << depth) {
+		leaves.push(bls_friend(u32::MAX - i).public_key());
+	}
+	FriendsTree::new(leaves)
+}
+
+/// A ring of `size` accounts, with the secret key of its last member.
+fn ring_of<T: Config>(size: u32) -> (Vec<T::AccountId>, [u8; 32]) {
+	let secrets: Vec<_> = (0..size)
+		.map(|i| Scalar::from_bytes_mod_order(blake2_256(&(b"ring member", i).encode())))
+		.collect();
+	let ring = secrets
+		.iter()
+		.map(|secret| {
+			let public = (secret * RISTRETTO_BASEPOINT_POINT).compress();
+			T::AccountId::decode(&mut &public.as_bytes()[..]).expect("ristretto points are valid account ids; qed")
+		})
+		.collect();
+	(ring, secrets[secrets.len() - 1].to_bytes())
+}
+
+/// The signature of `owner_key` on the blinded recovery of `groups` under `blinded_key`, without
+/// a delay period.
+fn sign_blinded_recovery<T: Config>(
+	owner_key: &sr25519::Public,
+	blinded_key: &[u8; 32],
+	groups: &[RecoveryGroup],
+	policy: GroupPolicy,
+	rescuers_merkle_root: &Option<Vec<u8>>,
+) -> Signature {
+	let delay_period: BlockNumberFor<T> = 0u32.into();
+	let payload = blinded_recovery_payload(blinded_key, groups, policy, &delay_period, rescuers_merkle_root);
+	sp_io::crypto::sr25519_sign(FRIEND_KEY_TYPE, owner_key, &payload).expect("the owner's key is in the keystore; qed")
+}
+
+/// Fill the active recovery of `lost` by `rescuer` with `approvals` approvals from other friends,
+/// at the positions following the first leaf of a tree of depth `depth`, the one of the first
+/// group of `lost` if it is recoverable.
+fn add_approvals<T: Config>(lost: &T::AccountId, rescuer: &T::AccountId, depth: u32, approvals: u32) {
+	let mut bitmap = ApprovalBitmap::default();
+	for i in 0..approvals {
+		bitmap.insert((1 << depth) + 1 + i);
+	}
+	let approval_roots = Recoverable::<T>::get(lost)
+		.map(|config| vec![config.groups()[0].friends_merkle_root().to_vec()])
+		.unwrap_or_default();
+	let active_recovery = ActiveRecovery {
+		created: <frame_system::Pallet<T>>::block_number(),
+		approvals: vec![bitmap],
+		key_images: vec![],
+		approval_roots,
+	};
+	<ActiveRecoveries<T>>::insert(lost, rescuer, active_recovery);
+}
+
+benchmarks! {
+	set_recovered {
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+	}: _(RawOrigin::Root, lost.clone(), rescuer.clone())
+	verify {
+		assert_eq!(Proxy::<T>::get(&rescuer), Some(lost));
+	}
+
+	as_recovered {
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		<Proxy<T>>::insert(&rescuer, &lost);
+		let call: <T as Config>::RuntimeCall = Call::<T>::create_recovery {
+			friends_merkle_root: vec![0; MERKLE_ROOT_LENGTH],
+			threshold: 1,
+			delay_period: 0u32.into(),
+			friend_count: FriendCount::new(1),
+		}.into();
+	}: _(RawOrigin::Signed(rescuer), lost.clone(), Box::new(call))
+	verify {
+		assert!(Recoverable::<T>::contains_key(&lost));
+	}
+
+	create_recovery {
+		let caller: T::AccountId = account("caller", 0, SEED);
+	}: _(RawOrigin::Signed(caller.clone()), vec![0; MERKLE_ROOT_LENGTH], 1, 0u32.into(), FriendCount::new(1))
+	verify {
+		assert!(Recoverable::<T>::contains_key(&caller));
+	}
+
+	create_group_recovery {
+		let g in 1 .. T::MaxGroups::get() as u32;
+		let caller: T::AccountId = account("caller", 0, SEED);
+		let groups = vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], 1); g as usize];
+		let friend_counts = vec![FriendCount::new(1); g as usize];
+	}: _(RawOrigin::Signed(caller.clone()), groups, GroupPolicy::AnyOf(g as u16), 0u32.into(), None, friend_counts)
+	verify {
+		assert!(Recoverable::<T>::contains_key(&caller));
+	}
+
+	initiate_recovery {
+		let d in 0 .. MAX_PROOF_DEPTH;
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		let rescuers_tree = merkle_tree::<T>(rescuer.clone(), d);
+		Pallet::<T>::do_create_recovery(
+			lost.clone(),
+			vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], 1)],
+			GroupPolicy::All,
+			0u32.into(),
+			Some(rescuers_tree.root()),
+		)?;
+		let rescuer_proof = rescuers_tree.proof_for(&rescuer);
+	}: _(RawOrigin::Signed(rescuer.clone()), lost.clone(), rescuer_proof)
+	verify {
+		assert!(ActiveRecoveries::<T>::contains_key(&lost, &rescuer));
+	}
+
+	approve_recovery {
+		let d in 0 .. MAX_PROOF_DEPTH;
+		let a in 0 .. T::MaxApprovals::get() as u32 - 1;
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		let (friend_key, friend) = signing_friend::<T>(0);
+		let friends_tree = merkle_tree::<T>(friend.clone(), d);
+		Pallet::<T>::do_create_recovery(
+			lost.clone(),
+			vec![RecoveryGroup::new(friends_tree.root(), 1)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+		add_approvals::<T>(&lost, &rescuer, d, a);
+		let payload = Pallet::<T>::approval_payload(&lost, &rescuer, 0).expect("the recovery is active; qed");
+		let signature = sp_io::crypto::sr25519_sign(FRIEND_KEY_TYPE, &friend_key, &payload)
+			.expect("the friend's key is in the keystore; qed");
+		let proof = friends_tree.proof_for(&friend).expect("friend is the first leaf; qed");
+	}: _(RawOrigin::Signed(rescuer.clone()), lost.clone(), rescuer.clone(), 0, signature, proof)
+	verify {
+		let active_recovery = ActiveRecoveries::<T>::get(&lost, &rescuer).expect("recovery is active; qed");
+		assert_eq!(active_recovery.total_approvals(), a + 1);
+	}
+
+	claim_recovery {
+		let a in 1 .. T::MaxApprovals::get() as u32;
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		Pallet::<T>::do_create_recovery(
+			lost.clone(),
+			vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], a as u16)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+		add_approvals::<T>(&lost, &rescuer, 0, a);
+		// The proxy is a consumer of the rescuer's account, which must exist
+		T::Currency::make_free_balance_be(&rescuer, T::Currency::minimum_balance() * 10u32.into());
+	}: _(RawOrigin::Signed(rescuer.clone()), lost.clone())
+	verify {
+		assert_eq!(Proxy::<T>::get(&rescuer), Some(lost));
+	}
+
+	set_auto_sweep {
+		let caller: T::AccountId = account("caller", 0, SEED);
+		Pallet::<T>::do_create_recovery(
+			caller.clone(),
+			vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], 1)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+	}: _(RawOrigin::Signed(caller.clone()), true)
+	verify {
+		assert!(Recoverable::<T>::get(&caller).map_or(false, |config| config.auto_sweep()));
+	}
+
+	sweep_recovered {
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		let balance = T::Currency::minimum_balance() * 100u32.into();
+		T::Currency::make_free_balance_be(&lost, balance);
+		Pallet::<T>::hold_deposit(&lost, T::CommitmentDeposit::get())?;
+		let reserved = T::Currency::minimum_balance() * 10u32.into();
+		T::Currency::reserve(&lost, reserved)?;
+		<Proxy<T>>::insert(&rescuer, &lost);
+	}: _(RawOrigin::Signed(rescuer.clone()), lost.clone(), rescuer.clone())
+	verify {
+		// the deposit is swept, the other reserve is left alone, along with the existential deposit
+		// keeping the account alive
+		assert_eq!(Deposits::<T>::get(&lost), Zero::zero());
+		assert_eq!(T::Currency::reserved_balance(&lost), reserved);
+		assert_eq!(T::Currency::free_balance(&lost), T::Currency::minimum_balance());
+		assert_eq!(T::Currency::free_balance(&rescuer), balance - reserved - T::Currency::minimum_balance());
+	}
+
+	create_blinded_recovery {
+		let g in 1 .. T::MaxGroups::get() as u32;
+		let (owner_key, owner) = signing_friend::<T>(0);
+		let key = blinded_key(&owner, &[0; 32]);
+		let groups = vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], 1); g as usize];
+		let friend_counts = vec![FriendCount::new(1); g as usize];
+		let policy = GroupPolicy::AnyOf(g as u16);
+		let owner_signature = sign_blinded_recovery::<T>(&owner_key, &key, &groups, policy, &None);
+		T::Currency::make_free_balance_be(&owner, BalanceOf::<T>::max_value() / 2u32.into());
+	}: _(RawOrigin::Signed(owner.clone()), key, groups, policy, 0u32.into(), None, friend_counts, owner_signature)
+	verify {
+		assert!(BlindedRecoverable::<T>::contains_key(blinded_storage_key(&owner, &key)));
+		assert_eq!(T::Currency::reserved_balance(&owner), T::BlindedRecoveryDeposit::get());
+	}
+
+	blind_recovery {
+		let (owner_key, owner) = signing_friend::<T>(0);
+		let key = blinded_key(&owner, &[0; 32]);
+		let groups = vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], 1); T::MaxGroups::get() as usize];
+		let policy = GroupPolicy::All;
+		Pallet::<T>::do_create_recovery(owner.clone(), groups.clone(), policy, 0u32.into(), None)?;
+		let owner_signature = sign_blinded_recovery::<T>(&owner_key, &key, &groups, policy, &None);
+		T::Currency::make_free_balance_be(&owner, BalanceOf::<T>::max_value() / 2u32.into());
+	}: _(RawOrigin::Signed(owner.clone()), key, owner_signature)
+	verify {
+		assert!(!Recoverable::<T>::contains_key(&owner));
+		assert!(BlindedRecoverable::<T>::contains_key(blinded_storage_key(&owner, &key)));
+	}
+
+	initiate_blinded_recovery {
+		let d in 0 .. MAX_PROOF_DEPTH;
+		let (owner_key, owner) = signing_friend::<T>(0);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		let salt = [0; 32];
+		let key = blinded_key(&owner, &salt);
+		let rescuers_tree = merkle_tree::<T>(rescuer.clone(), d);
+		let groups = vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], 1); T::MaxGroups::get() as usize];
+		let friend_counts = vec![FriendCount::new(1); T::MaxGroups::get() as usize];
+		let policy = GroupPolicy::All;
+		let rescuers_merkle_root = Some(rescuers_tree.root());
+		let owner_signature = sign_blinded_recovery::<T>(&owner_key, &key, &groups, policy, &rescuers_merkle_root);
+		T::Currency::make_free_balance_be(&owner, BalanceOf::<T>::max_value() / 2u32.into());
+		Pallet::<T>::create_blinded_recovery(
+			RawOrigin::Signed(owner.clone()).into(),
+			key,
+			groups,
+			policy,
+			0u32.into(),
+			rescuers_merkle_root,
+			friend_counts,
+			owner_signature,
+		)?;
+		let rescuer_proof = rescuers_tree.proof_for(&rescuer);
+	}: _(RawOrigin::Signed(rescuer.clone()), owner.clone(), salt, rescuer_proof)
+	verify {
+		assert!(ActiveRecoveries::<T>::contains_key(&owner, &rescuer));
+		assert_eq!(T::Currency::reserved_balance(&owner), 0u32.into());
+	}
+
+	commit_approval {
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		add_approvals::<T>(&lost, &rescuer, 0, 0);
+		T::Currency::make_free_balance_be(&rescuer, BalanceOf::<T>::max_value() / 2u32.into());
+		let pending = (0..T::MaxApprovals::get() as u32 - 1)
+			.map(|i| (blake2_256(&(b"pending", i).encode()), <frame_system::Pallet<T>>::block_number()))
+			.collect::<Vec<_>>();
+		<ApprovalCommitments<T>>::insert((&lost, &rescuer, &rescuer), pending);
+	}: _(RawOrigin::Signed(rescuer.clone()), lost.clone(), rescuer.clone(), [0; 32])
+	verify {
+		assert_eq!(
+			ApprovalCommitments::<T>::get((&lost, &rescuer, &rescuer)).len(),
+			T::MaxApprovals::get() as usize
+		);
+	}
+
+	reveal_approvals {
+		let n in 1 .. T::MaxApprovals::get() as u32;
+		let d in 0 .. Pallet::<T>::max_proof_depth().min(MAX_PROOF_DEPTH);
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		let friends: Vec<_> = (0..n).map(signing_friend::<T>).collect();
+		let mut leaves: Vec<T::AccountId> = friends.iter().map(|(_, friend)| friend.clone()).collect();
+		for i in n..(1u32 << d) {
+			leaves.push(account("leaf", i, SEED));
+		}
+		let friends_tree = FriendsTree::new(leaves);
+		Pallet::<T>::do_create_recovery(
+			lost.clone(),
+			vec![RecoveryGroup::new(friends_tree.root(), n as u16)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+		add_approvals::<T>(&lost, &rescuer, 0, 0);
+		let payload = Pallet::<T>::approval_payload(&lost, &rescuer, 0).expect("the recovery is active; qed");
+		let openings: Vec<_> = friends
+			.iter()
+			.map(|(friend_key, friend)| ApprovalOpening {
+				group: 0,
+				signature: sp_io::crypto::sr25519_sign(FRIEND_KEY_TYPE, friend_key, &payload)
+					.expect("the friend's key is in the keystore; qed"),
+				proof: friends_tree.proof_for(friend).expect("friends are leaves of the tree; qed"),
+				salt: blake2_256(&friend.encode()),
+			})
+			.collect();
+		let committed = <frame_system::Pallet<T>>::block_number();
+		let commitments = openings
+			.iter()
+			.map(|opening| (approval_commitment(&lost, &rescuer, opening), committed))
+			.collect::<Vec<_>>();
+		T::Currency::make_free_balance_be(&rescuer, BalanceOf::<T>::max_value() / 2u32.into());
+		T::Currency::reserve(&rescuer, T::CommitmentDeposit::get().saturating_mul(n.into()))?;
+		<ApprovalCommitments<T>>::insert((&lost, &rescuer, &rescuer), commitments);
+		<frame_system::Pallet<T>>::set_block_number(committed + T::RevealDelay::get());
+	}: _(RawOrigin::Signed(rescuer.clone()), lost.clone(), rescuer.clone(), openings)
+	verify {
+		let active_recovery = ActiveRecoveries::<T>::get(&lost, &rescuer).expect("recovery is active; qed");
+		assert_eq!(active_recovery.total_approvals(), n);
+		assert!(!ApprovalCommitments::<T>::contains_key((&lost, &rescuer, &rescuer)));
+		assert_eq!(T::Currency::reserved_balance(&rescuer), 0u32.into());
+	}
+
+	clear_commitments {
+		let c in 0 .. T::MaxApprovals::get() as u32;
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		let pending = (0..T::MaxApprovals::get() as u32)
+			.map(|i| (blake2_256(&(b"pending", i).encode()), <frame_system::Pallet<T>>::block_number()))
+			.collect::<Vec<_>>();
+		let deposits = T::CommitmentDeposit::get().saturating_mul((pending.len() as u32).into());
+		for i in 0..c {
+			let committer: T::AccountId = account("committer", i, SEED);
+			T::Currency::make_free_balance_be(&committer, BalanceOf::<T>::max_value() / 2u32.into());
+			T::Currency::reserve(&committer, deposits)?;
+			<ApprovalCommitments<T>>::insert((&lost, &rescuer, &committer), pending.clone());
+		}
+	}: {
+		assert_eq!(Pallet::<T>::clear_commitments(&lost, &rescuer), c);
+	}
+	verify {
+		assert_eq!(ApprovalCommitments::<T>::iter_prefix((&lost, &rescuer)).count(), 0);
+	}
+
+	rotate_friends_merkle_root {
+		let caller: T::AccountId = account("caller", 0, SEED);
+		Pallet::<T>::do_create_recovery(
+			caller.clone(),
+			vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], 1)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+		// The oldest replaced root is dropped
+		for i in 0..T::MaxPreviousRoots::get() {
+			let root = blake2_256(&i.encode()).to_vec();
+			let origin = RawOrigin::Signed(caller.clone()).into();
+			Pallet::<T>::rotate_friends_merkle_root(origin, 0, root, FriendCount::new(1))?;
+		}
+	}: _(RawOrigin::Signed(caller.clone()), 0, vec![1; MERKLE_ROOT_LENGTH], FriendCount::new(1))
+	verify {
+		let config = Recoverable::<T>::get(&caller).expect("the caller is recoverable; qed");
+		assert_eq!(config.groups()[0].friends_merkle_root(), &[1; MERKLE_ROOT_LENGTH][..]);
+		assert_eq!(config.previous_roots().len() as u32, T::MaxPreviousRoots::get());
+	}
+
+	set_threshold_key {
+		let caller: T::AccountId = account("caller", 0, SEED);
+		Pallet::<T>::do_create_recovery(
+			caller.clone(),
+			vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], 1)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+		let threshold_key = ThresholdKey { group_key: [1; 32], threshold: 2, signers: 3 };
+	}: _(RawOrigin::Signed(caller.clone()), Some(threshold_key))
+	verify {
+		assert!(Recoverable::<T>::get(&caller).map_or(false, |config| config.threshold_key().is_some()));
+	}
+
+	claim_with_threshold_signature {
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		// A FROST signature is verified as any Ed25519 signature of the group key
+		let group_key = sp_io::crypto::ed25519_generate(FRIEND_KEY_TYPE, None);
+		Pallet::<T>::do_create_recovery(
+			lost.clone(),
+			vec![RecoveryGroup::new(vec![0; MERKLE_ROOT_LENGTH], 1)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+		Pallet::<T>::set_threshold_key(
+			RawOrigin::Signed(lost.clone()).into(),
+			Some(ThresholdKey { group_key: group_key.0, threshold: 2, signers: 3 }),
+		)?;
+		add_approvals::<T>(&lost, &rescuer, 0, 0);
+		let created = <frame_system::Pallet<T>>::block_number();
+		let payload = threshold_recovery_payload(&Pallet::<T>::genesis_hash(), &lost, &rescuer, &created);
+		let signature = sp_io::crypto::ed25519_sign(FRIEND_KEY_TYPE, &group_key, &payload)
+			.expect("the group key is in the keystore; qed");
+		// The proxy is a consumer of the rescuer's account, which must exist
+		T::Currency::make_free_balance_be(&rescuer, T::Currency::minimum_balance() * 10u32.into());
+	}: _(RawOrigin::Signed(rescuer.clone()), lost.clone(), signature)
+	verify {
+		assert_eq!(Proxy::<T>::get(&rescuer), Some(lost));
+	}
+
+	approve_recovery_aggregated {
+		let n in 1 .. T::MaxApprovals::get() as u32;
+		let d in 0 .. Pallet::<T>::max_proof_depth().min(MAX_PROOF_DEPTH);
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		let friends: Vec<_> = (0..n).map(bls_friend).collect();
+		let friends_tree = bls_tree(&friends, d);
+		Pallet::<T>::do_create_recovery(
+			lost.clone(),
+			vec![RecoveryGroup::new(friends_tree.root(), n as u16)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+		add_approvals::<T>(&lost, &rescuer, 0, 0);
+		let created = <frame_system::Pallet<T>>::block_number();
+		let payload = bls_approval_payload(&Pallet::<T>::genesis_hash(), &lost, &rescuer, 0, &created);
+		let signatures: Vec<_> = friends.iter().map(|friend| friend.sign(&payload)).collect();
+		let signature = bls::aggregate(&signatures).expect("signatures are valid; qed");
+		let proofs: Vec<_> = friends
+			.iter()
+			.map(|friend| friends_tree.proof_for(&friend.public_key()).expect("friends are leaves of the tree; qed"))
+			.collect();
+	}: _(RawOrigin::Signed(rescuer.clone()), lost.clone(), rescuer.clone(), 0, proofs, signature)
+	verify {
+		let active_recovery = ActiveRecoveries::<T>::get(&lost, &rescuer).expect("recovery is active; qed");
+		assert_eq!(active_recovery.total_approvals(), n);
+	}
+
+	approve_recovery_anonymously {
+		let r in 1 .. T::MaxFriends::get();
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		let (ring, secret) = ring_of::<T>(r);
+		let friends_tree = FriendsTree::new(ring.clone());
+		Pallet::<T>::do_create_recovery(
+			lost.clone(),
+			vec![RecoveryGroup::new(friends_tree.root(), 1)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+		add_approvals::<T>(&lost, &rescuer, 0, 0);
+		let members: Vec<[u8; 32]> = ring
+			.iter()
+			.map(|member| member.encode().try_into().expect("ring members are 32 bytes; qed"))
+			.collect();
+		let created = <frame_system::Pallet<T>>::block_number();
+		let payload = ring_approval_payload(&Pallet::<T>::genesis_hash(), &lost, &rescuer, 0, &created);
+		let signature = lsag::sign(&members, &secret, &payload).expect("the signer is in the ring; qed");
+	}: _(RawOrigin::Signed(rescuer.clone()), lost.clone(), rescuer.clone(), 0, ring, signature)
+	verify {
+		let active_recovery = ActiveRecoveries::<T>::get(&lost, &rescuer).expect("recovery is active; qed");
+		assert_eq!(active_recovery.total_approvals(), 1);
+	}
+
+	// Not a call: `n` friends approving one by one, to compare with `approve_recovery_aggregated`
+	repeated_approve_recovery {
+		let n in 1 .. T::MaxApprovals::get() as u32;
+		let lost: T::AccountId = account("lost", 0, SEED);
+		let rescuer: T::AccountId = account("rescuer", 0, SEED);
+		let friends: Vec<_> = (0..n).map(signing_friend::<T>).collect();
+		let friends_tree = FriendsTree::new(friends.iter().map(|(_, friend)| friend.clone()).collect());
+		Pallet::<T>::do_create_recovery(
+			lost.clone(),
+			vec![RecoveryGroup::new(friends_tree.root(), n as u16)],
+			GroupPolicy::All,
+			0u32.into(),
+			None,
+		)?;
+		add_approvals::<T>(&lost, &rescuer, 0, 0);
+		let payload = Pallet::<T>::approval_payload(&lost, &rescuer, 0).expect("the recovery is active; qed");
+		let approvals: Vec<_> = friends
+			.iter()
+			.map(|(friend_key, friend)| {
+				let signature = sp_io::crypto::sr25519_sign(FRIEND_KEY_TYPE, friend_key, &payload)
+					.expect("the friend's key is in the keystore; qed");
+				(signature, friends_tree.proof_for(friend).expect("friends are leaves of the tree; qed"))
+			})
+			.collect();
+	}: {
+		for (signature, proof) in approvals {
+			Pallet::<T>::approve_recovery(
+				RawOrigin::Signed(rescuer.clone()).into(),
+				lost.clone(),
+				rescuer.clone(),
+				0,
+				signature,
+				proof,
+			)
+			.map_err(|e| e.error)?;
+		}
+	}
+	verify {
+		let active_recovery = ActiveRecoveries::<T>::get(&lost, &rescuer).expect("recovery is active; qed");
+		assert_eq!(active_recovery.total_approvals(), n);
+	}
+}
+
+impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
@@ -0,0 +1,361 @@
+//! `ssr`: off-chain tools for secret social recovery.
+//!
+//! Builds the friends merkle tree and its proofs for the account owner, and signs or verifies
+//! friend approvals. Everything is byte-identical to what `create_recovery`, `initiate_recovery`
+//! and `approve_recovery` check on-chain:
+//! - roots and signatures are printed as `0x` prefixed hex,
+//! - proofs are printed as `0x` prefixed hex of their SCALE encoding, ready to be submitted.
+//!
+//! The order of the accounts matters: proofs must be generated from the same list, in the same
+//! order, as the tree whose root was committed. Padded trees also need the same depth and seed.
+
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	process,
+};
+
+use codec::{Decode, Encode};
+use secret_social_recovery::{
+	client::{ApprovalPayload, FriendsTree, MerkleProof},
+	Signature,
+};
+use sp_core::{
+	crypto::{Pair as _, Ss58Codec},
+	sr25519, H256,
+};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "ssr", about = "Off-chain tools for secret social recovery")]
+enum Command {
+	/// Merkle trees of friends or rescuers.
+	Tree(TreeCommand),
+	/// Merkle proofs for the members of a tree.
+	Proof(ProofCommand),
+	/// Friend approvals of a recovery.
+	Approve(ApproveCommand),
+}
+
+#[derive(Debug, StructOpt)]
+enum TreeCommand {
+	/// Build the merkle tree of the given SS58 accounts and print its root.
+	Build {
+		/// The accounts of the tree, in order.
+		#[structopt(required = true)]
+		accounts: Vec<String>,
+		#[structopt(flatten)]
+		padding: Padding,
+	},
+}
+
+/// How a tree is padded with dummy leaves, see `FriendsTree::padded`.
+#[derive(Debug, Default, StructOpt)]
+struct Padding {
+	/// Pad the tree to this depth, so its proofs don't tell how many accounts it has.
+	#[structopt(long, requires = "seed")]
+	depth: Option<u32>,
+	/// The hex 32 byte secret seed of the padded tree, needed again to generate its proofs.
+	#[structopt(long, requires = "depth")]
+	seed: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+enum ProofCommand {
+	/// Generate the merkle proof of `member` in the tree of the given SS58 accounts.
+	Gen {
+		/// The account to prove the membership of.
+		#[structopt(long)]
+		member: String,
+		/// The accounts of the tree, in the same order as for `tree build`.
+		#[structopt(required = true)]
+		accounts: Vec<String>,
+		#[structopt(flatten)]
+		padding: Padding,
+	},
+}
+
+/// The recovery an approval is for.
+#[derive(Debug, StructOpt)]
+struct Recovery {
+	/// The account being recovered.
+	#[structopt(long)]
+	lost: String,
+	/// The account rescuing `lost`.
+	#[structopt(long)]
+	rescuer: String,
+	/// The hex genesis hash of the chain the recovery is on.
+	#[structopt(long)]
+	genesis_hash: String,
+	/// The index of the friend's group in the recovery config.
+	#[structopt(long, default_value = "0")]
+	group: u16,
+	/// The block the recovery was initiated at.
+	#[structopt(long)]
+	created: u32,
+}
+
+impl Recovery {
+	/// The payload a friend signs to approve this recovery.
+	fn payload(&self) -> Result<ApprovalPayload<sr25519::Public, H256, u32>, String> {
+		let genesis_hash = from_hex(&self.genesis_hash)?;
+		if genesis_hash.len() != 32 {
+			return Err("A genesis hash must be 32 bytes long".into());
+		}
+		Ok(ApprovalPayload::new(
+			H256::from_slice(&genesis_hash),
+			parse_account(&self.lost)?,
+			parse_account(&self.rescuer)?,
+			self.group,
+			self.created,
+		))
+	}
+}
+
+#[derive(Debug, StructOpt)]
+enum ApproveCommand {
+	/// Sign the approval of a recovery as a friend.
+	Sign {
+		#[structopt(flatten)]
+		recovery: Recovery,
+		/// The secret URI (seed, phrase or derivation) of the friend.
+		#[structopt(long, required_unless = "keystore")]
+		suri: Option<String>,
+		/// A keystore directory holding the secret of `friend`.
+		#[structopt(long, parse(from_os_str), conflicts_with = "suri", requires = "friend")]
+		keystore: Option<PathBuf>,
+		/// The SS58 account of the friend, to look up in the keystore.
+		#[structopt(long)]
+		friend: Option<String>,
+	},
+	/// Verify a friend approval exactly like `approve_recovery` does.
+	Verify {
+		#[structopt(flatten)]
+		recovery: Recovery,
+		/// The hex signature of the friend.
+		#[structopt(long)]
+		signature: String,
+		/// The hex SCALE encoded merkle proof of the friend.
+		#[structopt(long)]
+		proof: String,
+		/// The hex merkle root the proof must be valid against.
+		#[structopt(long)]
+		root: Option<String>,
+	},
+}
+
+fn main() {
+	let result = match Command::from_args() {
+		Command::Tree(TreeCommand::Build { accounts, padding }) => parse_accounts(&accounts)
+			.and_then(|accounts| build_tree(accounts, &padding))
+			.map(|tree| to_hex(&tree.root())),
+		Command::Proof(ProofCommand::Gen { member, accounts, padding }) => parse_accounts(&accounts)
+			.and_then(|accounts| Ok((accounts, parse_account(&member)?)))
+			.and_then(|(accounts, member)| gen_proof(accounts, member, &padding))
+			.map(|proof| to_hex(&proof.encode())),
+		Command::Approve(ApproveCommand::Sign {
+			recovery,
+			suri,
+			keystore,
+			friend,
+		}) => load_pair(suri, keystore, friend).and_then(|pair| {
+			let signature = sign_approval(&pair, &recovery.payload()?);
+			Ok(to_hex(signature.as_ref()))
+		}),
+		Command::Approve(ApproveCommand::Verify {
+			recovery,
+			signature,
+			proof,
+			root,
+		}) => (|| -> Result<String, String> {
+			let proof = MerkleProof::<sr25519::Public>::decode(&mut &from_hex(&proof)?[..])
+				.map_err(|e| format!("Invalid proof: {:?}", e))?;
+			let root = root.map(|root| from_hex(&root)).transpose()?;
+			verify_approval(
+				&recovery.payload()?,
+				&parse_signature(&signature)?,
+				&proof,
+				root.as_ref().map(|root| &root[..]),
+			)?;
+			Ok(format!("Valid approval by {}", proof.value.to_ss58check()))
+		})(),
+	};
+	match result {
+		Ok(output) => println!("{}", output),
+		Err(e) => {
+			eprintln!("Error: {}", e);
+			process::exit(1);
+		}
+	}
+}
+
+/// Build the merkle tree of `accounts`, in order, padded if asked to.
+fn build_tree(accounts: Vec<sr25519::Public>, padding: &Padding) -> Result<FriendsTree<sr25519::Public>, String> {
+	match (padding.depth, &padding.seed) {
+		(Some(depth), Some(seed)) => {
+			let seed: [u8; 32] = from_hex(seed)?.try_into().map_err(|_| "A seed must be 32 bytes long".to_string())?;
+			FriendsTree::padded(accounts, depth, &seed)
+				.ok_or_else(|| format!("A tree of depth {} can't hold all the accounts", depth))
+		}
+		_ => Ok(FriendsTree::new(accounts)),
+	}
+}
+
+/// Generate the merkle proof of `member` in the tree of `accounts`.
+fn gen_proof(
+	accounts: Vec<sr25519::Public>,
+	member: sr25519::Public,
+	padding: &Padding,
+) -> Result<MerkleProof<sr25519::Public>, String> {
+	build_tree(accounts, padding)?
+		.proof_for(&member)
+		.ok_or_else(|| format!("{} is not one of the accounts", member.to_ss58check()))
+}
+
+/// Sign the approval of a recovery.
+fn sign_approval(pair: &sr25519::Pair, payload: &ApprovalPayload<sr25519::Public, H256, u32>) -> Signature {
+	payload.sign(pair)
+}
+
+/// Check the signature and, if a root is given, the merkle proof of an approval.
+fn verify_approval(
+	payload: &ApprovalPayload<sr25519::Public, H256, u32>,
+	signature: &Signature,
+	proof: &MerkleProof<sr25519::Public>,
+	root: Option<&[u8]>,
+) -> Result<(), String> {
+	if !sr25519::Pair::verify(signature, payload.payload(), &proof.value) {
+		return Err("The signature is invalid".into());
+	}
+	if let Some(root) = root {
+		if !proof.validate(root) {
+			return Err("The merkle proof is invalid".into());
+		}
+	}
+	Ok(())
+}
+
+/// Load the signing key of a friend from a secret URI, or from the keystore by its account.
+fn load_pair(
+	suri: Option<String>,
+	keystore: Option<PathBuf>,
+	friend: Option<String>,
+) -> Result<sr25519::Pair, String> {
+	let suri = match (suri, keystore, friend) {
+		(Some(suri), _, _) => suri,
+		(None, Some(keystore), Some(friend)) => read_keystore(&keystore, &parse_account(&friend)?)?,
+		_ => return Err("Either --suri or --keystore and --friend must be given".into()),
+	};
+	sr25519::Pair::from_string(&suri, None).map_err(|e| format!("Invalid secret URI: {:?}", e))
+}
+
+/// Read the secret of `public` from a keystore directory.
+///
+/// Keystore files are named after the hex key type followed by the hex public key, and hold the
+/// secret as a JSON string.
+fn read_keystore(keystore: &Path, public: &sr25519::Public) -> Result<String, String> {
+	let suffix = hex::encode(public.as_ref());
+	let entries = fs::read_dir(keystore).map_err(|e| format!("Can't read keystore: {}", e))?;
+	for entry in entries {
+		let path = entry.map_err(|e| format!("Can't read keystore: {}", e))?.path();
+		let is_key = path
+			.file_name()
+			.and_then(|name| name.to_str())
+			.map_or(false, |name| name.ends_with(&suffix));
+		if is_key {
+			let content = fs::read_to_string(&path).map_err(|e| format!("Can't read key file: {}", e))?;
+			return serde_json::from_str(&content).map_err(|e| format!("Invalid key file: {}", e));
+		}
+	}
+	Err(format!("No key for {} in the keystore", public.to_ss58check()))
+}
+
+fn parse_account(account: &str) -> Result<sr25519::Public, String> {
+	sr25519::Public::from_ss58check(account).map_err(|e| format!("Invalid SS58 account {}: {:?}", account, e))
+}
+
+fn parse_accounts(accounts: &[String]) -> Result<Vec<sr25519::Public>, String> {
+	accounts.iter().map(|account| parse_account(account)).collect()
+}
+
+fn parse_signature(signature: &str) -> Result<Signature, String> {
+	let bytes = from_hex(signature)?;
+	if bytes.len() != 64 {
+		return Err("A signature must be 64 bytes long".into());
+	}
+	let mut raw = [0u8; 64];
+	raw.copy_from_slice(&bytes);
+	Ok(Signature::from_raw(raw))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+	format!("0x{}", hex::encode(bytes))
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>, String> {
+	hex::decode(s.trim_start_matches("0x")).map_err(|e| format!("Invalid hex {}: {}", s, e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn account(seed: &str) -> sr25519::Public {
+		sr25519::Pair::from_string(&format!("//{}", seed), None).unwrap().public()
+	}
+
+	#[test]
+	fn approvals_round_trip() {
+		let friends = vec![account("charlie"), account("dave"), account("eve")];
+		let (lost, rescuer) = (account("alice"), account("bob"));
+		let root = build_tree(friends.clone(), &Padding::default()).unwrap().root();
+
+		let proof = gen_proof(friends.clone(), account("dave"), &Padding::default()).unwrap();
+		let encoded = MerkleProof::<sr25519::Public>::decode(&mut &proof.encode()[..]).unwrap();
+		let dave = sr25519::Pair::from_string("//dave", None).unwrap();
+		let recovery = Recovery {
+			lost: lost.to_ss58check(),
+			rescuer: rescuer.to_ss58check(),
+			genesis_hash: to_hex(&[1; 32]),
+			group: 0,
+			created: 5,
+		};
+		let payload = recovery.payload().unwrap();
+		let signature = sign_approval(&dave, &payload);
+
+		assert_eq!(verify_approval(&payload, &signature, &encoded, Some(&root)), Ok(()));
+		// the pallet checks the signature over the payload of the chain and the recovery
+		let context = &b"secret-social-recovery/approve"[..];
+		let expected = (context, H256::repeat_byte(1), lost, rescuer, 0u16, 5u32).encode();
+		assert!(sr25519::Pair::verify(&signature, expected, &account("dave")));
+		// someone else's signature is rejected
+		let eve = sr25519::Pair::from_string("//eve", None).unwrap();
+		assert!(verify_approval(&payload, &sign_approval(&eve, &payload), &proof, None).is_err());
+		// and so is the approval of another recovery
+		let later = Recovery { created: 6, ..recovery };
+		assert!(verify_approval(&later.payload().unwrap(), &signature, &proof, None).is_err());
+		// and a proof against another root
+		let other_root = build_tree(vec![account("dave")], &Padding::default()).unwrap().root();
+		assert!(verify_approval(&payload, &signature, &proof, Some(&other_root)).is_err());
+	}
+
+	#[test]
+	fn proofs_are_only_generated_for_members() {
+		let friends = vec![account("charlie"), account("dave")];
+		assert!(gen_proof(friends, account("eve"), &Padding::default()).is_err());
+	}
+
+	#[test]
+	fn padded_proofs_have_the_padded_depth() {
+		let friends = vec![account("charlie"), account("dave"), account("eve")];
+		let padding = Padding { depth: Some(3), seed: Some(to_hex(&[7; 32])) };
+		let root = build_tree(friends.clone(), &padding).unwrap().root();
+		for friend in &friends {
+			let proof = gen_proof(friends.clone(), *friend, &padding).unwrap();
+			assert_eq!(proof.depth(), 3);
+			assert!(proof.validate(&root));
+		}
+		let too_shallow = Padding { depth: Some(1), seed: Some(to_hex(&[7; 32])) };
+		assert!(build_tree(friends, &too_shallow).is_err());
+	}
+}
@@ -0,0 +1,119 @@
+//! # BLS aggregate approvals
+//!
+//! The friends of a group can hold BLS12-381 keys instead of accounts. The leaves of the group's
+//! tree are then their compressed public keys, and the rescuer gathers their signatures of the
+//! `bls_approval_payload` into a single one, which `approve_recovery_aggregated` checks with two
+//! pairings however many friends signed.
+//!
+//! Keys are in G1 and signatures in G2, as in the proof of possession ciphersuite of the IETF BLS
+//! signatures draft. All friends sign the same message, so a friend could pick a key cancelling
+//! out the others' and sign for all of them alone. The owner rules this out by checking the
+//! [`BlsSecretKey::proof_of_possession`] of every key with [`verify_possession`] before committing
+//! it to the tree.
+//!
+//! Everything here is pure Rust and works in `no_std`.
+
+use bls12_381::{
+	hash_to_curve::{ExpandMsgXmd, HashToCurve},
+	multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar,
+};
+use codec::Encode;
+use sha2::Sha256;
+use sp_io::hashing::blake2_256;
+
+/// A compressed BLS12-381 public key, the leaf of a friend in a BLS group.
+pub type BlsPublicKey = [u8; 48];
+
+/// A compressed BLS12-381 signature, of one friend or aggregated.
+pub type BlsSignature = [u8; 96];
+
+/// The domain of the signatures of messages.
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The domain of the proofs of possession of keys.
+const POSSESSION_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The domain of the secret keys drawn from seeds.
+const KEYGEN_CONTEXT: &[u8] = b"secret-social-recovery/bls-keygen";
+
+/// A friend's BLS secret key
+#[derive(Clone)]
+pub struct BlsSecretKey(Scalar);
+
+impl BlsSecretKey {
+	/// The secret key drawn from `seed`, which must be kept as secret as the key itself.
+	pub fn from_seed(seed: &[u8; 32]) -> Self {
+		let mut wide = [0u8; 64];
+		wide[..32].copy_from_slice(&blake2_256(&(KEYGEN_CONTEXT, seed, 0u8).encode()));
+		wide[32..].copy_from_slice(&blake2_256(&(KEYGEN_CONTEXT, seed, 1u8).encode()));
+		BlsSecretKey(Scalar::from_bytes_wide(&wide))
+	}
+
+	/// The public key, to hand to the owner as the friend's leaf.
+	pub fn public_key(&self) -> BlsPublicKey {
+		G1Affine::from(G1Projective::generator() * self.0).to_compressed()
+	}
+
+	/// Sign `message`, usually the `bls_approval_payload` of a recovery.
+	pub fn sign(&self, message: &[u8]) -> BlsSignature {
+		G2Affine::from(hash(message, SIGNATURE_DST) * self.0).to_compressed()
+	}
+
+	/// The proof that the friend holds the secret key of its public key.
+	pub fn proof_of_possession(&self) -> BlsSignature {
+		G2Affine::from(hash(&self.public_key(), POSSESSION_DST) * self.0).to_compressed()
+	}
+}
+
+fn hash(message: &[u8], dst: &[u8]) -> G2Projective {
+	<G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(message, dst)
+}
+
+/// Decode a public key, refusing the identity, which any signature would be valid for.
+fn public_key(public_key: &BlsPublicKey) -> Option<G1Affine> {
+	Option::<G1Affine>::from(G1Affine::from_compressed(public_key)).filter(|key| !bool::from(key.is_identity()))
+}
+
+/// Check `signature` of `message`, hashed to the curve, under `public_key`.
+fn verify(public_key: &G1Affine, message: G2Projective, signature: &BlsSignature) -> bool {
+	let signature = match Option::<G2Affine>::from(G2Affine::from_compressed(signature)) {
+		Some(signature) => signature,
+		None => return false,
+	};
+	// e(public_key, H(message)) == e(generator, signature)
+	let message = G2Prepared::from(G2Affine::from(message));
+	let signature = G2Prepared::from(signature);
+	multi_miller_loop(&[(public_key, &message), (&-G1Affine::generator(), &signature)]).final_exponentiation()
+		== Gt::identity()
+}
+
+/// Aggregate the signatures of the same message by several friends. Returns `None` if one of
+/// them doesn't decode.
+pub fn aggregate(signatures: &[BlsSignature]) -> Option<BlsSignature> {
+	signatures
+		.iter()
+		.try_fold(G2Projective::identity(), |sum, signature| {
+			Option::<G2Affine>::from(G2Affine::from_compressed(signature)).map(|signature| sum + signature)
+		})
+		.map(|sum| G2Affine::from(sum).to_compressed())
+}
+
+/// Check that `signature` aggregates the signatures of `message` under all of `public_keys`.
+pub fn verify_aggregate(public_keys: &[BlsPublicKey], message: &[u8], signature: &BlsSignature) -> bool {
+	if public_keys.is_empty() {
+		return false;
+	}
+	let aggregated = public_keys.iter().try_fold(G1Projective::identity(), |sum, key| public_key(key).map(|key| sum + key));
+	match aggregated {
+		Some(aggregated) => verify(&G1Affine::from(aggregated), hash(message, SIGNATURE_DST), signature),
+		None => false,
+	}
+}
+
+/// Check the proof of possession of `public_key`, before committing it as a friend's leaf.
+pub fn verify_possession(public_key: &BlsPublicKey, proof: &BlsSignature) -> bool {
+	match self::public_key(public_key) {
+		Some(key) => verify(&key, hash(public_key, POSSESSION_DST), proof),
+		None => false,
+	}
+}
@@ -0,0 +1,84 @@
+//! # Client helpers
+//!
+//! Everything a wallet needs to take part in a recovery, without reimplementing the pallet's
+//! hashing or payload format:
+//! - the account owner builds the [`FriendsTree`] of a group and commits its root,
+//! - each friend gets their [`MerkleProof`] from the tree,
+//! - a friend approving a recovery signs the [`ApprovalPayload`] of the chain and the recovery,
+//! - an owner hiding its recovery configuration signs the [`BlindedRecoveryPayload`] of it.
+//!
+//! These are the very types and functions `initiate_recovery` and `approve_recovery` verify with,
+//! so what is built here is accepted on-chain as is.
+
+use codec::Encode;
+use sp_core::{sr25519, Pair};
+
+use crate::{approval_payload, blinded_key, blinded_recovery_payload, GroupPolicy, RecoveryGroup, Signature};
+
+pub use crate::proof::{FriendsTree, MerkleProof, ProofNode};
+
+/// The message a friend signs to approve the recovery of `lost` by `rescuer`
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ApprovalPayload<AccountId, Hash, BlockNumber> {
+	/// The genesis hash of the chain the recovery is on.
+	pub genesis_hash: Hash,
+	/// The account to be recovered.
+	pub lost: AccountId,
+	/// The account rescuing `lost`.
+	pub rescuer: AccountId,
+	/// The index of the friend's group in the recovery config.
+	pub group: u16,
+	/// The block the recovery was initiated at.
+	pub created: BlockNumber,
+}
+
+impl<AccountId: Encode, Hash: Encode, BlockNumber: Encode> ApprovalPayload<AccountId, Hash, BlockNumber> {
+	pub fn new(genesis_hash: Hash, lost: AccountId, rescuer: AccountId, group: u16, created: BlockNumber) -> Self {
+		ApprovalPayload { genesis_hash, lost, rescuer, group, created }
+	}
+
+	/// The bytes to sign, as checked by `approve_recovery`.
+	pub fn payload(&self) -> Vec<u8> {
+		approval_payload(&self.genesis_hash, &self.lost, &self.rescuer, self.group, &self.created)
+	}
+
+	/// Sign the payload with the key of a friend.
+	pub fn sign(&self, pair: &sr25519::Pair) -> Signature {
+		pair.sign(&self.payload())
+	}
+}
+
+/// The message an owner signs to store its recovery configuration under a blinded key
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct BlindedRecoveryPayload<BlockNumber> {
+	/// The blinded key of the configuration, see [`BlindedRecoveryPayload::key`].
+	pub blinded_key: [u8; 32],
+	pub groups: Vec<RecoveryGroup>,
+	pub policy: GroupPolicy,
+	pub delay_period: BlockNumber,
+	pub rescuers_merkle_root: Option<Vec<u8>>,
+}
+
+impl<BlockNumber: Encode> BlindedRecoveryPayload<BlockNumber> {
+	/// The blinded key of `account` with the secret `salt`, which its rescuers need to initiate
+	/// the recovery.
+	pub fn key<AccountId: Encode>(account: &AccountId, salt: &[u8; 32]) -> [u8; 32] {
+		blinded_key(account, salt)
+	}
+
+	/// The bytes to sign, as checked by `initiate_blinded_recovery` and `blind_recovery`.
+	pub fn payload(&self) -> Vec<u8> {
+		blinded_recovery_payload(
+			&self.blinded_key,
+			&self.groups,
+			self.policy,
+			&self.delay_period,
+			&self.rescuers_merkle_root,
+		)
+	}
+
+	/// Sign the payload with the key of the owner.
+	pub fn sign(&self, pair: &sr25519::Pair) -> Signature {
+		pair.sign(&self.payload())
+	}
+}
@@ -0,0 +1,282 @@
+//! # FROST threshold signatures
+//!
+//! Instead of approving a recovery one by one, the friends of an account can share a single
+//! Ed25519 key, any `threshold` of them producing a signature under it with the two rounds of
+//! FROST, as specified by RFC 9591 for the `FROST(Ed25519, SHA-512)` ciphersuite. The result is a
+//! plain Ed25519 signature, which `claim_with_threshold_signature` checks like any other.
+//!
+//! The key is split by a trusted dealer, as in the RFC's appendix: the account owner runs
+//! [`trusted_dealer_keygen`], hands each friend their [`KeyShare`], registers the group key with
+//! `set_threshold_key`, and forgets the rest. To sign the `threshold_recovery_payload` of a
+//! recovery, which names the chain by its genesis hash:
+//! 1. each signing friend draws nonces with [`commit`] and shares its [`SigningCommitment`],
+//! 2. each signing friend computes its [`SignatureShare`] over all the commitments with [`sign`],
+//! 3. anyone combines the shares into the signature with [`aggregate`].
+//!
+//! [`threshold_sign`] runs both rounds for a set of shares held in one place, as in tests.
+
+use curve25519_dalek::{
+	edwards::{CompressedEdwardsY, EdwardsPoint},
+	scalar::Scalar,
+	traits::Identity,
+};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+/// The context string of the ciphersuite.
+const CONTEXT: &[u8] = b"FROST-ED25519-SHA512-v1";
+
+/// The reasons a key can't be split or a signature produced
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum FrostError {
+	/// The threshold doesn't lie between one and the number of signers.
+	InvalidThreshold,
+	/// The commitments are given twice for a signer, or not at all for the signing one.
+	InvalidCommitments,
+	/// A commitment isn't a valid point.
+	InvalidPoint,
+	/// The shares don't match the commitments, or one isn't a valid scalar.
+	InvalidShares,
+}
+
+/// A friend's share of the group key
+#[derive(Clone, Debug)]
+pub struct KeyShare {
+	/// The friend's identifier, from 1 up to the number of signers.
+	pub index: u16,
+	/// The friend's secret share.
+	secret: Scalar,
+	/// The public key of the secret share.
+	pub public_share: [u8; 32],
+	/// The group public key.
+	pub group_key: [u8; 32],
+}
+
+/// The secret nonces of a signer for a single signature, used up by [`sign`]
+#[derive(Debug)]
+pub struct SigningNonces {
+	hiding: Scalar,
+	binding: Scalar,
+}
+
+/// The public commitment to a signer's nonces
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct SigningCommitment {
+	/// The signer's identifier.
+	pub index: u16,
+	/// The commitment to the hiding nonce.
+	pub hiding: [u8; 32],
+	/// The commitment to the binding nonce.
+	pub binding: [u8; 32],
+}
+
+/// A signer's share of the signature
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct SignatureShare {
+	/// The signer's identifier.
+	pub index: u16,
+	/// The share, a scalar.
+	pub share: [u8; 32],
+}
+
+fn hash(parts: &[&[u8]]) -> [u8; 64] {
+	let mut hasher = Sha512::new();
+	for part in parts {
+		hasher.update(part);
+	}
+	hasher.finalize().into()
+}
+
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar {
+	Scalar::from_bytes_mod_order_wide(&hash(parts))
+}
+
+fn identifier(index: u16) -> Scalar {
+	Scalar::from(index as u64)
+}
+
+fn public(scalar: &Scalar) -> EdwardsPoint {
+	EdwardsPoint::mul_base(scalar)
+}
+
+/// A uniformly random scalar.
+fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+	let mut wide = [0u8; 64];
+	rng.fill_bytes(&mut wide);
+	Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+fn decompress(bytes: &[u8; 32]) -> Result<EdwardsPoint, FrostError> {
+	CompressedEdwardsY(*bytes).decompress().ok_or(FrostError::InvalidPoint)
+}
+
+/// Split a fresh group key into `signers` shares, any `threshold` of which can sign.
+///
+/// Returns the group key, to register with `set_threshold_key`, and the shares of the friends.
+pub fn trusted_dealer_keygen<R: RngCore + CryptoRng>(
+	threshold: u16,
+	signers: u16,
+	rng: &mut R,
+) -> Result<([u8; 32], Vec<KeyShare>), FrostError> {
+	if threshold == 0 || threshold > signers {
+		return Err(FrostError::InvalidThreshold);
+	}
+	// The group secret is the constant term of a random polynomial of degree `threshold - 1`
+	let coefficients: Vec<Scalar> = (0..threshold).map(|_| random_scalar(rng)).collect();
+	let group_key = public(&coefficients[0]).compress().to_bytes();
+	let shares = (1..=signers)
+		.map(|index| {
+			let x = identifier(index);
+			let secret = coefficients.iter().rev().fold(Scalar::ZERO, |value, coefficient| value * x + coefficient);
+			KeyShare {
+				index,
+				secret,
+				public_share: public(&secret).compress().to_bytes(),
+				group_key,
+			}
+		})
+		.collect();
+	Ok((group_key, shares))
+}
+
+/// Round one: draw the nonces of `share` for a signature, and the commitment to share with the
+/// other signers.
+pub fn commit<R: RngCore + CryptoRng>(share: &KeyShare, rng: &mut R) -> (SigningNonces, SigningCommitment) {
+	let mut nonce = || {
+		let mut random = [0u8; 32];
+		rng.fill_bytes(&mut random);
+		hash_to_scalar(&[CONTEXT, b"nonce", &random, share.secret.as_bytes()])
+	};
+	let nonces = SigningNonces {
+		hiding: nonce(),
+		binding: nonce(),
+	};
+	let commitment = SigningCommitment {
+		index: share.index,
+		hiding: public(&nonces.hiding).compress().to_bytes(),
+		binding: public(&nonces.binding).compress().to_bytes(),
+	};
+	(nonces, commitment)
+}
+
+/// The commitments ordered by signer, checking that no signer is given twice.
+fn sorted(commitments: &[SigningCommitment]) -> Result<Vec<SigningCommitment>, FrostError> {
+	let mut sorted = commitments.to_vec();
+	sorted.sort_by_key(|commitment| commitment.index);
+	if sorted.is_empty() || sorted.windows(2).any(|pair| pair[0].index == pair[1].index) {
+		return Err(FrostError::InvalidCommitments);
+	}
+	Ok(sorted)
+}
+
+/// The binding factor of each signer, in the order of the sorted commitments.
+fn binding_factors(group_key: &[u8; 32], commitments: &[SigningCommitment], message: &[u8]) -> Vec<Scalar> {
+	let mut encoded = Vec::new();
+	for commitment in commitments {
+		encoded.extend_from_slice(identifier(commitment.index).as_bytes());
+		encoded.extend_from_slice(&commitment.hiding);
+		encoded.extend_from_slice(&commitment.binding);
+	}
+	let prefix = [
+		&group_key[..],
+		&hash(&[CONTEXT, b"msg", message])[..],
+		&hash(&[CONTEXT, b"com", &encoded])[..],
+	]
+	.concat();
+	commitments
+		.iter()
+		.map(|commitment| hash_to_scalar(&[CONTEXT, b"rho", &prefix, identifier(commitment.index).as_bytes()]))
+		.collect()
+}
+
+/// The commitment of the group to the nonces of all the signers, the `R` of the signature.
+fn group_commitment(commitments: &[SigningCommitment], factors: &[Scalar]) -> Result<EdwardsPoint, FrostError> {
+	commitments.iter().zip(factors).try_fold(EdwardsPoint::identity(), |sum, (commitment, factor)| {
+		Ok(sum + decompress(&commitment.hiding)? + decompress(&commitment.binding)? * factor)
+	})
+}
+
+/// The Lagrange coefficient of signer `index` among the signers `indices`.
+fn lagrange(index: u16, indices: &[u16]) -> Scalar {
+	let x = identifier(index);
+	let (numerator, denominator) = indices.iter().filter(|other| **other != index).fold(
+		(Scalar::ONE, Scalar::ONE),
+		|(numerator, denominator), other| {
+			let x_other = identifier(*other);
+			(numerator * x_other, denominator * (x_other - x))
+		},
+	);
+	numerator * denominator.invert()
+}
+
+/// The Ed25519 challenge of the signature.
+fn challenge(group_commitment: &EdwardsPoint, group_key: &[u8; 32], message: &[u8]) -> Scalar {
+	hash_to_scalar(&[group_commitment.compress().as_bytes(), group_key, message])
+}
+
+/// Round two: the share of `share` of the signature of `message`, given the commitments of all
+/// the signers, its own included.
+pub fn sign(
+	share: &KeyShare,
+	nonces: SigningNonces,
+	commitments: &[SigningCommitment],
+	message: &[u8],
+) -> Result<SignatureShare, FrostError> {
+	let commitments = sorted(commitments)?;
+	let position = commitments
+		.iter()
+		.position(|commitment| commitment.index == share.index)
+		.ok_or(FrostError::InvalidCommitments)?;
+	let factors = binding_factors(&share.group_key, &commitments, message);
+	let group_commitment = group_commitment(&commitments, &factors)?;
+	let indices: Vec<u16> = commitments.iter().map(|commitment| commitment.index).collect();
+	let lambda = lagrange(share.index, &indices);
+	let c = challenge(&group_commitment, &share.group_key, message);
+	let z = nonces.hiding + nonces.binding * factors[position] + lambda * share.secret * c;
+	Ok(SignatureShare {
+		index: share.index,
+		share: z.to_bytes(),
+	})
+}
+
+/// Combine the shares of every signer into the Ed25519 signature of `message` under `group_key`.
+pub fn aggregate(
+	group_key: &[u8; 32],
+	commitments: &[SigningCommitment],
+	message: &[u8],
+	shares: &[SignatureShare],
+) -> Result<[u8; 64], FrostError> {
+	let commitments = sorted(commitments)?;
+	if shares.len() != commitments.len()
+		|| !commitments.iter().all(|commitment| shares.iter().any(|share| share.index == commitment.index))
+	{
+		return Err(FrostError::InvalidShares);
+	}
+	let factors = binding_factors(group_key, &commitments, message);
+	let group_commitment = group_commitment(&commitments, &factors)?;
+	let z = shares
+		.iter()
+		.try_fold(Scalar::ZERO, |z, share| Option::<Scalar>::from(Scalar::from_canonical_bytes(share.share)).map(|share| z + share))
+		.ok_or(FrostError::InvalidShares)?;
+	let mut signature = [0u8; 64];
+	signature[..32].copy_from_slice(group_commitment.compress().as_bytes());
+	signature[32..].copy_from_slice(z.as_bytes());
+	Ok(signature)
+}
+
+/// Run both rounds with the `shares` of the signing friends, and aggregate their signature of
+/// `message`.
+pub fn threshold_sign<R: RngCore + CryptoRng>(
+	shares: &[KeyShare],
+	message: &[u8],
+	rng: &mut R,
+) -> Result<[u8; 64], FrostError> {
+	let group_key = shares.first().ok_or(FrostError::InvalidCommitments)?.group_key;
+	let (nonces, commitments): (Vec<_>, Vec<_>) = shares.iter().map(|share| commit(share, rng)).unzip();
+	let signature_shares = shares
+		.iter()
+		.zip(nonces)
+		.map(|(share, nonces)| sign(share, nonces, &commitments, message))
+		.collect::<Result<Vec<_>, _>>()?;
+	aggregate(&group_key, &commitments, message, &signature_shares)
+}
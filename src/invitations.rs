@@ -0,0 +1,176 @@
+//! # Invitations
+//!
+//! The owner of an account must hand each friend their own merkle proof ahead of time, without
+//! revealing the other friends. This module builds, for every friend, an [`Invitation`] holding
+//! their proof, the account to recover and the recovery parameters, sealed to that friend's key.
+//!
+//! Sealing needs no extra key from the friends: sr25519 public keys are Ristretto points, so the
+//! owner runs Diffie-Hellman between a one-time key and the friend's account key. The shared
+//! secret keys a ChaCha20-Poly1305 cipher, and only the friend's sr25519 secret can open the
+//! resulting [`InvitationBundle`].
+
+use chacha20poly1305::{
+	aead::{Aead, KeyInit},
+	ChaCha20Poly1305, Key, Nonce,
+};
+use codec::{Decode, Encode};
+use curve25519_dalek::{
+	ristretto::{CompressedRistretto, RistrettoPoint},
+	scalar::Scalar,
+};
+use rand::RngCore;
+use sp_core::{hashing::blake2_256, sr25519, Pair};
+
+use crate::{lsag, FriendsTree, MerkleProof};
+
+/// Domain separation of the invitation encryption keys.
+const INVITATION_CONTEXT: &[u8] = b"secret-social-recovery/invitation";
+
+/// The nonce of every bundle.
+///
+/// A fixed nonce is safe because no two bundles share a cipher key: `seal` draws a fresh one-time
+/// secret for each bundle, and the key is derived from the one-time public key. Sealing must
+/// never reuse a one-time secret.
+const NONCE: [u8; 12] = [0; 12];
+
+/// What a friend needs to know to later approve a recovery
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Debug)]
+pub struct Invitation<AccountId, BlockNumber> {
+	/// The account the friend may be asked to help recover.
+	pub lost: AccountId,
+	/// The index of the friend's group in the recovery config.
+	pub group: u16,
+	/// The number of friends of the group needed to recover the account.
+	pub threshold: u16,
+	/// The delay between initiating and claiming a recovery.
+	pub delay_period: BlockNumber,
+	/// The merkle proof of the friend in the group's merkle tree.
+	pub proof: MerkleProof<AccountId>,
+}
+
+/// An invitation sealed to a single friend
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Debug)]
+pub struct InvitationBundle {
+	/// The one-time public key of the owner.
+	pub ephemeral_public: [u8; 32],
+	/// The encrypted invitation.
+	pub ciphertext: Vec<u8>,
+}
+
+/// The reasons an invitation can't be sealed or opened
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum InvitationError {
+	/// The account isn't a valid sr25519 public key.
+	InvalidPublicKey,
+	/// The secret key can't be used for Diffie-Hellman.
+	InvalidSecretKey,
+	/// The friend isn't one of the accounts of the tree.
+	NotAFriend,
+	/// The bundle isn't meant for this friend, or was tampered with.
+	DecryptionFailed,
+	/// The decrypted invitation couldn't be decoded.
+	Malformed,
+}
+
+impl std::fmt::Display for InvitationError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			InvitationError::InvalidPublicKey => write!(f, "Invalid sr25519 public key"),
+			InvitationError::InvalidSecretKey => write!(f, "Invalid sr25519 secret key"),
+			InvitationError::NotAFriend => write!(f, "The account isn't one of the friends"),
+			InvitationError::DecryptionFailed => write!(f, "The invitation can't be opened with this key"),
+			InvitationError::Malformed => write!(f, "Malformed invitation"),
+		}
+	}
+}
+
+impl std::error::Error for InvitationError {}
+
+/// The cipher shared by the holders of `secret` and `public`, bound to the one-time key.
+fn cipher(
+	secret: &Scalar,
+	public: &RistrettoPoint,
+	ephemeral_public: &[u8; 32],
+	friend: &[u8; 32],
+) -> ChaCha20Poly1305 {
+	let shared = (secret * public).compress();
+	let key = blake2_256(&(INVITATION_CONTEXT, shared.as_bytes(), ephemeral_public, friend).encode());
+	ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+fn decompress(public: &sr25519::Public) -> Result<RistrettoPoint, InvitationError> {
+	CompressedRistretto(public.0).decompress().ok_or(InvitationError::InvalidPublicKey)
+}
+
+/// Seal `invitation` so that only the holder of the secret key of `friend` can open it.
+///
+/// Each call seals under a new one-time key, so sealing the same invitation twice gives unrelated
+/// bundles.
+pub fn seal<AccountId: Encode, BlockNumber: Encode>(
+	friend: &sr25519::Public,
+	invitation: &Invitation<AccountId, BlockNumber>,
+) -> Result<InvitationBundle, InvitationError> {
+	let friend_point = decompress(friend)?;
+	let mut wide = [0u8; 64];
+	rand::rngs::OsRng.fill_bytes(&mut wide);
+	let ephemeral_secret = Scalar::from_bytes_mod_order_wide(&wide);
+	let ephemeral_public = RistrettoPoint::mul_base(&ephemeral_secret).compress().to_bytes();
+	let ciphertext = cipher(&ephemeral_secret, &friend_point, &ephemeral_public, &friend.0)
+		.encrypt(Nonce::from_slice(&NONCE), &invitation.encode()[..])
+		.map_err(|_| InvitationError::Malformed)?;
+	Ok(InvitationBundle {
+		ephemeral_public,
+		ciphertext,
+	})
+}
+
+/// Open a bundle sealed to the public key of `friend`.
+pub fn open<AccountId: Decode, BlockNumber: Decode>(
+	friend: &sr25519::Pair,
+	bundle: &InvitationBundle,
+) -> Result<Invitation<AccountId, BlockNumber>, InvitationError> {
+	let secret = lsag::secret_scalar(friend).ok_or(InvitationError::InvalidSecretKey)?;
+	let ephemeral_point = CompressedRistretto(bundle.ephemeral_public)
+		.decompress()
+		.ok_or(InvitationError::DecryptionFailed)?;
+	let plaintext = cipher(&secret, &ephemeral_point, &bundle.ephemeral_public, &friend.public().0)
+		.decrypt(Nonce::from_slice(&NONCE), &bundle.ciphertext[..])
+		.map_err(|_| InvitationError::DecryptionFailed)?;
+	Invitation::decode(&mut &plaintext[..]).map_err(|_| InvitationError::Malformed)
+}
+
+/// Seal an invitation to each friend of the merkle `tree` of a group.
+///
+/// The tree is built by the owner, with `FriendsTree::new` or, to hide the number of friends,
+/// `FriendsTree::padded`. Returns the merkle root to commit with `create_recovery` or
+/// `create_group_recovery`, and the bundles in the order of `tree.leaves()`, to be handed to each
+/// friend privately.
+pub fn invite_friends<AccountId, BlockNumber>(
+	lost: AccountId,
+	group: u16,
+	threshold: u16,
+	delay_period: BlockNumber,
+	tree: &FriendsTree<AccountId>,
+) -> Result<(Vec<u8>, Vec<InvitationBundle>), InvitationError>
+where
+	AccountId: Encode + Clone + PartialEq,
+	BlockNumber: Encode + Clone,
+{
+	let bundles = tree
+		.leaves()
+		.iter()
+		.map(|friend| {
+			let raw: [u8; 32] = friend.encode().try_into().map_err(|_| InvitationError::InvalidPublicKey)?;
+			let public = sr25519::Public::from_raw(raw);
+			let invitation = Invitation {
+				lost: lost.clone(),
+				group,
+				threshold,
+				delay_period: delay_period.clone(),
+				proof: tree.proof_for(friend).ok_or(InvitationError::NotAFriend)?,
+			};
+			seal(&public, &invitation)
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+	Ok((tree.root(), bundles))
+}
@@ -12,108 +12,633 @@
 // Ensure we're `no_std` when compiling for Wasm.
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use codec::{Decode, Encode};
-use frame_support::{
-	decl_error, decl_event, decl_module, decl_storage, ensure,
-	weights::{FunctionOf, GetDispatchInfo, SimpleDispatchInfo},
-	Parameter, RuntimeDebug,
-};
-use sp_runtime::{
-	traits::{CheckedAdd, Dispatchable},
-	DispatchResult,
-};
-use sp_std::convert::TryInto;
-use system::{self as system, ensure_root, ensure_signed};
-
-use merkle::Proof;
-use sp_core::{sr25519, Pair};
+use codec::{Decode, Encode, EncodeLike};
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use frame_support::{traits::ConstU32, BoundedVec, RuntimeDebug};
+use scale_info::TypeInfo;
+use sp_std::{prelude::*, vec};
+
+use sp_core::{ed25519, sr25519};
 
 #[cfg(test)]
 mod mock;
 
+mod benchmarking;
+pub mod bls;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "std")]
+pub mod frost;
+#[cfg(feature = "std")]
+pub mod invitations;
+pub mod lsag;
+pub mod migrations;
+pub mod package;
+pub mod payment;
+pub mod proof;
+pub mod weights;
+
 #[cfg(test)]
 mod tests;
 
+pub use pallet::*;
+pub use payment::ChargeRecoveredAccount;
+pub use proof::{FriendsTree, LeafCountProof, MerkleProof};
+pub use weights::WeightInfo;
+
 pub type Signature = sr25519::Signature;
 
-// #[derive(Encode, Decode)]
-// pub type MerkleProof = Proof<Vec<u8>>;
+/// The length of a merkle root, as produced by the SHA256 merkle tree of the friends.
+pub const MERKLE_ROOT_LENGTH: usize = 32;
+
+/// The domain of the owner signatures of blinded recovery configurations.
+const BLINDED_RECOVERY_CONTEXT: &[u8] = b"secret-social-recovery/blinded";
+
+/// The domain of the storage keys of blinded recovery configurations.
+const BLINDED_STORAGE_CONTEXT: &[u8] = b"secret-social-recovery/blinded-storage";
+
+/// The domain of the signatures of friends approving a recovery.
+const APPROVAL_CONTEXT: &[u8] = b"secret-social-recovery/approve";
+
+/// The domain of the commitments to friend approvals.
+const APPROVAL_COMMITMENT_CONTEXT: &[u8] = b"secret-social-recovery/commitment";
+
+/// The domain of the threshold signatures claiming a recovery.
+const THRESHOLD_RECOVERY_CONTEXT: &[u8] = b"secret-social-recovery/threshold";
+
+/// The domain of the BLS signatures approving a recovery.
+const BLS_APPROVAL_CONTEXT: &[u8] = b"secret-social-recovery/bls-approval";
+
+/// The domain of the ring signatures approving a recovery.
+const RING_APPROVAL_CONTEXT: &[u8] = b"secret-social-recovery/ring-approval";
+
+/// The most withdrawal checks the search for the transferable balance of a swept account makes.
+/// What it doesn't narrow down stays in the account, for the next sweep.
+pub const MAX_SWEEP_PROBES: u32 = 64;
+
+/// The most bytes an approval bitmap holds, enough for the positions of a tree of 16384 friends,
+/// which bounds `MaxFriends`.
+pub const MAX_APPROVAL_BITMAP_LEN: u32 = 4096;
+
+/// A group of friends committed to by a single merkle root
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RecoveryGroup {
+	/// The merkle root of the friends in this group.
+	friends_merkle_root: Vec<u8>,
+	/// The number of approving friends needed for this group to be satisfied.
+	threshold: u16,
+	/// The depth the friends merkle tree is padded to, if it is, see `FriendsTree::padded`.
+	/// Every proof of the group must then have exactly this depth.
+	padded_depth: Option<u32>,
+}
+
+impl RecoveryGroup {
+	pub fn new(friends_merkle_root: Vec<u8>, threshold: u16) -> Self {
+		RecoveryGroup {
+			friends_merkle_root,
+			threshold,
+			padded_depth: None,
+		}
+	}
+
+	/// A group whose friends merkle tree is padded to `depth`, so that the proofs of its friends
+	/// don't tell how many they are.
+	pub fn padded(friends_merkle_root: Vec<u8>, threshold: u16, depth: u32) -> Self {
+		RecoveryGroup {
+			friends_merkle_root,
+			threshold,
+			padded_depth: Some(depth),
+		}
+	}
+
+	/// The merkle root of the friends in this group.
+	pub fn friends_merkle_root(&self) -> &[u8] {
+		&self.friends_merkle_root
+	}
+
+	/// The number of approving friends needed for this group.
+	pub fn threshold(&self) -> u16 {
+		self.threshold
+	}
+
+	/// The depth the friends merkle tree is padded to, if any.
+	pub fn padded_depth(&self) -> Option<u32> {
+		self.padded_depth
+	}
+}
+
+/// How the groups of a recovery configuration are combined
+#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum GroupPolicy {
+	/// Every group must reach its threshold.
+	All,
+	/// At least this many groups must reach their threshold.
+	AnyOf(u16),
+}
+
+impl Default for GroupPolicy {
+	fn default() -> Self {
+		GroupPolicy::All
+	}
+}
+
+/// The number of friends of a group, declared by the owner when creating a recovery so that its
+/// threshold can be checked to be reachable
+///
+/// The count isn't stored. It can come with a proof that the group's tree has that many leaves,
+/// which only reveals hashes, see `LeafCountProof`. The leaves of a padded tree include its
+/// dummies, so a padded group can only be proven to have `2^depth` friends.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct FriendCount {
+	/// The number of friends, i.e. of leaves of the group's tree.
+	pub friends: u32,
+	/// Optionally, the proof that the group's tree has `friends` leaves.
+	pub proof: Option<LeafCountProof>,
+}
+
+impl FriendCount {
+	pub fn new(friends: u32) -> Self {
+		FriendCount { friends, proof: None }
+	}
+
+	/// A count proven by `proof`, see `FriendsTree::leaf_count_proof`.
+	pub fn proven(friends: u32, proof: LeafCountProof) -> Self {
+		FriendCount {
+			friends,
+			proof: Some(proof),
+		}
+	}
+}
 
-// pub type MerkleProof = Vec<u8>;
-/// The pallet's configuration trait.
-pub trait Trait: system::Trait {
-	/// The overarching event type.
-	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+/// The key friends jointly sign recoveries with, instead of approving one by one
+///
+/// The friends share an Ed25519 key with FROST, see the `frost` module, and any `threshold` of the
+/// `signers` produce a plain Ed25519 signature under `group_key`. The chain only checks that
+/// signature, the threshold and the number of signers are kept for wallets.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ThresholdKey {
+	/// The Ed25519 group public key.
+	pub group_key: [u8; 32],
+	/// The number of friends needed to sign.
+	pub threshold: u16,
+	/// The number of friends holding a share of the key.
+	pub signers: u16,
+}
 
-	/// The overarching call type.
-	type Call: Parameter + Dispatchable<Origin = Self::Origin> + GetDispatchInfo;
+/// A friends merkle root a group had before `rotate_friends_merkle_root` replaced it
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct PreviousRoot<BlockNumber> {
+	/// The index of the group in the recovery config.
+	pub group: u16,
+	/// The replaced merkle root.
+	pub friends_merkle_root: Vec<u8>,
+	/// The block the root was replaced in, i.e. the one its successor was activated in. Proofs
+	/// against it are accepted for `RootGracePeriod` blocks from there.
+	pub replaced: BlockNumber,
 }
 
 /// Modified version of RecoveryConfig
-#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct RecoveryConfig<BlockNumber> {
 	/// The minimum number of blocks since the start of the recovery process before the account
 	/// can be recovered.
 	delay_period: BlockNumber,
-	/// The list of friends which can help recover an account. Always sorted.
-	friends_merkle_root: Vec<u8>,
-	/// The number of approving friends needed to recover an account.
-	threshold: u16,
+	/// The groups of friends which can help recover an account.
+	groups: Vec<RecoveryGroup>,
+	/// How the groups are combined to decide whether the account can be recovered.
+	policy: GroupPolicy,
+	/// The merkle root of the accounts allowed to rescue this account, if the owner committed
+	/// to them. Any account can initiate a recovery otherwise.
+	rescuers_merkle_root: Option<Vec<u8>>,
+	/// Whether the account is swept into the rescuer when the recovery is claimed.
+	auto_sweep: bool,
+	/// The key of the friends, if they can also claim the recovery with a single threshold
+	/// signature.
+	threshold_key: Option<ThresholdKey>,
+	/// The last `MaxPreviousRoots` roots replaced in any group, oldest first.
+	previous_roots: Vec<PreviousRoot<BlockNumber>>,
+}
+
+impl<BlockNumber> RecoveryConfig<BlockNumber> {
+	/// The minimum number of blocks between initiating and claiming a recovery.
+	pub fn delay_period(&self) -> &BlockNumber {
+		&self.delay_period
+	}
+
+	/// The groups of friends which can help recover the account.
+	pub fn groups(&self) -> &[RecoveryGroup] {
+		&self.groups
+	}
+
+	/// How the groups are combined.
+	pub fn policy(&self) -> GroupPolicy {
+		self.policy
+	}
+
+	/// The merkle root of the committed rescuers, if any.
+	pub fn rescuers_merkle_root(&self) -> Option<&[u8]> {
+		self.rescuers_merkle_root.as_ref().map(|root| &root[..])
+	}
+
+	/// Whether the account is swept into the rescuer on claim.
+	pub fn auto_sweep(&self) -> bool {
+		self.auto_sweep
+	}
+
+	/// The threshold key of the friends, if any.
+	pub fn threshold_key(&self) -> Option<&ThresholdKey> {
+		self.threshold_key.as_ref()
+	}
+
+	/// The last roots replaced in the groups, oldest first.
+	pub fn previous_roots(&self) -> &[PreviousRoot<BlockNumber>] {
+		&self.previous_roots
+	}
+
+	/// Count the approvals of each group of this configuration.
+	fn approvals_per_group<B>(&self, active_recovery: &ActiveRecovery<B>) -> Vec<u16> {
+		(0..self.groups.len())
+			.map(|group| active_recovery.group_approvals(group))
+			.collect()
+	}
+
+	/// Check whether the approvals of `active_recovery` satisfy the policy of this configuration.
+	fn is_satisfied_by<B>(&self, active_recovery: &ActiveRecovery<B>) -> bool {
+		let satisfied_groups = self
+			.approvals_per_group(active_recovery)
+			.iter()
+			.zip(self.groups.iter())
+			.filter(|(approved, group)| **approved >= group.threshold)
+			.count();
+		match self.policy {
+			GroupPolicy::All => satisfied_groups == self.groups.len(),
+			GroupPolicy::AnyOf(required) => satisfied_groups >= required as usize,
+		}
+	}
+}
+
+/// The set of friends of a group which have approved a recovery, keyed by the position of their
+/// leaf in the group's friends merkle tree (see `MerkleProof::position`), of at most
+/// `MAX_APPROVAL_BITMAP_LEN` bytes
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug, TypeInfo)]
+pub struct ApprovalBitmap(BoundedVec<u8, ConstU32<MAX_APPROVAL_BITMAP_LEN>>);
+
+impl ApprovalBitmap {
+	/// Whether the friend at `position` has approved.
+	pub fn contains(&self, position: u32) -> bool {
+		self.0
+			.get((position / 8) as usize)
+			.map_or(false, |byte| byte & (1 << (position % 8)) != 0)
+	}
+
+	/// Record the approval of the friend at `position`, growing the bitmap as needed. Returns
+	/// false if `position` is beyond the bitmap's bound.
+	pub fn insert(&mut self, position: u32) -> bool {
+		let index = (position / 8) as usize;
+		if index >= MAX_APPROVAL_BITMAP_LEN as usize {
+			return false;
+		}
+		if self.0.len() <= index {
+			self.0.bounded_resize(index + 1, 0);
+		}
+		self.0[index] |= 1 << (position % 8);
+		true
+	}
+
+	/// The number of friends which have approved.
+	pub fn count(&self) -> u16 {
+		self.0.iter().map(|byte| byte.count_ones() as u16).fold(0, u16::saturating_add)
+	}
+}
+
+/// A recovery configuration stored under a blinded key, along with the owner's authorization
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct BlindedRecovery<BlockNumber> {
+	/// The recovery configuration, revealed as is.
+	config: RecoveryConfig<BlockNumber>,
+	/// The owner's signature on the `blinded_recovery_payload` of the key and configuration,
+	/// checked against the caller when stored and against the account once it is revealed.
+	owner_signature: Signature,
+}
+
+impl<BlockNumber> BlindedRecovery<BlockNumber> {
+	/// The recovery configuration.
+	pub fn config(&self) -> &RecoveryConfig<BlockNumber> {
+		&self.config
+	}
+}
+
+/// A friend approval hidden behind a commitment until it is revealed, see `commit_approval`
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct ApprovalOpening<AccountId> {
+	/// The index of the friend's group in the recovery config.
+	pub group: u16,
+	/// The friend's signature on the approval payload.
+	pub signature: Signature,
+	/// The merkle proof of the friend in its group.
+	pub proof: MerkleProof<AccountId>,
+	/// A random salt, so that the commitment can't be checked against guessed approvals.
+	pub salt: [u8; 32],
 }
 
 /// Modified version of ActiveRecovery
-#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
-pub struct ActiveRecovery<BlockNumber, AccountId> {
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug, TypeInfo)]
+pub struct ActiveRecovery<BlockNumber> {
 	/// The block number when the recovery process started.
 	created: BlockNumber,
-	/// The friends which have approved so far. Always sorted.
-	approved_friends: Vec<AccountId>,
+	/// The friends which have approved so far, one bitmap per group. Groups nobody approved in
+	/// yet may be missing from the end.
+	approvals: Vec<ApprovalBitmap>,
+	/// The key images of the friends which have approved anonymously so far, per group, see
+	/// `approve_recovery_anonymously`. Groups nobody approved in yet may be missing from the end.
+	key_images: Vec<Vec<lsag::KeyImage>>,
+	/// The friends merkle root the proofs or rings of each group's approvals were checked
+	/// against, as the positions of different trees can't be compared. The approvals only count
+	/// while their root is accepted. Groups nobody approved in yet may be missing from the end, or
+	/// have an empty root.
+	approval_roots: Vec<Vec<u8>>,
 }
 
-// This pallet's storage items.
-decl_storage! {
-	trait Store for Module<T: Trait> as SecretSocialRecovery {
-		/// The set of recoverable accounts and their recovery configuration.
-		pub Recoverable get(fn recovery_config):
-		map hasher(twox_64_concat) T::AccountId => Option<RecoveryConfig<T::BlockNumber>>;
+impl<BlockNumber> ActiveRecovery<BlockNumber> {
+	/// The total number of approvals, over all groups.
+	pub fn total_approvals(&self) -> u32 {
+		(0..self.approvals.len().max(self.key_images.len()))
+			.map(|group| self.group_approvals(group) as u32)
+			.sum()
+	}
 
+	/// The number of approvals of `group`, anonymous or not.
+	fn group_approvals(&self, group: usize) -> u16 {
+		let approvals = self.approvals.get(group).map_or(0, ApprovalBitmap::count);
+		approvals.saturating_add(self.key_images.get(group).map_or(0, |images| images.len() as u16))
+	}
+}
 
-		/// Active recovery attempts.
-		///
-		/// First account is the account to be recovered, and the second account
-		/// is the user trying to recover the account.
-		pub ActiveRecoveries get(fn active_recovery):
-			double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) T::AccountId =>
-			Option<ActiveRecovery<T::BlockNumber, T::AccountId>>;
+/// The progress of an active recovery, as exposed through the runtime API
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RecoveryStatus<BlockNumber> {
+	/// The number of approvals so far, per group.
+	pub approvals: Vec<u16>,
+	/// The number of approvals needed, per group.
+	pub thresholds: Vec<u16>,
+	/// Whether the approvals satisfy the group policy.
+	pub threshold_reached: bool,
+	/// The block number from which the recovery can be claimed.
+	pub claimable_at: BlockNumber,
+}
 
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::{
+		dispatch::{extract_actual_weight, GetDispatchInfo, PostDispatchInfo, WithPostDispatchInfo},
+		pallet_prelude::*,
+		storage::{with_transaction, TransactionOutcome},
+		traits::{Currency, ExistenceRequirement, ReservableCurrency, WithdrawReasons},
+	};
+	use frame_system::{ensure_root, ensure_signed, pallet_prelude::*, RawOrigin};
+	use sp_runtime::{
+		traits::{BadOrigin, CheckedAdd, Dispatchable, One, Saturating, Zero},
+		transaction_validity::{TransactionLongevity, TransactionPriority},
+	};
 
-		/// The list of allowed proxy accounts.
-		///
-		/// Map from the user who can access it to the recovered account.
-		pub Proxy get(fn proxy):
-			map hasher(blake2_128_concat) T::AccountId => Option<T::AccountId>;
+	pub(crate) type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	/// The version of the layout of the pallet's storage, see `migrations`.
+	pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(11);
+
+	#[pallet::pallet]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	#[pallet::without_storage_info]
+	pub struct Pallet<T>(_);
+
+	/// The pallet's configuration trait.
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The overarching call type.
+		type RuntimeCall: Parameter
+			+ Dispatchable<RuntimeOrigin = Self::RuntimeOrigin, PostInfo = PostDispatchInfo>
+			+ GetDispatchInfo
+			+ From<Call<Self>>;
+
+		/// The currency swept out of recovered accounts.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// The maximum number of friend groups a recovery configuration can have.
+		#[pallet::constant]
+		type MaxGroups: Get<u16>;
+
+		/// The maximum number of approvals an active recovery can hold.
+		#[pallet::constant]
+		type MaxApprovals: Get<u16>;
+
+		/// The maximum number of friends in a group, at most 16384. Merkle proofs deeper than a
+		/// tree of `MaxFriends` leaves are rejected, which bounds the approval bitmaps.
+		#[pallet::constant]
+		type MaxFriends: Get<u32>;
+
+		/// The number of blocks a commitment to an approval must wait before it can be revealed.
+		#[pallet::constant]
+		type RevealDelay: Get<BlockNumberFor<Self>>;
+
+		/// The deposit reserved from the committer for each pending commitment to an approval,
+		/// returned when it is revealed or the recovery is claimed.
+		#[pallet::constant]
+		type CommitmentDeposit: Get<BalanceOf<Self>>;
 
+		/// The deposit reserved from the owner of a blinded recovery configuration, returned when
+		/// `initiate_blinded_recovery` reveals it.
+		#[pallet::constant]
+		type BlindedRecoveryDeposit: Get<BalanceOf<Self>>;
+
+		/// The number of replaced friends merkle roots a recovery configuration keeps, over all
+		/// its groups.
+		#[pallet::constant]
+		type MaxPreviousRoots: Get<u32>;
+
+		/// The number of blocks proofs against a replaced friends merkle root are still accepted
+		/// for, so that rotating a root doesn't strand the recoveries in progress.
+		#[pallet::constant]
+		type RootGracePeriod: Get<BlockNumberFor<Self>>;
+
+		/// The priority of unsigned friend approvals in the transaction pool.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// The number of blocks an unsigned friend approval stays valid in the transaction pool.
+		#[pallet::constant]
+		type UnsignedLongevity: Get<TransactionLongevity>;
+
+		/// Weight information for the extrinsics of this pallet.
+		type WeightInfo: WeightInfo;
 	}
-}
 
-// The pallet's events
-decl_event!(
-	pub enum Event<T>
-	where
-		AccountId = <T as system::Trait>::AccountId,
-	{
+	/// The set of recoverable accounts and their recovery configuration.
+	#[pallet::storage]
+	pub type Recoverable<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, RecoveryConfig<BlockNumberFor<T>>, OptionQuery>;
+
+	/// The recovery configurations stored under a blinded key, see `create_blinded_recovery`.
+	///
+	/// The keys are the `blinded_storage_key` of the owner and its blinded key, a hash of the
+	/// account and a secret salt, so the accounts can't be listed. Configurations stored before
+	/// that are under the blinded key itself.
+	#[pallet::storage]
+	pub type BlindedRecoverable<T: Config> =
+		StorageMap<_, Blake2_128Concat, [u8; 32], BlindedRecovery<BlockNumberFor<T>>, OptionQuery>;
+
+	/// The deposits reserved from the owners of the blinded recovery configurations, by storage
+	/// key. Configurations blinded before deposits were taken have none.
+	#[pallet::storage]
+	pub type BlindedDeposits<T: Config> = StorageMap<_, Blake2_128Concat, [u8; 32], BalanceOf<T>, ValueQuery>;
+
+	/// The deposits this pallet holds from each account, for its pending commitments and blinded
+	/// recovery configurations together.
+	#[pallet::storage]
+	pub type Deposits<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+	/// The deposits of the blinded recovery configurations stored before `Deposits` tracked them,
+	/// by blinded key. They aren't swept, and are unreserved as they are on reveal.
+	#[pallet::storage]
+	pub type LegacyBlindedDeposits<T: Config> = StorageMap<_, Blake2_128Concat, [u8; 32], BalanceOf<T>, ValueQuery>;
+
+	/// Active recovery attempts.
+	///
+	/// First account is the account to be recovered, and the second account
+	/// is the user trying to recover the account.
+	#[pallet::storage]
+	pub type ActiveRecoveries<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::AccountId,
+		ActiveRecovery<BlockNumberFor<T>>,
+		OptionQuery,
+	>;
+
+	/// The pending commitments to friend approvals of active recoveries, with the block they were
+	/// made at, see `commit_approval`.
+	///
+	/// Keyed by the lost account, the rescuer, then the committer, who reserved a deposit for each.
+	#[pallet::storage]
+	pub type ApprovalCommitments<T: Config> = StorageNMap<
+		_,
+		(
+			NMapKey<Blake2_128Concat, T::AccountId>,
+			NMapKey<Blake2_128Concat, T::AccountId>,
+			NMapKey<Blake2_128Concat, T::AccountId>,
+		),
+		Vec<([u8; 32], BlockNumberFor<T>)>,
+		ValueQuery,
+	>;
+
+	/// The list of allowed proxy accounts.
+	///
+	/// Map from the user who can access it to the recovered account.
+	#[pallet::storage]
+	pub type Proxy<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, T::AccountId, OptionQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// The accounts set up for a single group recovery, as
+		/// `(account, friends_merkle_root, threshold, delay_period)`.
+		pub recoverable: Vec<(T::AccountId, Vec<u8>, u16, BlockNumberFor<T>)>,
+		/// The already recovered accounts, as `(rescuer, lost)`.
+		pub proxies: Vec<(T::AccountId, T::AccountId)>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			for (who, friends_merkle_root, threshold, delay_period) in &self.recoverable {
+				assert!(*threshold >= 1, "Recovery threshold must be greater than zero");
+				assert!(*threshold <= T::MaxApprovals::get(), "Recovery threshold must not exceed MaxApprovals");
+				assert!(
+					friends_merkle_root.len() == MERKLE_ROOT_LENGTH,
+					"Friends merkle root must be {} bytes long",
+					MERKLE_ROOT_LENGTH,
+				);
+				assert!(!<Recoverable<T>>::contains_key(who), "Duplicate recoverable account in genesis");
+				let recovery_config = RecoveryConfig {
+					delay_period: *delay_period,
+					groups: vec![RecoveryGroup::new(friends_merkle_root.clone(), *threshold)],
+					policy: GroupPolicy::All,
+					rescuers_merkle_root: None,
+					auto_sweep: false,
+					threshold_key: None,
+					previous_roots: vec![],
+				};
+				<Recoverable<T>>::insert(who, recovery_config);
+			}
+			for (rescuer, lost) in &self.proxies {
+				assert!(!<Proxy<T>>::contains_key(rescuer), "Duplicate proxy rescuer in genesis");
+				<Proxy<T>>::insert(rescuer, lost);
+			}
+			STORAGE_VERSION.put::<Pallet<T>>();
+		}
+	}
+
+	// The pallet's events
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
 		/// A recovery process has been set up for an account
-		RecoveryCreated(AccountId),
+		RecoveryCreated(T::AccountId),
 		/// A recovery process has been initiated for account_1 by account_2
-		RecoveryInitiated(AccountId, AccountId),
-		AccountRecovered(AccountId, AccountId),
-		ApprovedRecovery(AccountId, AccountId, AccountId),
+		RecoveryInitiated(T::AccountId, T::AccountId),
+		AccountRecovered(T::AccountId, T::AccountId),
+		ApprovedRecovery(T::AccountId, T::AccountId, T::AccountId),
+		/// A recovered account has been swept, as (lost, destination, transferred, unreserved,
+		/// left behind because of locks or reserves)
+		AccountSwept(T::AccountId, T::AccountId, BalanceOf<T>, BalanceOf<T>, BalanceOf<T>),
+		/// A recovered account couldn't be swept into its rescuer on claim, which can still sweep
+		/// it with `sweep_recovered`, as (lost, rescuer, error)
+		SweepFailed(T::AccountId, T::AccountId, DispatchError),
+		/// The owner of an account has set whether it is swept on claim
+		AutoSweepSet(T::AccountId, bool),
+		/// The threshold key of the friends of an account has been set, or removed
+		ThresholdKeySet(T::AccountId, Option<[u8; 32]>),
+		/// Friends of a BLS group approved the recovery of account_1 by account_2 together, as
+		/// (lost, rescuer, number of friends)
+		AggregatedApproval(T::AccountId, T::AccountId, u32),
+		/// A friend of a group approved the recovery of account_1 by account_2 anonymously, as
+		/// (lost, rescuer, group)
+		AnonymousApproval(T::AccountId, T::AccountId, u16),
+		/// The owner of an account replaced the friends merkle root of one of its groups, as
+		/// (account, group)
+		FriendsMerkleRootRotated(T::AccountId, u16),
+		/// A recovery configuration has been stored under a blinded key
+		BlindedRecoveryCreated([u8; 32]),
+		/// The account of a blinded recovery configuration has been revealed
+		RecoveryRevealed(T::AccountId),
+		/// A commitment to an approval of the recovery of account_1 by account_2 has been made
+		ApprovalCommitted(T::AccountId, T::AccountId, [u8; 32]),
+		/// Committed approvals of the recovery of account_1 by account_2 have been revealed, as
+		/// (lost, rescuer, counted, rejected)
+		ApprovalsRevealed(T::AccountId, T::AccountId, u32, u32),
 	}
-);
 
-// The pallet's errors
-decl_error! {
-	pub enum Error for Module<T: Trait> {
+	// The pallet's errors
+	#[pallet::error]
+	pub enum Error<T> {
 		/// Value was None
 		NoneValue,
 		/// Value reached maximum and cannot be incremented further
@@ -143,147 +668,1414 @@ decl_error! {
 		// The recovery process is still within the delay period and can't be claim right now
 		DelayPeriod,
 		// The recovery process hasn't reach the required threshold
-		UnderThreshold
+		UnderThreshold,
+		/// A recovery configuration needs at least one group
+		NoGroups,
+		/// There are more groups than allowed by `MaxGroups`
+		TooManyGroups,
+		/// The group policy can't be satisfied by the given groups
+		InvalidPolicy,
+		/// The group doesn't exist in the recovery configuration
+		InvalidGroup,
+		/// The owner committed to a set of rescuers but no rescuer proof was given
+		RescuerProofMissing,
+		/// The rescuer proof doesn't prove the caller is one of the committed rescuers
+		RescuerNotAllowed,
+		/// The thresholds add up to more than `MaxApprovals`
+		ThresholdTooHigh,
+		/// The active recovery already holds `MaxApprovals` approvals
+		TooManyApprovals,
+		/// The merkle proof is deeper than a tree of `MaxFriends` friends
+		ProofTooDeep,
+		/// The owner's signature of the blinded recovery configuration is invalid
+		OwnerSignatureInvalid,
+		/// The padded depth of a group is deeper than a tree of `MaxFriends` friends
+		InvalidPaddedDepth,
+		/// The merkle proof doesn't have the depth the group's tree is padded to
+		ProofDepthMismatch,
+		/// The same commitment to an approval is already pending
+		AlreadyCommitted,
+		/// The active recovery already holds `MaxApprovals` pending commitments
+		TooManyCommitments,
+		/// The threshold doesn't lie between one and the number of signers
+		InvalidThresholdKey,
+		/// The recovery config has no threshold key
+		NoThresholdKey,
+		/// The threshold signature isn't valid for the recovery
+		ThresholdSignatureInvalid,
+		/// The ring has more accounts than `MaxFriends`
+		RingTooLarge,
+		/// The group's friends approve either anonymously or with merkle proofs, and did the
+		/// other way first
+		MixedApprovals,
+		/// There isn't exactly one friend count per group
+		FriendCountsMismatch,
+		/// The threshold of a group is above its number of friends, so it could never be reached
+		ThresholdAboveFriendCount,
+		/// The proof doesn't prove the group's tree has the declared number of friends
+		FriendCountProofInvalid,
+		/// The group's approvals were made against another of its roots, still accepted
+		MixedRoots,
 	}
-}
-
-// The pallet's dispatchable functions.
-decl_module! {
-	/// The module declaration.
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
-		// Initializing errors
-		// this includes information about your errors in the node's metadata.
-		// it is needed only if you are using errors in your pallet
-		type Error = Error<T>;
-
-		// Initializing events
-		// this is needed only if you are using events in your pallet
-		fn deposit_event() = default;
 
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn integrity_test() {
+			// The positions of a tree of depth `d` are below `2^(d + 1)`, one bit each
+			assert!(
+				1u64 << (Self::max_proof_depth() + 1) <= 8 * MAX_APPROVAL_BITMAP_LEN as u64,
+				"`MaxFriends` is above what the approval bitmaps can hold"
+			);
+		}
+	}
 
-		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
-		fn set_recovered(origin, lost: T::AccountId, rescuer: T::AccountId) {
+	// The pallet's dispatchable functions.
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::set_recovered())]
+		pub fn set_recovered(origin: OriginFor<T>, lost: T::AccountId, rescuer: T::AccountId) -> DispatchResult {
 			ensure_root(origin)?;
 			// Create the recovery storage item.
 			<Proxy<T>>::insert(&rescuer, &lost);
-			Self::deposit_event(RawEvent::AccountRecovered(lost, rescuer));
+			Self::deposit_event(Event::AccountRecovered(lost, rescuer));
+			Ok(())
 		}
 
-		#[weight = FunctionOf(
-			|args: (&T::AccountId, &Box<<T as Trait>::Call>)| args.1.get_dispatch_info().weight + 10_000,
-			|args: (&T::AccountId, &Box<<T as Trait>::Call>)| args.1.get_dispatch_info().class,
-			true
-		)]
-		fn as_recovered(origin,
+		/// Dispatch `call` as the recovered account `lost`.
+		///
+		/// Only the weight `call` actually used is charged on top of the proxy check.
+		#[pallet::call_index(1)]
+		#[pallet::weight({
+			let dispatch_info = call.get_dispatch_info();
+			(T::WeightInfo::as_recovered().saturating_add(dispatch_info.weight), dispatch_info.class)
+		})]
+		pub fn as_recovered(
+			origin: OriginFor<T>,
 			lost: T::AccountId,
-			call: Box<<T as Trait>::Call>
-		) -> DispatchResult {
-			let rescuer= ensure_signed(origin)?;
+			call: Box<<T as Config>::RuntimeCall>,
+		) -> DispatchResultWithPostInfo {
+			let rescuer = ensure_signed(origin)?;
 			// Check `who` is allowed to make a call on behalf of `account`
 			let target = Self::proxy(&rescuer).ok_or(Error::<T>::NotAllowed)?;
 			ensure!(&target == &lost, Error::<T>::NotAllowed);
-			call.dispatch(system::RawOrigin::Signed(lost).into())
+			let dispatch_info = call.get_dispatch_info();
+			let result = call.dispatch(RawOrigin::Signed(lost).into());
+			let actual_weight =
+				T::WeightInfo::as_recovered().saturating_add(extract_actual_weight(&result, &dispatch_info));
+			result
+				.map(|_| Some(actual_weight).into())
+				.map_err(|e| e.error.with_weight(actual_weight))
 		}
 
-
-		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
-		fn create_recovery(origin,
+		/// Set up a single group recovery, where `threshold` friends of the merkle tree rooted
+		/// at `friends_merkle_root` are needed to recover the account.
+		///
+		/// `friend_count` is the number of friends of the tree, which `threshold` can't exceed,
+		/// optionally proven with `FriendsTree::leaf_count_proof`.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::create_recovery())]
+		pub fn create_recovery(
+			origin: OriginFor<T>,
 			friends_merkle_root: Vec<u8>,
 			threshold: u16,
-			delay_period: T::BlockNumber
-		) {
+			delay_period: BlockNumberFor<T>,
+			friend_count: FriendCount,
+		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			// Check account is not already set up for recovery
-			ensure!(!<Recoverable<T>>::contains_key(&who), Error::<T>::AlreadyRecoverable);
-			// Check user input is valid
-			ensure!(threshold >= 1, Error::<T>::ZeroThreshold);
-
-			// Create the recovery configuration
-			let recovery_config = RecoveryConfig {
-				delay_period,
-				friends_merkle_root,
-				threshold,
-			};
-
-			// Create the recovery configuration storage item
-			<Recoverable<T>>::insert(&who, recovery_config);
+			let groups = vec![RecoveryGroup::new(friends_merkle_root, threshold)];
+			Self::check_friend_counts(&groups, &[friend_count])?;
+			Self::do_create_recovery(who, groups, GroupPolicy::All, delay_period, None)
+		}
 
-			Self::deposit_event(RawEvent::RecoveryCreated(who));
+		/// Set up a multi group recovery.
+		///
+		/// Parameters:
+		/// - `groups`: the friend groups, each with its own merkle root and threshold
+		/// - `policy`: whether all groups or only some of them must reach their threshold
+		/// - `delay_period`: the blocks to wait after initiating a recovery before it can be claimed
+		/// - `rescuers_merkle_root`: optionally, the merkle root of the accounts allowed to
+		/// 	initiate a recovery, so the rescue destinations are committed to privately
+		/// - `friend_counts`: the number of friends of each group, which its threshold can't
+		/// 	exceed, each optionally proven
+		///
+		/// # <weight>
+		/// Key: G(len of groups)
+		/// - one storage read to check the account isn't recoverable yet, O(1)
+		/// - one iteration over the groups to validate the thresholds, O(G)
+		/// - one storage write of the recovery config, Codec O(G)
+		/// - one event
+		/// # </weight>
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::create_group_recovery(groups.len() as u32))]
+		pub fn create_group_recovery(
+			origin: OriginFor<T>,
+			groups: Vec<RecoveryGroup>,
+			policy: GroupPolicy,
+			delay_period: BlockNumberFor<T>,
+			rescuers_merkle_root: Option<Vec<u8>>,
+			friend_counts: Vec<FriendCount>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::check_friend_counts(&groups, &friend_counts)?;
+			Self::do_create_recovery(who, groups, policy, delay_period, rescuers_merkle_root)
 		}
 
-		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
-		fn initiate_recovery(origin, lost: T::AccountId) {
+		/// Parameters:
+		/// - `lost`: the account you want to rescue
+		/// - `rescuer_proof`: a merkle proof that the caller is included in the rescuers merkle
+		/// 	tree, required only if the owner of `lost` committed to a `rescuers_merkle_root`
+		///
+		/// # <weight>
+		/// Key: D(depth of the rescuer proof)
+		/// - one storage read to get the recovery config, O(1)
+		/// - one verification to check the rescuer proof validity, O(D)
+		/// - one storage read and one storage write of the active recovery, O(1)
+		/// - one event
+		/// # </weight>
+		#[pallet::call_index(4)]
+		#[pallet::weight(T::WeightInfo::initiate_recovery(rescuer_proof.as_ref().map_or(0, MerkleProof::depth)))]
+		pub fn initiate_recovery(
+			origin: OriginFor<T>,
+			lost: T::AccountId,
+			rescuer_proof: Option<MerkleProof<T::AccountId>>,
+		) -> DispatchResult {
 			let rescuer = ensure_signed(origin)?;
 			// Check that the account is recoverable
-			ensure!(<Recoverable<T>>::contains_key(&lost), Error::<T>::NotRecoverable);
-			// Check that the recovery process has not already been started
-			ensure!(!<ActiveRecoveries<T>>::contains_key(&lost, &rescuer), Error::<T>::AlreadyStarted);
-			// Create an active recovery status
-			let recovery_status = ActiveRecovery {
-				created: <system::Module<T>>::block_number(),
-				approved_friends: vec![]
-			};
-			// Create the active recovery storage item
-			<ActiveRecoveries<T>>::insert(&lost, &rescuer, recovery_status);
-			Self::deposit_event(RawEvent::RecoveryInitiated(lost, rescuer));
+			let recovery_config = Self::recovery_config(&lost).ok_or(Error::<T>::NotRecoverable)?;
+			Self::do_initiate_recovery(lost, rescuer, recovery_config, rescuer_proof)
 		}
 
 		/// Parameters:
 		/// - `lost`: the account you want to rescue
 		/// - `rescuer`: the account used to rescue the lost account
-		/// - `sigature`: the signature of a friend that's included in the merkle tree, on the message
-		/// 	of the slice of `rescuer` account, mean that friend approving the recovery process
-		/// - `proof`: a merkle proof that prove the friend is truly included in the merkle tree
+		/// - `group`: the index of the group in the recovery config the friend belongs to
+		/// - `sigature`: the signature of a friend that's included in the merkle tree, on the
+		/// 	`approval_payload` of the chain, the recovery and the group, mean that friend approving
+		/// 	the recovery process
+		/// - `proof`: a merkle proof that prove the friend is truly included in the group's merkle tree
+		///
+		/// Any account can submit it, or nobody as an unsigned transaction, see `ValidateUnsigned`.
 		///
 		/// # <weight>
-		/// Key: F(len of friends commited to the merkle tree), A(len of friends already approved)
+		/// Key: D(depth of the merkle proof, log of friends commited to the merkle tree),
+		/// A(len of friends already approved, bounded by `MaxApprovals`)
 		/// - one verification to check the signature validity,
-		/// - one verification to check the merkle proof validity
+		/// - one verification to check the merkle proof validity, O(D)
 		/// - one storage read to get the recovery config, O(1),
 		/// - one storage read to get the active recovery process, O(1), Codec O(A)
-		/// - one binary search to confirm the friend's account is already approved, O(log(A))
+		/// - one bitmap lookup to confirm the friend's leaf hasn't approved yet, O(1)
 		/// - one event
+		///
+		/// Charged for `MaxApprovals` upfront, the approvals the recovery didn't hold are refunded.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
-		fn approve_recovery(origin, lost: T::AccountId, rescuer: T::AccountId, signature: Signature, proof: Proof<T::AccountId>) {
-			let _ = ensure_signed(origin);
-			// Check that the lost account is recoverable
-			ensure!(<Recoverable<T>>::contains_key(&lost), Error::<T>::NotRecoverable);
-			// todo better Error
-			let approver = proof.clone().value;
-			let approver_public: [u8;32] = approver.as_ref().try_into().expect("");
-			// Check that the friend's signature on resuer account is valid
-			ensure!(sr25519::Pair::verify(&signature, rescuer.clone(), &sr25519::Public(approver_public)), Error::<T>::SignatureInvalid);
-			// ensure!(sr25519::Pair::verify(&signature, rescuer.clone(), &sr25519::Public(public)), Error::<T>::SignatureInvalid);
+		#[pallet::call_index(5)]
+		#[pallet::weight(T::WeightInfo::approve_recovery(proof.depth(), T::MaxApprovals::get() as u32))]
+		pub fn approve_recovery(
+			origin: OriginFor<T>,
+			lost: T::AccountId,
+			rescuer: T::AccountId,
+			group: u16,
+			signature: Signature,
+			proof: MerkleProof<T::AccountId>,
+		) -> DispatchResultWithPostInfo {
+			// Anyone can submit an approval, or the friend can submit it unsigned
+			ensure_signed_or_none(origin)?;
+			let active_recovery = Self::approved_recovery(&lost, &rescuer, group, &signature, &proof)?;
+			let approvals = active_recovery.total_approvals();
+			<ActiveRecoveries<T>>::insert(&lost, &rescuer, active_recovery);
+			let actual_weight = T::WeightInfo::approve_recovery(proof.depth(), approvals.saturating_sub(1));
+			Self::deposit_event(Event::ApprovedRecovery(lost, rescuer, proof.value));
+			Ok(Some(actual_weight).into())
+		}
+
+		/// Claim the recovery of `lost` once its delay period is over and its approvals satisfy
+		/// the policy. Only the approvals against roots which are still accepted count.
+		///
+		/// # <weight>
+		/// Key: A(len of friends already approved, bounded by `MaxApprovals`), G(number of groups,
+		/// bounded by `MaxGroups`)
+		/// - one storage read to get the recovery config, O(1)
+		/// - one storage read to get the active recovery process, O(1), Codec O(A)
+		/// - one check of the root of each group's approvals against its accepted roots, O(G)
+		/// - one count of the approval bitmaps per group to check the policy, O(A)
+		/// - one storage write of the proxy and one of the rescuer's consumers
+		/// - one event
+		/// - the removal of the pending commitments of up to `MaxApprovals` committers, returning
+		/// 	their deposits, O(`MaxApprovals`)
+		/// - a sweep of the account into the rescuer, if the owner enabled `auto_sweep`
+		///
+		/// Charged for `MaxApprovals` approvals and committers and a sweep upfront, the approvals
+		/// the recovery didn't hold, the committers it didn't have and the sweep when `auto_sweep`
+		/// is off are refunded.
+		/// # </weight>
+		#[pallet::call_index(6)]
+		#[pallet::weight(
+			T::WeightInfo::claim_recovery(T::MaxApprovals::get() as u32)
+				.saturating_add(Self::max_claim_cleanup_weight())
+		)]
+		pub fn claim_recovery(origin: OriginFor<T>, lost: T::AccountId) -> DispatchResultWithPostInfo {
+			let rescuer = ensure_signed(origin)?;
+			let (recovery_config, active_recovery) = Self::claimable_recovery(&lost, &rescuer)?;
+			// Check the thresholds of the groups against the policy, counting only the approvals
+			// against roots which are still accepted
+			let current_approvals = Self::current_approvals(&recovery_config, active_recovery.clone());
+			ensure!(recovery_config.is_satisfied_by(&current_approvals), Error::<T>::UnderThreshold);
+			let claim_weight = T::WeightInfo::claim_recovery(active_recovery.total_approvals());
+			let actual_weight = claim_weight.saturating_add(Self::do_claim(lost, rescuer, &recovery_config)?);
+			Ok(Some(actual_weight).into())
+		}
+
+		/// Set the key the caller's friends can jointly sign a recovery with, or remove it.
+		///
+		/// Parameters:
+		/// - `threshold_key`: the FROST group key of the friends, with its threshold and number of
+		/// 	signers
+		///
+		/// Its holders then sign recoveries together, see `claim_with_threshold_signature`.
+		#[pallet::call_index(14)]
+		#[pallet::weight(T::WeightInfo::set_threshold_key())]
+		pub fn set_threshold_key(origin: OriginFor<T>, threshold_key: Option<ThresholdKey>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut recovery_config = Self::recovery_config(&who).ok_or(Error::<T>::NotRecoverable)?;
+			if let Some(key) = &threshold_key {
+				ensure!(key.threshold >= 1 && key.threshold <= key.signers, Error::<T>::InvalidThresholdKey);
+			}
+			let group_key = threshold_key.as_ref().map(|key| key.group_key);
+			recovery_config.threshold_key = threshold_key;
+			<Recoverable<T>>::insert(&who, recovery_config);
+			Self::deposit_event(Event::ThresholdKeySet(who, group_key));
+			Ok(())
+		}
+
+		/// Claim the recovery of `lost` with a threshold signature of its friends, in place of
+		/// their approvals.
+		///
+		/// Parameters:
+		/// - `lost`: the account to recover, whose recovery the caller initiated
+		/// - `signature`: the FROST signature of the friends under the config's threshold key, on
+		/// 	the `threshold_recovery_payload` of the recovery
+		///
+		/// The delay period applies as for `claim_recovery`.
+		///
+		/// # <weight>
+		/// - one storage read to get the recovery config and one of the active recovery, O(1)
+		/// - one Ed25519 signature verification
+		/// - one storage write of the proxy and one of the rescuer's consumers
+		/// - one event
+		/// - the removal of the pending commitments, as for `claim_recovery`
+		/// - a sweep of the account into the rescuer, if the owner enabled `auto_sweep`
+		/// # </weight>
+		#[pallet::call_index(15)]
+		#[pallet::weight(
+			T::WeightInfo::claim_with_threshold_signature().saturating_add(Self::max_claim_cleanup_weight())
+		)]
+		pub fn claim_with_threshold_signature(
+			origin: OriginFor<T>,
+			lost: T::AccountId,
+			signature: ed25519::Signature,
+		) -> DispatchResultWithPostInfo {
+			let rescuer = ensure_signed(origin)?;
+			let (recovery_config, active_recovery) = Self::claimable_recovery(&lost, &rescuer)?;
+			let threshold_key = recovery_config.threshold_key.as_ref().ok_or(Error::<T>::NoThresholdKey)?;
+			let payload = threshold_recovery_payload(&Self::genesis_hash(), &lost, &rescuer, &active_recovery.created);
+			ensure!(
+				sp_io::crypto::ed25519_verify(&signature, &payload, &ed25519::Public::from_raw(threshold_key.group_key)),
+				Error::<T>::ThresholdSignatureInvalid
+			);
+			let claim_weight = T::WeightInfo::claim_with_threshold_signature();
+			let actual_weight = claim_weight.saturating_add(Self::do_claim(lost, rescuer, &recovery_config)?);
+			Ok(Some(actual_weight).into())
+		}
+
+		/// Approve the recovery of `lost` by `rescuer` for several friends of a BLS group at once.
+		///
+		/// Parameters:
+		/// - `lost`, `rescuer`: the active recovery
+		/// - `group`: the index of the friends' group, whose leaves are BLS public keys
+		/// - `proofs`: the merkle proofs of the friends' keys, at most `MaxApprovals`
+		/// - `signature`: the aggregate of the friends' BLS signatures of the `bls_approval_payload`
+		///
+		/// The approvals are recorded like those of `approve_recovery`, all of them or none.
+		///
+		/// # <weight>
+		/// Key: N(len of proofs), D(depth of the deepest merkle proof)
+		/// - one storage read of the recovery config and of the active recovery, O(1)
+		/// - one merkle proof verification and one key decompression per friend, O(N * D)
+		/// - one hash to the curve and two pairings
+		/// - one storage write of the active recovery
+		/// - one event
+		/// # </weight>
+		#[pallet::call_index(16)]
+		#[pallet::weight(T::WeightInfo::approve_recovery_aggregated(
+			proofs.len() as u32,
+			proofs.iter().map(|proof| proof.depth()).max().unwrap_or(0)
+		))]
+		pub fn approve_recovery_aggregated(
+			origin: OriginFor<T>,
+			lost: T::AccountId,
+			rescuer: T::AccountId,
+			group: u16,
+			proofs: Vec<MerkleProof<bls::BlsPublicKey>>,
+			signature: bls::BlsSignature,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			ensure!(!proofs.is_empty(), Error::<T>::SignatureInvalid);
+			ensure!(proofs.len() <= T::MaxApprovals::get() as usize, Error::<T>::TooManyApprovals);
 			let recovery_config = Self::recovery_config(&lost).ok_or(Error::<T>::NotRecoverable)?;
-			// Check that the merkle proof is valid so the friend's account is in recovery group
-			ensure!(proof.validate(&recovery_config.friends_merkle_root), Error::<T>::MerkleProofInvalid);
 			let mut active_recovery = Self::active_recovery(&lost, &rescuer).ok_or(Error::<T>::NotStarted)?;
-			match active_recovery.approved_friends.binary_search(&approver) {
-				Ok(_pos) => Err(Error::<T>::AlreadyApproved)?,
-				Err(pos) => active_recovery.approved_friends.insert(pos, approver.clone()),
+			for proof in &proofs {
+				let root = Self::check_proof(&recovery_config, group, proof)?;
+				// Also catches the same friend given twice
+				Self::record_approval(&recovery_config, &mut active_recovery, group, root, proof)?;
 			}
+			let public_keys: Vec<_> = proofs.iter().map(|proof| proof.value).collect();
+			let payload = bls_approval_payload(&Self::genesis_hash(), &lost, &rescuer, group, &active_recovery.created);
+			ensure!(bls::verify_aggregate(&public_keys, &payload, &signature), Error::<T>::SignatureInvalid);
 			<ActiveRecoveries<T>>::insert(&lost, &rescuer, active_recovery);
-			Self::deposit_event(RawEvent::ApprovedRecovery(lost, rescuer, approver));
+			Self::deposit_event(Event::AggregatedApproval(lost, rescuer, proofs.len() as u32));
+			Ok(())
 		}
 
+		/// Approve the recovery of `lost` by `rescuer` as one of the friends of `group`, without
+		/// telling which.
+		///
+		/// Parameters:
+		/// - `lost`, `rescuer`: the active recovery
+		/// - `group`: the index of the friend's group, whose tree isn't padded
+		/// - `ring`: all the accounts of the group, in the order of the tree's leaves
+		/// - `signature`: the friend's ring signature of the `ring_approval_payload`, see the
+		/// 	`lsag` module
+		///
+		/// Each friend counts once, by its key image. A group approves either this way or with
+		/// `approve_recovery`, whichever comes first.
+		///
+		/// # <weight>
+		/// Key: R(len of ring, bounded by `MaxFriends`)
+		/// - one storage read of the recovery config and of the active recovery, O(1)
+		/// - one merkle tree of the ring, O(R)
+		/// - one ring signature verification, O(R)
+		/// - one storage write of the active recovery
+		/// - one event
+		/// # </weight>
+		#[pallet::call_index(17)]
+		#[pallet::weight(T::WeightInfo::approve_recovery_anonymously(ring.len() as u32))]
+		pub fn approve_recovery_anonymously(
+			origin: OriginFor<T>,
+			lost: T::AccountId,
+			rescuer: T::AccountId,
+			group: u16,
+			ring: Vec<T::AccountId>,
+			signature: lsag::RingSignature,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			ensure!(ring.len() <= T::MaxFriends::get() as usize, Error::<T>::RingTooLarge);
+			let recovery_config = Self::recovery_config(&lost).ok_or(Error::<T>::NotRecoverable)?;
+			// The ring is the group, which the leaves of a padded tree aren't. A friend's key image
+			// doesn't depend on the ring, so rings of different roots can't count it twice
+			let ring_root = FriendsTree::new(ring.clone()).root();
+			ensure!(
+				Self::accepted_roots(&recovery_config, group)?.contains(&&ring_root[..]),
+				Error::<T>::MerkleProofInvalid
+			);
+			let members = ring
+				.iter()
+				.map(|member| member.encode().try_into())
+				.collect::<Result<Vec<[u8; 32]>, _>>()
+				.map_err(|_| Error::<T>::SignatureInvalid)?;
+			let mut active_recovery = Self::active_recovery(&lost, &rescuer).ok_or(Error::<T>::NotStarted)?;
+			ensure!(
+				active_recovery.total_approvals() < T::MaxApprovals::get() as u32,
+				Error::<T>::TooManyApprovals
+			);
+			ensure!(
+				active_recovery.approvals.get(group as usize).map_or(0, ApprovalBitmap::count) == 0,
+				Error::<T>::MixedApprovals
+			);
+			let payload = ring_approval_payload(&Self::genesis_hash(), &lost, &rescuer, group, &active_recovery.created);
+			ensure!(lsag::verify(&members, &payload, &signature), Error::<T>::SignatureInvalid);
+			if active_recovery.key_images.len() <= group as usize {
+				active_recovery.key_images.resize(group as usize + 1, vec![]);
+			}
+			if active_recovery.approval_roots.len() <= group as usize {
+				active_recovery.approval_roots.resize(group as usize + 1, vec![]);
+			}
+			let key_images = &mut active_recovery.key_images[group as usize];
+			let approval_root = &mut active_recovery.approval_roots[group as usize];
+			// The rings of a group's approvals are all of the same root, like its proofs
+			if approval_root[..] != ring_root[..] {
+				let accepted = Self::accepted_roots(&recovery_config, group)?.contains(&&approval_root[..]);
+				ensure!(key_images.is_empty() || !accepted, Error::<T>::MixedRoots);
+				key_images.clear();
+				*approval_root = ring_root;
+			}
+			ensure!(!key_images.contains(&signature.key_image), Error::<T>::AlreadyApproved);
+			key_images.push(signature.key_image);
+			<ActiveRecoveries<T>>::insert(&lost, &rescuer, active_recovery);
+			Self::deposit_event(Event::AnonymousApproval(lost, rescuer, group));
+			Ok(())
+		}
+
+		/// Replace the friends merkle root of one of the caller's groups.
+		///
+		/// Parameters:
+		/// - `group`: the index of the group in the caller's recovery config
+		/// - `friends_merkle_root`: the root of the group's new tree, padded to the same depth if
+		/// 	the group is padded
+		/// - `friend_count`: the number of friends of the new tree, as for `create_recovery`
+		///
+		/// Proofs against the replaced root are accepted for `RootGracePeriod` more blocks.
+		///
+		/// # <weight>
+		/// - one storage read and one storage write of the recovery config, O(1)
+		/// - one event
+		/// # </weight>
+		#[pallet::call_index(18)]
+		#[pallet::weight(T::WeightInfo::rotate_friends_merkle_root())]
+		pub fn rotate_friends_merkle_root(
+			origin: OriginFor<T>,
+			group: u16,
+			friends_merkle_root: Vec<u8>,
+			friend_count: FriendCount,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut recovery_config = Self::recovery_config(&who).ok_or(Error::<T>::NotRecoverable)?;
+			let recovery_group = recovery_config.groups.get_mut(group as usize).ok_or(Error::<T>::InvalidGroup)?;
+			let mut rotated = recovery_group.clone();
+			rotated.friends_merkle_root = friends_merkle_root;
+			Self::check_friend_counts(sp_std::slice::from_ref(&rotated), &[friend_count])?;
+			let replaced = sp_std::mem::replace(recovery_group, rotated).friends_merkle_root;
+			recovery_config.previous_roots.push(PreviousRoot {
+				group,
+				friends_merkle_root: replaced,
+				replaced: <frame_system::Pallet<T>>::block_number(),
+			});
+			let excess = recovery_config.previous_roots.len().saturating_sub(T::MaxPreviousRoots::get() as usize);
+			recovery_config.previous_roots.drain(..excess);
+			<Recoverable<T>>::insert(&who, recovery_config);
+			Self::deposit_event(Event::FriendsMerkleRootRotated(who, group));
+			Ok(())
+		}
+
+		/// Set whether the caller's account is swept into the rescuer when a recovery is claimed.
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::set_auto_sweep())]
+		pub fn set_auto_sweep(origin: OriginFor<T>, auto_sweep: bool) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut recovery_config = Self::recovery_config(&who).ok_or(Error::<T>::NotRecoverable)?;
+			recovery_config.auto_sweep = auto_sweep;
+			<Recoverable<T>>::insert(&who, recovery_config);
+			Self::deposit_event(Event::AutoSweepSet(who, auto_sweep));
+			Ok(())
+		}
+
+		/// Move everything out of a recovered account in one go.
+		///
+		/// The pallet's deposits are released first, what stays behind is reported in `AccountSwept`.
+		///
+		/// Parameters:
+		/// - `lost`: the recovered account, which the caller must be the proxy of
+		/// - `dest`: the account receiving the funds
+		///
+		/// # <weight>
+		/// - one storage read of the proxy, O(1)
+		/// - one storage read and write of the account's deposits and one unreserve, O(1)
+		/// - one transfer, O(1)
+		/// - a binary search of the transferable balance if the account has locks, of at most
+		/// 	`MAX_SWEEP_PROBES` withdrawal checks
+		/// - one event
+		/// # </weight>
+		#[pallet::call_index(8)]
+		#[pallet::weight(T::WeightInfo::sweep_recovered())]
+		pub fn sweep_recovered(origin: OriginFor<T>, lost: T::AccountId, dest: T::AccountId) -> DispatchResult {
+			let rescuer = ensure_signed(origin)?;
+			let target = Self::proxy(&rescuer).ok_or(Error::<T>::NotAllowed)?;
+			ensure!(&target == &lost, Error::<T>::NotAllowed);
+			Self::do_sweep(&lost, &dest)
+		}
+
+		/// Set up a recovery stored under a blinded key, so the account can't be found by
+		/// listing the recoverable accounts.
+		///
+		/// Parameters:
+		/// - `blinded_key`: the `blinded_key` of the account and a salt only the owner and its
+		/// 	rescuers know
+		/// - `groups`, `policy`, `delay_period`, `rescuers_merkle_root`, `friend_counts`: as for
+		/// 	`create_group_recovery`
+		/// - `owner_signature`: the signature of the caller on the `blinded_recovery_payload`
+		///
+		/// The caller reserves `BlindedRecoveryDeposit` until the config is revealed.
+		///
+		/// # <weight>
+		/// Key: G(len of groups)
+		/// - two storage reads to check the key isn't used yet, O(1)
+		/// - one iteration over the groups to validate the thresholds, O(G)
+		/// - one verification to check the owner's signature
+		/// - one reserve of the deposit
+		/// - two storage writes of the blinded recovery config and its deposit, Codec O(G)
+		/// - one event
+		/// # </weight>
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::WeightInfo::create_blinded_recovery(groups.len() as u32))]
+		pub fn create_blinded_recovery(
+			origin: OriginFor<T>,
+			blinded_key: [u8; 32],
+			groups: Vec<RecoveryGroup>,
+			policy: GroupPolicy,
+			delay_period: BlockNumberFor<T>,
+			rescuers_merkle_root: Option<Vec<u8>>,
+			friend_counts: Vec<FriendCount>,
+			owner_signature: Signature,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::stored_blinded_key(&who, &blinded_key).is_none(), Error::<T>::AlreadyRecoverable);
+			Self::check_friend_counts(&groups, &friend_counts)?;
+			let config = Self::checked_recovery_config(groups, policy, delay_period, rescuers_merkle_root)?;
+			let blinded_recovery = BlindedRecovery { config, owner_signature };
+			ensure!(Self::is_authorized_by(&blinded_key, &blinded_recovery, &who), Error::<T>::OwnerSignatureInvalid);
+			let storage_key = blinded_storage_key(&who, &blinded_key);
+			Self::reserve_blinded_deposit(&who, &storage_key)?;
+			<BlindedRecoverable<T>>::insert(&storage_key, blinded_recovery);
+			Self::deposit_event(Event::BlindedRecoveryCreated(blinded_key));
+			Ok(())
+		}
+
+		/// Move the recovery configuration of the caller under a blinded key.
+		///
+		/// The recovery can't be in progress.
+		///
+		/// Parameters:
+		/// - `blinded_key`: the `blinded_key` of the caller and its secret salt
+		/// - `owner_signature`: the signature of the caller on the `blinded_recovery_payload` of
+		/// 	its current configuration
+		///
+		/// The caller reserves `BlindedRecoveryDeposit` until the config is revealed.
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::WeightInfo::blind_recovery())]
+		pub fn blind_recovery(
+			origin: OriginFor<T>,
+			blinded_key: [u8; 32],
+			owner_signature: Signature,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let config = Self::recovery_config(&who).ok_or(Error::<T>::NotRecoverable)?;
+			ensure!(<ActiveRecoveries<T>>::iter_prefix(&who).next().is_none(), Error::<T>::AlreadyStarted);
+			ensure!(Self::stored_blinded_key(&who, &blinded_key).is_none(), Error::<T>::AlreadyRecoverable);
+			let blinded_recovery = BlindedRecovery { config, owner_signature };
+			ensure!(Self::is_authorized_by(&blinded_key, &blinded_recovery, &who), Error::<T>::OwnerSignatureInvalid);
+			let storage_key = blinded_storage_key(&who, &blinded_key);
+			Self::reserve_blinded_deposit(&who, &storage_key)?;
+			<Recoverable<T>>::remove(&who);
+			<BlindedRecoverable<T>>::insert(&storage_key, blinded_recovery);
+			Self::deposit_event(Event::BlindedRecoveryCreated(blinded_key));
+			Ok(())
+		}
 
-		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
-		fn claim_recovery(origin, lost: T::AccountId) {
+		/// Reveal the account of a blinded recovery configuration, and initiate its recovery.
+		///
+		/// Parameters:
+		/// - `lost`: the account you want to rescue
+		/// - `salt`: the owner's secret salt, so that `blinded_key(lost, salt)` is the key of the
+		/// 	configuration
+		/// - `rescuer_proof`: as for `initiate_recovery`
+		///
+		/// The revealed configuration becomes the one of `lost` and its deposit is returned.
+		///
+		/// # <weight>
+		/// Key: D(depth of the rescuer proof)
+		/// - two storage reads and one removal of the blinded recovery config, O(1)
+		/// - one verification to check the owner's signature
+		/// - one removal of the deposit and one unreserve
+		/// - one storage write of the recovery config, O(1)
+		/// - the weight of `initiate_recovery`
+		/// # </weight>
+		#[pallet::call_index(11)]
+		#[pallet::weight(
+			T::WeightInfo::initiate_blinded_recovery(rescuer_proof.as_ref().map_or(0, MerkleProof::depth))
+		)]
+		pub fn initiate_blinded_recovery(
+			origin: OriginFor<T>,
+			lost: T::AccountId,
+			salt: [u8; 32],
+			rescuer_proof: Option<MerkleProof<T::AccountId>>,
+		) -> DispatchResult {
 			let rescuer = ensure_signed(origin)?;
+			let blinded_key = blinded_key(&lost, &salt);
+			let storage_key = Self::stored_blinded_key(&lost, &blinded_key).ok_or(Error::<T>::NotRecoverable)?;
+			let blinded_recovery = <BlindedRecoverable<T>>::get(&storage_key).ok_or(Error::<T>::NotRecoverable)?;
+			ensure!(!<Recoverable<T>>::contains_key(&lost), Error::<T>::AlreadyRecoverable);
+			// Anyone could store a configuration under the blinded key itself before configurations
+			// were keyed by their owner, only the owner's is authorized by it
+			ensure!(
+				Self::is_authorized_by(&blinded_key, &blinded_recovery, &lost),
+				Error::<T>::OwnerSignatureInvalid
+			);
+			let recovery_config = blinded_recovery.config;
+			<BlindedRecoverable<T>>::remove(&storage_key);
+			Self::release_deposit(&lost, <BlindedDeposits<T>>::take(&storage_key));
+			T::Currency::unreserve(&lost, <LegacyBlindedDeposits<T>>::take(&storage_key));
+			<Recoverable<T>>::insert(&lost, &recovery_config);
+			Self::deposit_event(Event::RecoveryRevealed(lost.clone()));
+			Self::do_initiate_recovery(lost, rescuer, recovery_config, rescuer_proof)
+		}
+
+		/// Commit to a friend approval of the recovery of `lost` by `rescuer`, without revealing
+		/// it yet.
+		///
+		/// Parameters:
+		/// - `lost`, `rescuer`: the active recovery
+		/// - `commitment`: the `approval_commitment` of the approval's opening
+		///
+		/// The caller reserves `CommitmentDeposit`, returned on reveal or claim, and holds at most
+		/// `MaxApprovals` pending commitments.
+		///
+		/// # <weight>
+		/// - one storage read of the active recovery, O(1)
+		/// - one storage read and write of the caller's pending commitments, Codec O(`MaxApprovals`)
+		/// - one reserve of the deposit
+		/// - one event
+		/// # </weight>
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::commit_approval())]
+		pub fn commit_approval(
+			origin: OriginFor<T>,
+			lost: T::AccountId,
+			rescuer: T::AccountId,
+			commitment: [u8; 32],
+		) -> DispatchResult {
+			let committer = ensure_signed(origin)?;
+			ensure!(<ActiveRecoveries<T>>::contains_key(&lost, &rescuer), Error::<T>::NotStarted);
+			let mut commitments = <ApprovalCommitments<T>>::get((&lost, &rescuer, &committer));
+			ensure!(commitments.len() < T::MaxApprovals::get() as usize, Error::<T>::TooManyCommitments);
+			ensure!(!commitments.iter().any(|(pending, _)| pending == &commitment), Error::<T>::AlreadyCommitted);
+			Self::hold_deposit(&committer, T::CommitmentDeposit::get())?;
+			commitments.push((commitment, <frame_system::Pallet<T>>::block_number()));
+			<ApprovalCommitments<T>>::insert((&lost, &rescuer, &committer), commitments);
+			Self::deposit_event(Event::ApprovalCommitted(lost, rescuer, commitment));
+			Ok(())
+		}
+
+		/// Reveal the caller's committed approvals of the recovery of `lost` by `rescuer`, all in
+		/// one step.
+		///
+		/// Parameters:
+		/// - `lost`, `rescuer`: the active recovery
+		/// - `openings`: the openings of the caller's commitments, at most `MaxApprovals`
+		///
+		/// Only openings of commitments at least `RevealDelay` blocks old, with a valid approval,
+		/// count. Others are rejected without failing the call.
+		///
+		/// # <weight>
+		/// Key: N(len of openings), D(depth of the deepest merkle proof)
+		/// - one storage read of the recovery config, the active recovery and the commitments, O(1)
+		/// - one commitment hash, signature verification and merkle proof verification per
+		/// 	opening, O(N * D)
+		/// - one storage write of the active recovery and the commitments
+		/// - one unreserve of the deposits
+		/// - one event
+		/// # </weight>
+		#[pallet::call_index(13)]
+		#[pallet::weight(T::WeightInfo::reveal_approvals(
+			openings.len() as u32,
+			openings.iter().map(|opening| opening.proof.depth()).max().unwrap_or(0)
+		))]
+		pub fn reveal_approvals(
+			origin: OriginFor<T>,
+			lost: T::AccountId,
+			rescuer: T::AccountId,
+			openings: Vec<ApprovalOpening<T::AccountId>>,
+		) -> DispatchResult {
+			let committer = ensure_signed(origin)?;
+			ensure!(openings.len() <= T::MaxApprovals::get() as usize, Error::<T>::TooManyApprovals);
 			let recovery_config = Self::recovery_config(&lost).ok_or(Error::<T>::NotRecoverable)?;
-			let active_recovery = Self::active_recovery(&lost, &rescuer).ok_or(Error::<T>::NotStarted)?;
-			ensure!(!<Proxy<T>>::contains_key(&rescuer), Error::<T>::AlreadyProxied);
+			let mut active_recovery = Self::active_recovery(&lost, &rescuer).ok_or(Error::<T>::NotStarted)?;
+			let mut commitments = <ApprovalCommitments<T>>::get((&lost, &rescuer, &committer));
+			let pending = commitments.len();
+			let revealable_before = <frame_system::Pallet<T>>::block_number().saturating_sub(T::RevealDelay::get());
+			let (mut counted, mut rejected) = (0u32, 0u32);
+			for opening in &openings {
+				let commitment = approval_commitment(&lost, &rescuer, opening);
+				let matured = commitments
+					.iter()
+					.position(|(pending, committed)| pending == &commitment && *committed <= revealable_before);
+				let valid = match matured {
+					Some(index) => {
+						commitments.swap_remove(index);
+						Self::check_approval(
+							&recovery_config,
+							&active_recovery,
+							&lost,
+							&rescuer,
+							opening.group,
+							&opening.signature,
+							&opening.proof,
+						)
+						.and_then(|root| {
+							Self::record_approval(
+								&recovery_config,
+								&mut active_recovery,
+								opening.group,
+								root,
+								&opening.proof,
+							)
+						})
+						.is_ok()
+					}
+					None => false,
+				};
+				if valid {
+					counted += 1;
+				} else {
+					rejected += 1;
+				}
+			}
+			<ActiveRecoveries<T>>::insert(&lost, &rescuer, active_recovery);
+			let opened = (pending - commitments.len()) as u32;
+			Self::release_deposit(&committer, T::CommitmentDeposit::get().saturating_mul(opened.into()));
+			if commitments.is_empty() {
+				<ApprovalCommitments<T>>::remove((&lost, &rescuer, &committer));
+			} else {
+				<ApprovalCommitments<T>>::insert((&lost, &rescuer, &committer), commitments);
+			}
+			Self::deposit_event(Event::ApprovalsRevealed(lost, rescuer, counted, rejected));
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		/// Accept unsigned `approve_recovery` calls which would succeed if dispatched now.
+		///
+		/// The same approval is provided once per `(lost, rescuer, group, friend)`, so it can't
+		/// be replayed in the pool, and is dropped from it after `UnsignedLongevity` blocks.
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			if let Call::approve_recovery { lost, rescuer, group, signature, proof } = call {
+				Self::approved_recovery(lost, rescuer, *group, signature, proof).map_err(|e| match e {
+					Error::<T>::SignatureInvalid |
+					Error::<T>::MerkleProofInvalid |
+					Error::<T>::ProofTooDeep |
+					Error::<T>::ProofDepthMismatch => InvalidTransaction::BadProof,
+					Error::<T>::AlreadyApproved => InvalidTransaction::Stale,
+					Error::<T>::TooManyApprovals => InvalidTransaction::ExhaustsResources,
+					_ => InvalidTransaction::Call,
+				})?;
+				Ok(ValidTransaction {
+					priority: T::UnsignedPriority::get(),
+					requires: vec![],
+					provides: vec![(b"approve_recovery", lost, rescuer, group, &proof.value).encode()],
+					longevity: T::UnsignedLongevity::get(),
+					propagate: true,
+				})
+			} else {
+				InvalidTransaction::Call.into()
+			}
+		}
+	}
+
+	/// Ensure that the origin `o` represents either a signed extrinsic or an unsigned one.
+	/// Returns `Ok` with the account that signed the extrinsic, if any, or an `Err` otherwise.
+	fn ensure_signed_or_none<OuterOrigin, AccountId>(o: OuterOrigin) -> Result<Option<AccountId>, BadOrigin>
+	where
+		OuterOrigin: Into<Result<RawOrigin<AccountId>, OuterOrigin>>,
+	{
+		match o.into() {
+			Ok(RawOrigin::Signed(who)) => Ok(Some(who)),
+			Ok(RawOrigin::None) => Ok(None),
+			_ => Err(BadOrigin),
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The recovery configuration of `who`, if it is recoverable.
+		pub fn recovery_config(who: impl EncodeLike<T::AccountId>) -> Option<RecoveryConfig<BlockNumberFor<T>>> {
+			<Recoverable<T>>::get(who)
+		}
+
+		/// The active recovery of `lost` by `rescuer`, if it has been initiated.
+		pub fn active_recovery(
+			lost: impl EncodeLike<T::AccountId>,
+			rescuer: impl EncodeLike<T::AccountId>,
+		) -> Option<ActiveRecovery<BlockNumberFor<T>>> {
+			<ActiveRecoveries<T>>::get(lost, rescuer)
+		}
+
+		/// The recovery configuration `owner` stored under `blinded_key`, if any.
+		pub fn blinded_recovery(
+			owner: &T::AccountId,
+			blinded_key: [u8; 32],
+		) -> Option<BlindedRecovery<BlockNumberFor<T>>> {
+			<BlindedRecoverable<T>>::get(Self::stored_blinded_key(owner, &blinded_key)?)
+		}
+
+		/// The storage key the blinded recovery configuration `owner` stored under `blinded_key`
+		/// is at, if any, the blinded key itself for configurations stored before they were keyed
+		/// by their owner.
+		fn stored_blinded_key(owner: &T::AccountId, blinded_key: &[u8; 32]) -> Option<[u8; 32]> {
+			[blinded_storage_key(owner, blinded_key), *blinded_key]
+				.into_iter()
+				.find(|key| <BlindedRecoverable<T>>::contains_key(key))
+		}
+
+		/// The recovered account `rescuer` can make calls on behalf of, if any.
+		pub fn proxy(rescuer: impl EncodeLike<T::AccountId>) -> Option<T::AccountId> {
+			<Proxy<T>>::get(rescuer)
+		}
+
+		/// The depth of the deepest merkle proof accepted, the one of a tree of `MaxFriends`
+		/// friends.
+		pub fn max_proof_depth() -> u32 {
+			let max_friends = T::MaxFriends::get().max(1);
+			u32::BITS - (max_friends - 1).leading_zeros()
+		}
+
+		/// The message a friend of `group` signs to approve the recovery of `lost` by `rescuer`,
+		/// if it has been initiated.
+		pub fn approval_payload(lost: &T::AccountId, rescuer: &T::AccountId, group: u16) -> Option<Vec<u8>> {
+			let active_recovery = Self::active_recovery(lost, rescuer)?;
+			Some(approval_payload(&Self::genesis_hash(), lost, rescuer, group, &active_recovery.created))
+		}
+
+		/// The hash of the genesis block of the chain, which approvals are bound to.
+		pub fn genesis_hash() -> T::Hash {
+			<frame_system::Pallet<T>>::block_hash(BlockNumberFor::<T>::zero())
+		}
+
+		/// The accounts currently trying to rescue `lost`.
+		pub fn active_recoveries_for(lost: &T::AccountId) -> Vec<T::AccountId> {
+			<ActiveRecoveries<T>>::iter_prefix(lost).map(|(rescuer, _)| rescuer).collect()
+		}
+
+		/// The progress of the recovery of `lost` by `rescuer`, if it has been initiated.
+		pub fn recovery_status(
+			lost: &T::AccountId,
+			rescuer: &T::AccountId,
+		) -> Option<RecoveryStatus<BlockNumberFor<T>>> {
+			let recovery_config = Self::recovery_config(lost)?;
+			let active_recovery = Self::current_approvals(&recovery_config, Self::active_recovery(lost, rescuer)?);
+			Some(RecoveryStatus {
+				approvals: recovery_config.approvals_per_group(&active_recovery),
+				thresholds: recovery_config.groups.iter().map(|group| group.threshold).collect(),
+				threshold_reached: recovery_config.is_satisfied_by(&active_recovery),
+				claimable_at: active_recovery.created.saturating_add(recovery_config.delay_period),
+			})
+		}
+
+		/// Get the config and active recovery of `lost` by `rescuer`, checking that it isn't
+		/// claimed yet and that its delay period is over.
+		fn claimable_recovery(
+			lost: &T::AccountId,
+			rescuer: &T::AccountId,
+		) -> Result<(RecoveryConfig<BlockNumberFor<T>>, ActiveRecovery<BlockNumberFor<T>>), Error<T>> {
+			let recovery_config = Self::recovery_config(lost).ok_or(Error::<T>::NotRecoverable)?;
+			let active_recovery = Self::active_recovery(lost, rescuer).ok_or(Error::<T>::NotStarted)?;
+			ensure!(!<Proxy<T>>::contains_key(rescuer), Error::<T>::AlreadyProxied);
 			// Check delay period
-			let current_block_number = <system::Module<T>>::block_number();
+			let current_block_number = <frame_system::Pallet<T>>::block_number();
 			let recoverable_block_number = active_recovery.created.checked_add(&recovery_config.delay_period).ok_or(Error::<T>::Overflow)?;
 			ensure!(recoverable_block_number <= current_block_number, Error::<T>::DelayPeriod);
-			// Check threshold
-			ensure!(active_recovery.approved_friends.len() >= recovery_config.threshold as usize, Error::<T>::UnderThreshold);
+			Ok((recovery_config, active_recovery))
+		}
+
+		/// Make `rescuer` the proxy of `lost`, drop the pending commitments of the recovery, and
+		/// sweep the account into it if the owner enabled `auto_sweep`. Returns the weight of the
+		/// cleanup and sweep.
+		fn do_claim(
+			lost: T::AccountId,
+			rescuer: T::AccountId,
+			recovery_config: &RecoveryConfig<BlockNumberFor<T>>,
+		) -> Result<Weight, DispatchError> {
+			frame_system::Pallet::<T>::inc_consumers(&rescuer)?;
 			<Proxy<T>>::insert(&rescuer, &lost);
-			system::Module::<T>::inc_ref(&rescuer);
-			Self::deposit_event(RawEvent::AccountRecovered(lost, rescuer));
+			Self::deposit_event(Event::AccountRecovered(lost.clone(), rescuer.clone()));
+			let mut weight = T::WeightInfo::clear_commitments(Self::clear_commitments(&lost, &rescuer));
+			if recovery_config.auto_sweep {
+				// The account is recovered even if it can't be swept right now, in which case the
+				// rescuer can still sweep it later with `sweep_recovered`
+				if let Err(error) = Self::do_sweep(&lost, &rescuer) {
+					Self::deposit_event(Event::SweepFailed(lost.clone(), rescuer.clone(), error));
+				}
+				weight = weight.saturating_add(T::WeightInfo::sweep_recovered());
+			}
+			Ok(weight)
+		}
+
+		/// Remove the pending commitments of up to `MaxApprovals` committers to approvals of the
+		/// recovery of `lost` by `rescuer`, and return their deposits. Returns the number of
+		/// committers whose commitments were removed.
+		///
+		/// Committers beyond them get their deposits back by revealing their commitments.
+		pub(crate) fn clear_commitments(lost: &T::AccountId, rescuer: &T::AccountId) -> u32 {
+			let mut cleared = 0;
+			for (committer, commitments) in
+				<ApprovalCommitments<T>>::drain_prefix((lost, rescuer)).take(T::MaxApprovals::get() as usize)
+			{
+				let deposit = T::CommitmentDeposit::get().saturating_mul((commitments.len() as u32).into());
+				Self::release_deposit(&committer, deposit);
+				cleared += 1;
+			}
+			cleared
+		}
+
+		/// The most weight `do_claim` can use after the claim itself.
+		fn max_claim_cleanup_weight() -> Weight {
+			T::WeightInfo::clear_commitments(T::MaxApprovals::get() as u32)
+				.saturating_add(T::WeightInfo::sweep_recovered())
+		}
+
+		/// Unreserve the deposits this pallet holds from `lost`, then transfer its transferable
+		/// balance to `dest`. Nothing is changed if the transfer fails.
+		fn do_sweep(lost: &T::AccountId, dest: &T::AccountId) -> DispatchResult {
+			with_transaction(|| {
+				let unreserved = Self::release_deposit(lost, <Deposits<T>>::get(lost));
+				let transferable = Self::transferable_balance(lost);
+				let left_behind = T::Currency::total_balance(lost).saturating_sub(transferable);
+				if !transferable.is_zero() {
+					if let Err(error) = T::Currency::transfer(lost, dest, transferable, ExistenceRequirement::AllowDeath) {
+						return TransactionOutcome::Rollback(Err(error));
+					}
+				}
+				Self::deposit_event(Event::AccountSwept(lost.clone(), dest.clone(), transferable, unreserved, left_behind));
+				TransactionOutcome::Commit(Ok(()))
+			})
+		}
+
+		/// The part of the free balance of `who` which can be transferred out, given its locks and
+		/// reserves.
+		fn transferable_balance(who: &T::AccountId) -> BalanceOf<T> {
+			let free = T::Currency::free_balance(who);
+			// The account can't be reaped while it holds reserved funds, so it keeps the existential
+			// deposit then
+			let most = if T::Currency::reserved_balance(who).is_zero() {
+				free
+			} else {
+				free.saturating_sub(T::Currency::minimum_balance())
+			};
+			let can_transfer = |amount: BalanceOf<T>| {
+				T::Currency::ensure_can_withdraw(who, amount, WithdrawReasons::TRANSFER, free - amount).is_ok()
+			};
+			if can_transfer(most) {
+				return most;
+			}
+			// Locks only let part of the balance out, find the largest amount they allow
+			let (mut low, mut high) = (Zero::zero(), most);
+			for _ in 0..MAX_SWEEP_PROBES {
+				if high - low <= One::one() {
+					break;
+				}
+				let middle = low + (high - low) / 2u32.into();
+				if can_transfer(middle) {
+					low = middle;
+				} else {
+					high = middle;
+				}
+			}
+			low
+		}
+
+		/// Check an approval of the recovery of `lost` by `rescuer`, and return the active recovery
+		/// with the approval added.
+		///
+		/// These are the checks of `approve_recovery`, shared with the validation of unsigned
+		/// approvals so that only approvals which would succeed get into the transaction pool.
+		fn approved_recovery(
+			lost: &T::AccountId,
+			rescuer: &T::AccountId,
+			group: u16,
+			signature: &Signature,
+			proof: &MerkleProof<T::AccountId>,
+		) -> Result<ActiveRecovery<BlockNumberFor<T>>, Error<T>> {
+			// Check that the lost account is recoverable
+			let recovery_config = Self::recovery_config(lost).ok_or(Error::<T>::NotRecoverable)?;
+			let mut active_recovery = Self::active_recovery(lost, rescuer).ok_or(Error::<T>::NotStarted)?;
+			let root = Self::check_approval(
+				&recovery_config,
+				&active_recovery,
+				lost,
+				rescuer,
+				group,
+				signature,
+				proof,
+			)?;
+			Self::record_approval(&recovery_config, &mut active_recovery, group, root, proof)?;
+			Ok(active_recovery)
+		}
+
+		/// Check the signature and merkle proof of a friend of `group` approving the recovery of
+		/// `lost` by `rescuer`, in progress as `active_recovery`.
+		fn check_approval<'a>(
+			recovery_config: &'a RecoveryConfig<BlockNumberFor<T>>,
+			active_recovery: &ActiveRecovery<BlockNumberFor<T>>,
+			lost: &T::AccountId,
+			rescuer: &T::AccountId,
+			group: u16,
+			signature: &Signature,
+			proof: &MerkleProof<T::AccountId>,
+		) -> Result<&'a [u8], Error<T>> {
+			let approver_public: [u8; 32] =
+				proof.value.encode().try_into().map_err(|_| Error::<T>::SignatureInvalid)?;
+			// Check that the friend's signature on the approval payload is valid
+			let payload = approval_payload(&Self::genesis_hash(), lost, rescuer, group, &active_recovery.created);
+			ensure!(
+				sp_io::crypto::sr25519_verify(signature, &payload, &sr25519::Public::from_raw(approver_public)),
+				Error::<T>::SignatureInvalid
+			);
+			Self::check_proof(recovery_config, group, proof)
+		}
+
+		/// Check that `proof` proves a leaf of one of the accepted trees of `group`, and return
+		/// the root of that tree.
+		fn check_proof<'a, V: Encode>(
+			recovery_config: &'a RecoveryConfig<BlockNumberFor<T>>,
+			group: u16,
+			proof: &MerkleProof<V>,
+		) -> Result<&'a [u8], Error<T>> {
+			let recovery_group = recovery_config.groups.get(group as usize).ok_or(Error::<T>::InvalidGroup)?;
+			ensure!(proof.depth() <= Self::max_proof_depth(), Error::<T>::ProofTooDeep);
+			// Proofs of padded trees all have the same depth, any other one can't be of a friend
+			if let Some(padded_depth) = recovery_group.padded_depth {
+				ensure!(proof.depth() == padded_depth, Error::<T>::ProofDepthMismatch);
+			}
+			// Check that the merkle proof is valid so the friend's account is in recovery group
+			let root = proof.root();
+			Self::accepted_roots(recovery_config, group)?
+				.into_iter()
+				.find(|accepted| **accepted == root[..])
+				.ok_or(Error::<T>::MerkleProofInvalid)
+		}
+
+		/// The roots proofs of `group` are accepted against: its current one, then the ones it
+		/// replaced less than `RootGracePeriod` blocks ago.
+		fn accepted_roots(
+			recovery_config: &RecoveryConfig<BlockNumberFor<T>>,
+			group: u16,
+		) -> Result<Vec<&[u8]>, Error<T>> {
+			let recovery_group = recovery_config.groups.get(group as usize).ok_or(Error::<T>::InvalidGroup)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+			let previous = recovery_config.previous_roots.iter().rev().filter(|previous| {
+				previous.group == group && now < previous.replaced.saturating_add(T::RootGracePeriod::get())
+			});
+			Ok(sp_std::iter::once(&recovery_group.friends_merkle_root[..])
+				.chain(previous.map(|previous| &previous.friends_merkle_root[..]))
+				.collect())
+		}
+
+		/// `active_recovery` without the approvals of the groups whose root is no longer accepted,
+		/// as their friends may have been removed from the group since.
+		fn current_approvals(
+			recovery_config: &RecoveryConfig<BlockNumberFor<T>>,
+			mut active_recovery: ActiveRecovery<BlockNumberFor<T>>,
+		) -> ActiveRecovery<BlockNumberFor<T>> {
+			for group in 0..active_recovery.approval_roots.len() {
+				let approval_root = &active_recovery.approval_roots[group][..];
+				// Recoveries migrated from before roots were recorded have none for anonymous
+				// approvals, which were checked against the roots accepted then
+				if approval_root.is_empty() {
+					continue;
+				}
+				let accepted = Self::accepted_roots(recovery_config, group as u16)
+					.map_or(false, |roots| roots.contains(&approval_root));
+				if !accepted {
+					if let Some(approvals) = active_recovery.approvals.get_mut(group) {
+						*approvals = ApprovalBitmap::default();
+					}
+					if let Some(key_images) = active_recovery.key_images.get_mut(group) {
+						key_images.clear();
+					}
+				}
+			}
+			active_recovery
+		}
+
+		/// Add the approval of the friend of `group` proven by `proof` against `root` to
+		/// `active_recovery`, unless it already approved.
+		fn record_approval<V>(
+			recovery_config: &RecoveryConfig<BlockNumberFor<T>>,
+			active_recovery: &mut ActiveRecovery<BlockNumberFor<T>>,
+			group: u16,
+			root: &[u8],
+			proof: &MerkleProof<V>,
+		) -> Result<(), Error<T>> {
+			ensure!(
+				active_recovery.total_approvals() < T::MaxApprovals::get() as u32,
+				Error::<T>::TooManyApprovals
+			);
+			// An anonymous friend could approve again with its proof otherwise
+			ensure!(
+				active_recovery.key_images.get(group as usize).map_or(true, Vec::is_empty),
+				Error::<T>::MixedApprovals
+			);
+			// The friend is identified by the position of its leaf, which its valid proof fixes
+			let position = proof.position();
+			if active_recovery.approvals.len() <= group as usize {
+				active_recovery.approvals.resize(group as usize + 1, ApprovalBitmap::default());
+			}
+			if active_recovery.approval_roots.len() <= group as usize {
+				active_recovery.approval_roots.resize(group as usize + 1, vec![]);
+			}
+			let approvals = &mut active_recovery.approvals[group as usize];
+			let approval_root = &mut active_recovery.approval_roots[group as usize];
+			// Positions only identify friends within a tree. The approvals against a root which
+			// is no longer accepted are dropped when the group moves on to another one
+			if approval_root[..] != *root {
+				let accepted = Self::accepted_roots(recovery_config, group)?.contains(&&approval_root[..]);
+				ensure!(approvals.count() == 0 || !accepted, Error::<T>::MixedRoots);
+				*approvals = ApprovalBitmap::default();
+				*approval_root = root.to_vec();
+			}
+			ensure!(!approvals.contains(position), Error::<T>::AlreadyApproved);
+			ensure!(approvals.insert(position), Error::<T>::ProofTooDeep);
+			Ok(())
 		}
 
+		/// Validate the groups and policy, then store the recovery configuration of `who`.
+		pub(crate) fn do_create_recovery(
+			who: T::AccountId,
+			groups: Vec<RecoveryGroup>,
+			policy: GroupPolicy,
+			delay_period: BlockNumberFor<T>,
+			rescuers_merkle_root: Option<Vec<u8>>,
+		) -> DispatchResult {
+			// Check account is not already set up for recovery
+			ensure!(!<Recoverable<T>>::contains_key(&who), Error::<T>::AlreadyRecoverable);
+			let recovery_config = Self::checked_recovery_config(groups, policy, delay_period, rescuers_merkle_root)?;
+
+			// Create the recovery configuration storage item
+			<Recoverable<T>>::insert(&who, recovery_config);
+
+			Self::deposit_event(Event::RecoveryCreated(who));
+			Ok(())
+		}
+
+		/// Check that every group has as many friends as its threshold, and that the proven counts
+		/// match the groups' trees.
+		fn check_friend_counts(groups: &[RecoveryGroup], friend_counts: &[FriendCount]) -> Result<(), Error<T>> {
+			ensure!(groups.len() == friend_counts.len(), Error::<T>::FriendCountsMismatch);
+			for (group, count) in groups.iter().zip(friend_counts) {
+				ensure!(group.threshold as u32 <= count.friends, Error::<T>::ThresholdAboveFriendCount);
+				if let Some(proof) = &count.proof {
+					ensure!(
+						proof.validate(&group.friends_merkle_root, count.friends),
+						Error::<T>::FriendCountProofInvalid
+					);
+				}
+			}
+			Ok(())
+		}
+
+		/// Validate the groups and policy, and build the recovery configuration out of them.
+		fn checked_recovery_config(
+			groups: Vec<RecoveryGroup>,
+			policy: GroupPolicy,
+			delay_period: BlockNumberFor<T>,
+			rescuers_merkle_root: Option<Vec<u8>>,
+		) -> Result<RecoveryConfig<BlockNumberFor<T>>, Error<T>> {
+			// Check user input is valid
+			ensure!(!groups.is_empty(), Error::<T>::NoGroups);
+			ensure!(groups.len() <= T::MaxGroups::get() as usize, Error::<T>::TooManyGroups);
+			ensure!(groups.iter().all(|group| group.threshold >= 1), Error::<T>::ZeroThreshold);
+			ensure!(
+				groups.iter().all(|group| group.padded_depth.map_or(true, |depth| depth <= Self::max_proof_depth())),
+				Error::<T>::InvalidPaddedDepth
+			);
+			let total_threshold = groups.iter().fold(0u32, |total, group| total + group.threshold as u32);
+			ensure!(total_threshold <= T::MaxApprovals::get() as u32, Error::<T>::ThresholdTooHigh);
+			if let GroupPolicy::AnyOf(required) = policy {
+				ensure!(required >= 1 && required as usize <= groups.len(), Error::<T>::InvalidPolicy);
+			}
+
+			Ok(RecoveryConfig {
+				delay_period,
+				groups,
+				policy,
+				rescuers_merkle_root,
+				auto_sweep: false,
+				threshold_key: None,
+				previous_roots: vec![],
+			})
+		}
+
+		/// Check that `rescuer` can rescue `lost` under `recovery_config`, and start its recovery.
+		fn do_initiate_recovery(
+			lost: T::AccountId,
+			rescuer: T::AccountId,
+			recovery_config: RecoveryConfig<BlockNumberFor<T>>,
+			rescuer_proof: Option<MerkleProof<T::AccountId>>,
+		) -> DispatchResult {
+			// Check that the caller is one of the pre-committed rescuers
+			if let Some(rescuers_merkle_root) = recovery_config.rescuers_merkle_root {
+				let rescuer_proof = rescuer_proof.ok_or(Error::<T>::RescuerProofMissing)?;
+				ensure!(rescuer_proof.value == rescuer, Error::<T>::RescuerNotAllowed);
+				ensure!(rescuer_proof.validate(&rescuers_merkle_root), Error::<T>::RescuerNotAllowed);
+			}
+			// Check that the recovery process has not already been started
+			ensure!(!<ActiveRecoveries<T>>::contains_key(&lost, &rescuer), Error::<T>::AlreadyStarted);
+			// Create an active recovery status
+			let recovery_status = ActiveRecovery {
+				created: <frame_system::Pallet<T>>::block_number(),
+				approvals: vec![],
+				key_images: vec![],
+				approval_roots: vec![],
+			};
+			// Create the active recovery storage item
+			<ActiveRecoveries<T>>::insert(&lost, &rescuer, recovery_status);
+			Self::deposit_event(Event::RecoveryInitiated(lost, rescuer));
+			Ok(())
+		}
+
+		/// Reserve `BlindedRecoveryDeposit` from `owner` for the configuration stored under
+		/// `storage_key`.
+		fn reserve_blinded_deposit(owner: &T::AccountId, storage_key: &[u8; 32]) -> DispatchResult {
+			let deposit = T::BlindedRecoveryDeposit::get();
+			Self::hold_deposit(owner, deposit)?;
+			<BlindedDeposits<T>>::insert(storage_key, deposit);
+			Ok(())
+		}
+
+		/// Reserve `amount` from `who` as a deposit of this pallet.
+		pub(crate) fn hold_deposit(who: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+			T::Currency::reserve(who, amount)?;
+			<Deposits<T>>::mutate(who, |held| *held = held.saturating_add(amount));
+			Ok(())
+		}
+
+		/// Unreserve up to `amount` of the deposits this pallet holds from `who`, and return the
+		/// amount unreserved. Deposits already released by a sweep aren't released again.
+		fn release_deposit(who: &T::AccountId, amount: BalanceOf<T>) -> BalanceOf<T> {
+			let released = <Deposits<T>>::mutate_exists(who, |held| {
+				let all = held.unwrap_or_else(Zero::zero);
+				let released = all.min(amount);
+				*held = Some(all - released).filter(|rest| !rest.is_zero());
+				released
+			});
+			released.saturating_sub(T::Currency::unreserve(who, released))
+		}
+
+		/// Check that `owner` signed the blinded recovery configuration stored under `blinded_key`.
+		fn is_authorized_by(
+			blinded_key: &[u8; 32],
+			blinded_recovery: &BlindedRecovery<BlockNumberFor<T>>,
+			owner: &T::AccountId,
+		) -> bool {
+			let owner_public: [u8; 32] = match owner.encode().try_into() {
+				Ok(public) => public,
+				Err(_) => return false,
+			};
+			let config = &blinded_recovery.config;
+			let payload = blinded_recovery_payload(
+				blinded_key,
+				&config.groups,
+				config.policy,
+				&config.delay_period,
+				&config.rescuers_merkle_root,
+			);
+			sp_io::crypto::sr25519_verify(
+				&blinded_recovery.owner_signature,
+				&payload,
+				&sr25519::Public::from_raw(owner_public),
+			)
+		}
+	}
+}
+
+/// The message a friend of `group` signs to approve the recovery of `lost` by `rescuer`, started
+/// at block `created` on the chain of `genesis_hash`.
+///
+/// Binding all of them keeps an approval from being replayed on another chain, for another lost
+/// account, in another group or on a later recovery by the same rescuer. Off-chain tools use it
+/// to build byte-identical approvals.
+pub fn approval_payload<Hash: Encode, AccountId: Encode, BlockNumber: Encode>(
+	genesis_hash: &Hash,
+	lost: &AccountId,
+	rescuer: &AccountId,
+	group: u16,
+	created: &BlockNumber,
+) -> Vec<u8> {
+	(APPROVAL_CONTEXT, genesis_hash, lost, rescuer, group, created).encode()
+}
+
+/// The message the friends sign with their threshold key to claim the recovery of `lost` by
+/// `rescuer`, bound like `approval_payload`.
+pub fn threshold_recovery_payload<Hash: Encode, AccountId: Encode, BlockNumber: Encode>(
+	genesis_hash: &Hash,
+	lost: &AccountId,
+	rescuer: &AccountId,
+	created: &BlockNumber,
+) -> Vec<u8> {
+	(THRESHOLD_RECOVERY_CONTEXT, genesis_hash, lost, rescuer, created).encode()
+}
+
+/// The message the friends of the BLS group `group` sign to approve the recovery of `lost` by
+/// `rescuer`, started at block `created` on the chain of `genesis_hash`, bound like
+/// `approval_payload`.
+pub fn bls_approval_payload<Hash: Encode, AccountId: Encode, BlockNumber: Encode>(
+	genesis_hash: &Hash,
+	lost: &AccountId,
+	rescuer: &AccountId,
+	group: u16,
+	created: &BlockNumber,
+) -> Vec<u8> {
+	(BLS_APPROVAL_CONTEXT, genesis_hash, lost, rescuer, group, created).encode()
+}
+
+/// The message a friend signs with the ring of `group` to approve the recovery of `lost` by
+/// `rescuer` anonymously, bound like `approval_payload`. It also scopes the key image to this
+/// recovery.
+pub fn ring_approval_payload<Hash: Encode, AccountId: Encode, BlockNumber: Encode>(
+	genesis_hash: &Hash,
+	lost: &AccountId,
+	rescuer: &AccountId,
+	group: u16,
+	created: &BlockNumber,
+) -> Vec<u8> {
+	(RING_APPROVAL_CONTEXT, genesis_hash, lost, rescuer, group, created).encode()
+}
+
+/// The commitment to `opening` for the recovery of `lost` by `rescuer`, to submit with
+/// `commit_approval`.
+pub fn approval_commitment<AccountId: Encode>(
+	lost: &AccountId,
+	rescuer: &AccountId,
+	opening: &ApprovalOpening<AccountId>,
+) -> [u8; 32] {
+	sp_io::hashing::blake2_256(&(APPROVAL_COMMITMENT_CONTEXT, lost, rescuer, opening).encode())
+}
+
+/// The blinded key the recovery configuration of `account` is stored under, given the owner's
+/// secret `salt`.
+pub fn blinded_key<AccountId: Encode>(account: &AccountId, salt: &[u8; 32]) -> [u8; 32] {
+	sp_io::hashing::blake2_256(&(account, salt).encode())
+}
+
+/// The storage key of the recovery configuration `owner` stores under `blinded_key`.
+///
+/// It is bound to the account which stores the configuration, so nobody can take the blinded key
+/// of another account first.
+pub fn blinded_storage_key<AccountId: Encode>(owner: &AccountId, blinded_key: &[u8; 32]) -> [u8; 32] {
+	sp_io::hashing::blake2_256(&(BLINDED_STORAGE_CONTEXT, owner, blinded_key).encode())
+}
+
+/// The message the owner signs to store a recovery configuration under `blinded_key`.
+///
+/// The owner's signature binds the configuration to its account, so nobody else can store a
+/// configuration for it under a key of their own.
+///
+/// The groups are signed as their root and threshold, followed by their padded depths only if
+/// one of them is padded, so the signatures made before groups could be padded stay valid.
+pub fn blinded_recovery_payload<BlockNumber: Encode>(
+	blinded_key: &[u8; 32],
+	groups: &[RecoveryGroup],
+	policy: GroupPolicy,
+	delay_period: &BlockNumber,
+	rescuers_merkle_root: &Option<Vec<u8>>,
+) -> Vec<u8> {
+	let signed_groups: Vec<(&[u8], u16)> =
+		groups.iter().map(|group| (&group.friends_merkle_root[..], group.threshold)).collect();
+	let mut payload =
+		(BLINDED_RECOVERY_CONTEXT, blinded_key, signed_groups, policy, delay_period, rescuers_merkle_root).encode();
+	if groups.iter().any(|group| group.padded_depth.is_some()) {
+		payload.extend(groups.iter().map(|group| group.padded_depth).collect::<Vec<_>>().encode());
 	}
+	payload
 }
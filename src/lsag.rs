@@ -0,0 +1,163 @@
+//! # Ring signature approvals (LSAG)
+//!
+//! A merkle proof tells which leaf of the group approved. A friend who doesn't want to be singled
+//! out approves with a linkable ring signature (LSAG) over the whole group instead, which only
+//! shows that one of its accounts signed. Ring members are the sr25519 accounts of the group, which
+//! are Ristretto points, so friends sign with the secret key of their account.
+//!
+//! Each signature carries a key image, `x * Hp(message || P)` for the signer's key `x * G = P`. It
+//! is the same for any two signatures of the same message by the same friend, and nothing else,
+//! so the pallet counts each image once. The message names the chain, the group and the recovery,
+//! down to the block it started at, so the images of one friend in different recoveries can't be
+//! linked.
+//!
+//! Everything here is pure Rust and works in `no_std`, but for signing with an sr25519 pair.
+
+use codec::{Decode, Encode};
+use curve25519_dalek::{
+	constants::RISTRETTO_BASEPOINT_POINT,
+	ristretto::{CompressedRistretto, RistrettoPoint},
+	scalar::Scalar,
+};
+use scale_info::TypeInfo;
+use sha2::{Digest, Sha512};
+use sp_io::hashing::blake2_256;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+/// The domain of the hashes of ring signatures.
+const RING_CONTEXT: &[u8] = b"secret-social-recovery/ring";
+
+/// The key image of a ring signature, which tells two signatures of the same friend apart.
+pub type KeyImage = [u8; 32];
+
+/// A linkable ring signature of a message by one of the accounts of a ring
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct RingSignature {
+	/// The challenge of the first member of the ring.
+	pub challenge: [u8; 32],
+	/// The response of each member of the ring, in order.
+	pub responses: Vec<[u8; 32]>,
+	/// The key image of the signer.
+	pub key_image: KeyImage,
+}
+
+/// The point the key image of `member` is based on.
+fn image_base(message: &[u8], member: &[u8; 32]) -> RistrettoPoint {
+	RistrettoPoint::hash_from_bytes::<Sha512>(&[RING_CONTEXT, b"image", message, member].concat())
+}
+
+/// The challenge following a member whose commitments are `left` and `right`.
+fn challenge(ring_hash: &[u8; 32], message: &[u8], left: &RistrettoPoint, right: &RistrettoPoint) -> Scalar {
+	let mut hasher = Sha512::new();
+	hasher.update(RING_CONTEXT);
+	hasher.update(b"challenge");
+	hasher.update(ring_hash);
+	hasher.update(message);
+	hasher.update(left.compress().as_bytes());
+	hasher.update(right.compress().as_bytes());
+	Scalar::from_hash(hasher)
+}
+
+/// Check that `signature` is a signature of `message` by one of the `ring` accounts.
+pub fn verify(ring: &[[u8; 32]], message: &[u8], signature: &RingSignature) -> bool {
+	if ring.is_empty() || signature.responses.len() != ring.len() {
+		return false;
+	}
+	let decode_scalar = |bytes: &[u8; 32]| Option::<Scalar>::from(Scalar::from_canonical_bytes(*bytes));
+	let members: Option<Vec<RistrettoPoint>> =
+		ring.iter().map(|member| CompressedRistretto(*member).decompress()).collect();
+	let (members, key_image, first) = match (
+		members,
+		CompressedRistretto(signature.key_image).decompress(),
+		decode_scalar(&signature.challenge),
+	) {
+		(Some(members), Some(key_image), Some(first)) => (members, key_image, first),
+		_ => return false,
+	};
+	let ring_hash = blake2_256(&ring.encode());
+	let mut c = first;
+	for ((member, encoded), response) in members.iter().zip(ring).zip(&signature.responses) {
+		let s = match decode_scalar(response) {
+			Some(s) => s,
+			None => return false,
+		};
+		let left = s * RISTRETTO_BASEPOINT_POINT + c * member;
+		let right = s * image_base(message, encoded) + c * key_image;
+		c = challenge(&ring_hash, message, &left, &right);
+	}
+	c == first
+}
+
+/// Sign `message` as the member of `ring` whose secret key is the scalar `secret`. Returns `None`
+/// if its account isn't in the ring.
+///
+/// The nonces are derived from the secret, the ring and the message, so signing needs no
+/// randomness and works in `no_std`.
+pub fn sign(ring: &[[u8; 32]], secret: &[u8; 32], message: &[u8]) -> Option<RingSignature> {
+	let secret = Scalar::from_bytes_mod_order(*secret);
+	let public = (secret * RISTRETTO_BASEPOINT_POINT).compress().to_bytes();
+	let signer = ring.iter().position(|member| member == &public)?;
+	let members: Vec<RistrettoPoint> =
+		ring.iter().map(|member| CompressedRistretto(*member).decompress()).collect::<Option<_>>()?;
+	let ring_hash = blake2_256(&ring.encode());
+	let key_image = secret * image_base(message, &ring[signer]);
+	let nonce = |purpose: &[u8], index: u64| {
+		let mut hasher = Sha512::new();
+		hasher.update(RING_CONTEXT);
+		hasher.update(purpose);
+		hasher.update(secret.as_bytes());
+		hasher.update(ring_hash);
+		hasher.update(message);
+		hasher.update(index.to_le_bytes());
+		Scalar::from_hash(hasher)
+	};
+
+	let alpha = nonce(b"alpha", 0);
+	let mut challenges = vec![Scalar::ZERO; ring.len()];
+	let mut responses = vec![Scalar::ZERO; ring.len()];
+	let mut c = challenge(
+		&ring_hash,
+		message,
+		&(alpha * RISTRETTO_BASEPOINT_POINT),
+		&(alpha * image_base(message, &ring[signer])),
+	);
+	// Go round the ring from the signer, faking the responses of the others
+	for offset in 1..ring.len() {
+		let i = (signer + offset) % ring.len();
+		challenges[i] = c;
+		responses[i] = nonce(b"response", i as u64);
+		let left = responses[i] * RISTRETTO_BASEPOINT_POINT + c * members[i];
+		let right = responses[i] * image_base(message, &ring[i]) + c * key_image;
+		c = challenge(&ring_hash, message, &left, &right);
+	}
+	// Close the ring with the signer's secret
+	challenges[signer] = c;
+	responses[signer] = alpha - c * secret;
+	Some(RingSignature {
+		challenge: challenges[0].to_bytes(),
+		responses: responses.iter().map(Scalar::to_bytes).collect(),
+		key_image: key_image.compress().to_bytes(),
+	})
+}
+
+/// Sign `message` as the member of `ring` holding the sr25519 `pair` of its account.
+#[cfg(feature = "std")]
+pub fn sign_with_pair(ring: &[[u8; 32]], pair: &sp_core::sr25519::Pair, message: &[u8]) -> Option<RingSignature> {
+	sign(ring, &secret_scalar(pair)?.to_bytes(), message)
+}
+
+/// The secret scalar of the sr25519 `pair`, the one its public key is the multiple of.
+///
+/// The raw secret of a pair is its scalar, followed by its nonce. Nothing guarantees the scalar
+/// is reduced, so it is reduced with `Scalar::from_bytes_mod_order`, then checked against the
+/// public key of the pair. Returns `None` if it doesn't match, should the raw layout ever change.
+#[cfg(feature = "std")]
+pub fn secret_scalar(pair: &sp_core::sr25519::Pair) -> Option<Scalar> {
+	use sp_core::Pair;
+
+	let mut bytes = [0u8; 32];
+	bytes.copy_from_slice(&pair.to_raw_vec()[..32]);
+	let secret = Scalar::from_bytes_mod_order(bytes);
+	(RistrettoPoint::mul_base(&secret).compress().to_bytes() == pair.public().0).then_some(secret)
+}
@@ -0,0 +1,940 @@
+//! # Storage migrations
+//!
+//! Runtimes run [`Migrations`] from their `Executive`, which migrates the storage from the
+//! version on chain up to the current one. Each migration keeps a copy of the types it reads.
+
+use frame_support::{
+	migrations::VersionedMigration,
+	storage_alias,
+	traits::{Get, GetStorageVersion, OnRuntimeUpgrade, StorageVersion, UncheckedOnRuntimeUpgrade},
+	weights::Weight,
+	Blake2_128Concat, Identity, Twox64Concat,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use sp_runtime::traits::Saturating;
+use sp_std::{marker::PhantomData, prelude::*};
+#[cfg(feature = "try-runtime")]
+use {
+	codec::{Decode, Encode},
+	frame_support::ensure,
+	sp_runtime::TryRuntimeError,
+};
+
+use crate::{
+	ActiveRecoveries, ApprovalBitmap, BlindedRecoverable, Config, GroupPolicy, Pallet, Recoverable, Signature,
+};
+
+/// The storage version of the pallet before it used the FRAME storage version.
+pub mod legacy {
+	use super::*;
+	use codec::{Decode, Encode};
+
+	/// The versions of the layout of the pallet's storage, as the pallet tracked them itself
+	#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode, sp_runtime::RuntimeDebug)]
+	pub enum Releases {
+		/// The original layout, with a single group of friends per `RecoveryConfig`.
+		V1,
+		/// `RecoveryConfig` has groups of friends, their policy and the rescuers merkle root, and
+		/// `ActiveRecovery` the group of each approving friend.
+		V2,
+		/// `RecoveryConfig` has the `auto_sweep` flag.
+		V3,
+		/// `ActiveRecovery` tracks approvals as bitmaps of leaf positions instead of friend accounts.
+		V4,
+		/// `Recoverable`, `ActiveRecoveries` and `BlindedRecoverable` are keyed with `blake2_128_concat`.
+		V5,
+		/// `RecoveryGroup` has the `padded_depth` of its tree.
+		V6,
+		/// `RecoveryConfig` has the `threshold_key` of the friends.
+		V7,
+		/// `ActiveRecovery` has the `key_images` of anonymous approvals.
+		V8,
+		/// `RecoveryConfig` has the `previous_roots` of its groups, and `ActiveRecovery` the
+		/// `approval_roots` its approvals were checked against.
+		V9,
+		/// `ApprovalCommitments` is keyed by the committer as well, who reserves a deposit for each.
+		V10,
+	}
+
+	impl Default for Releases {
+		fn default() -> Self {
+			Releases::V1
+		}
+	}
+
+	impl Releases {
+		/// The number of the version, as a FRAME storage version.
+		pub fn number(self) -> u16 {
+			self as u16 + 1
+		}
+	}
+
+	/// The version of the storage layout, missing on the chains which ran the pallet before any
+	/// versioning, which are on `V1`.
+	#[storage_alias]
+	pub type StorageVersion<T: Config> = StorageValue<Pallet<T>, Releases, ValueQuery>;
+}
+
+/// The types of the first version of the storage, the original layout of the pallet, where a
+/// recovery configuration has a single group of friends.
+pub mod v1 {
+	use super::*;
+	use codec::{Decode, Encode};
+
+	/// A recovery configuration with a single group of friends.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct RecoveryConfig<BlockNumber> {
+		pub delay_period: BlockNumber,
+		pub friends_merkle_root: Vec<u8>,
+		pub threshold: u16,
+	}
+
+	/// An active recovery, with the accounts of the friends which approved it.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct ActiveRecovery<BlockNumber, AccountId> {
+		pub created: BlockNumber,
+		pub approved_friends: Vec<AccountId>,
+	}
+}
+
+/// The types and storage maps of the second version of the storage, where recovery
+/// configurations have groups of friends but no `auto_sweep` flag.
+pub mod v2 {
+	use super::*;
+	use codec::{Decode, Encode};
+
+	/// A recovery configuration, without the `auto_sweep` flag.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct RecoveryConfig<BlockNumber> {
+		pub delay_period: BlockNumber,
+		pub groups: Vec<v5::RecoveryGroup>,
+		pub policy: GroupPolicy,
+		pub rescuers_merkle_root: Option<Vec<u8>>,
+	}
+
+	/// An active recovery, with the group and account of the friends which approved it.
+	///
+	/// Active recoveries had this layout up to V3.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct ActiveRecovery<BlockNumber, AccountId> {
+		pub created: BlockNumber,
+		pub approved_friends: Vec<(u16, AccountId)>,
+	}
+
+	#[storage_alias]
+	pub type Recoverable<T: Config> = StorageMap<
+		Pallet<T>,
+		Twox64Concat,
+		<T as frame_system::Config>::AccountId,
+		RecoveryConfig<BlockNumberFor<T>>,
+	>;
+
+	#[storage_alias]
+	pub type ActiveRecoveries<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Twox64Concat,
+		<T as frame_system::Config>::AccountId,
+		Twox64Concat,
+		<T as frame_system::Config>::AccountId,
+		ActiveRecovery<BlockNumberFor<T>, <T as frame_system::Config>::AccountId>,
+	>;
+
+	impl<BlockNumber> From<v1::RecoveryConfig<BlockNumber>> for RecoveryConfig<BlockNumber> {
+		fn from(old: v1::RecoveryConfig<BlockNumber>) -> Self {
+			RecoveryConfig {
+				delay_period: old.delay_period,
+				groups: vec![v5::RecoveryGroup {
+					friends_merkle_root: old.friends_merkle_root,
+					threshold: old.threshold,
+				}],
+				policy: GroupPolicy::All,
+				rescuers_merkle_root: None,
+			}
+		}
+	}
+
+	impl<BlockNumber, AccountId> From<v1::ActiveRecovery<BlockNumber, AccountId>> for ActiveRecovery<BlockNumber, AccountId> {
+		fn from(old: v1::ActiveRecovery<BlockNumber, AccountId>) -> Self {
+			ActiveRecovery {
+				created: old.created,
+				approved_friends: old.approved_friends.into_iter().map(|friend| (0, friend)).collect(),
+			}
+		}
+	}
+}
+
+/// The storage maps up to the fourth version, keyed with `twox_64_concat`, or the identity for
+/// the blinded keys.
+pub mod v4 {
+	use super::*;
+
+	#[storage_alias]
+	pub type Recoverable<T: Config> = StorageMap<
+		Pallet<T>,
+		Twox64Concat,
+		<T as frame_system::Config>::AccountId,
+		v5::RecoveryConfig<BlockNumberFor<T>>,
+	>;
+
+	#[storage_alias]
+	pub type ActiveRecoveries<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Twox64Concat,
+		<T as frame_system::Config>::AccountId,
+		Twox64Concat,
+		<T as frame_system::Config>::AccountId,
+		v7::ActiveRecovery<BlockNumberFor<T>>,
+	>;
+
+	#[storage_alias]
+	pub type BlindedRecoverable<T: Config> =
+		StorageMap<Pallet<T>, Identity, [u8; 32], v5::BlindedRecovery<BlockNumberFor<T>>>;
+}
+
+/// The types and storage maps of the fifth version, where groups have no padded depth.
+///
+/// Recovery configurations have had this layout since V3.
+pub mod v5 {
+	use super::*;
+	use codec::{Decode, Encode};
+
+	/// A group of friends, whose tree isn't padded.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct RecoveryGroup {
+		pub friends_merkle_root: Vec<u8>,
+		pub threshold: u16,
+	}
+
+	/// A recovery configuration with groups without a padded depth.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct RecoveryConfig<BlockNumber> {
+		pub delay_period: BlockNumber,
+		pub groups: Vec<RecoveryGroup>,
+		pub policy: GroupPolicy,
+		pub rescuers_merkle_root: Option<Vec<u8>>,
+		pub auto_sweep: bool,
+	}
+
+	/// A blinded recovery configuration with groups without a padded depth.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, sp_runtime::RuntimeDebug)]
+	pub struct BlindedRecovery<BlockNumber> {
+		pub config: RecoveryConfig<BlockNumber>,
+		pub owner_signature: Signature,
+	}
+
+	#[storage_alias]
+	pub type Recoverable<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		RecoveryConfig<BlockNumberFor<T>>,
+	>;
+
+	#[storage_alias]
+	pub type BlindedRecoverable<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, [u8; 32], BlindedRecovery<BlockNumberFor<T>>>;
+
+	impl From<RecoveryGroup> for crate::RecoveryGroup {
+		fn from(old: RecoveryGroup) -> Self {
+			crate::RecoveryGroup::new(old.friends_merkle_root, old.threshold)
+		}
+	}
+
+	impl<BlockNumber> From<RecoveryConfig<BlockNumber>> for v6::RecoveryConfig<BlockNumber> {
+		fn from(old: RecoveryConfig<BlockNumber>) -> Self {
+			v6::RecoveryConfig {
+				delay_period: old.delay_period,
+				groups: old.groups.into_iter().map(Into::into).collect(),
+				policy: old.policy,
+				rescuers_merkle_root: old.rescuers_merkle_root,
+				auto_sweep: old.auto_sweep,
+			}
+		}
+	}
+
+	impl<BlockNumber> From<BlindedRecovery<BlockNumber>> for v6::BlindedRecovery<BlockNumber> {
+		fn from(old: BlindedRecovery<BlockNumber>) -> Self {
+			v6::BlindedRecovery {
+				config: old.config.into(),
+				owner_signature: old.owner_signature,
+			}
+		}
+	}
+
+	impl<BlockNumber> From<RecoveryConfig<BlockNumber>> for crate::RecoveryConfig<BlockNumber> {
+		fn from(old: RecoveryConfig<BlockNumber>) -> Self {
+			v6::RecoveryConfig::from(old).into()
+		}
+	}
+
+	impl<BlockNumber> From<BlindedRecovery<BlockNumber>> for crate::BlindedRecovery<BlockNumber> {
+		fn from(old: BlindedRecovery<BlockNumber>) -> Self {
+			v6::BlindedRecovery::from(old).into()
+		}
+	}
+}
+
+/// The types and storage maps of the sixth version, where recovery configurations have no
+/// threshold key.
+pub mod v6 {
+	use super::*;
+	use codec::{Decode, Encode};
+	use crate::RecoveryGroup;
+
+	/// A recovery configuration without a threshold key.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct RecoveryConfig<BlockNumber> {
+		pub delay_period: BlockNumber,
+		pub groups: Vec<RecoveryGroup>,
+		pub policy: GroupPolicy,
+		pub rescuers_merkle_root: Option<Vec<u8>>,
+		pub auto_sweep: bool,
+	}
+
+	/// A blinded recovery configuration without a threshold key.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, sp_runtime::RuntimeDebug)]
+	pub struct BlindedRecovery<BlockNumber> {
+		pub config: RecoveryConfig<BlockNumber>,
+		pub owner_signature: Signature,
+	}
+
+	#[storage_alias]
+	pub type Recoverable<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		RecoveryConfig<BlockNumberFor<T>>,
+	>;
+
+	#[storage_alias]
+	pub type BlindedRecoverable<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, [u8; 32], BlindedRecovery<BlockNumberFor<T>>>;
+
+	impl<BlockNumber> From<RecoveryConfig<BlockNumber>> for v8::RecoveryConfig<BlockNumber> {
+		fn from(old: RecoveryConfig<BlockNumber>) -> Self {
+			v8::RecoveryConfig {
+				delay_period: old.delay_period,
+				groups: old.groups,
+				policy: old.policy,
+				rescuers_merkle_root: old.rescuers_merkle_root,
+				auto_sweep: old.auto_sweep,
+				threshold_key: None,
+			}
+		}
+	}
+
+	impl<BlockNumber> From<BlindedRecovery<BlockNumber>> for v8::BlindedRecovery<BlockNumber> {
+		fn from(old: BlindedRecovery<BlockNumber>) -> Self {
+			v8::BlindedRecovery {
+				config: old.config.into(),
+				owner_signature: old.owner_signature,
+			}
+		}
+	}
+
+	impl<BlockNumber> From<RecoveryConfig<BlockNumber>> for crate::RecoveryConfig<BlockNumber> {
+		fn from(old: RecoveryConfig<BlockNumber>) -> Self {
+			v8::RecoveryConfig::from(old).into()
+		}
+	}
+
+	impl<BlockNumber> From<BlindedRecovery<BlockNumber>> for crate::BlindedRecovery<BlockNumber> {
+		fn from(old: BlindedRecovery<BlockNumber>) -> Self {
+			v8::BlindedRecovery::from(old).into()
+		}
+	}
+}
+
+/// The types and storage maps of the seventh version, where active recoveries have no key
+/// images.
+///
+/// Active recoveries have had this layout since V4.
+pub mod v7 {
+	use super::*;
+	use codec::{Decode, Encode};
+
+	/// An active recovery without anonymous approvals.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct ActiveRecovery<BlockNumber> {
+		pub created: BlockNumber,
+		pub approvals: Vec<ApprovalBitmap>,
+	}
+
+	#[storage_alias]
+	pub type ActiveRecoveries<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		ActiveRecovery<BlockNumberFor<T>>,
+	>;
+
+	impl<BlockNumber> From<ActiveRecovery<BlockNumber>> for v8::ActiveRecovery<BlockNumber> {
+		fn from(old: ActiveRecovery<BlockNumber>) -> Self {
+			v8::ActiveRecovery {
+				created: old.created,
+				approvals: old.approvals,
+				key_images: vec![],
+			}
+		}
+	}
+
+	impl<BlockNumber> From<ActiveRecovery<BlockNumber>> for crate::ActiveRecovery<BlockNumber> {
+		fn from(old: ActiveRecovery<BlockNumber>) -> Self {
+			v8::ActiveRecovery::from(old).into()
+		}
+	}
+}
+
+/// The types and storage maps of the eighth version, where recovery configurations have no
+/// previous roots, and active recoveries don't tell which roots their approvals were checked
+/// against.
+///
+/// Recovery configurations have had this layout since V7.
+pub mod v8 {
+	use super::*;
+	use codec::{Decode, Encode};
+	use crate::{lsag, RecoveryGroup, ThresholdKey};
+
+	/// A recovery configuration without previous roots.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct RecoveryConfig<BlockNumber> {
+		pub delay_period: BlockNumber,
+		pub groups: Vec<RecoveryGroup>,
+		pub policy: GroupPolicy,
+		pub rescuers_merkle_root: Option<Vec<u8>>,
+		pub auto_sweep: bool,
+		pub threshold_key: Option<ThresholdKey>,
+	}
+
+	/// A blinded recovery configuration without previous roots.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, sp_runtime::RuntimeDebug)]
+	pub struct BlindedRecovery<BlockNumber> {
+		pub config: RecoveryConfig<BlockNumber>,
+		pub owner_signature: Signature,
+	}
+
+	/// An active recovery without the roots of its approvals.
+	#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+	pub struct ActiveRecovery<BlockNumber> {
+		pub created: BlockNumber,
+		pub approvals: Vec<ApprovalBitmap>,
+		pub key_images: Vec<Vec<lsag::KeyImage>>,
+	}
+
+	#[storage_alias]
+	pub type Recoverable<T: Config> = StorageMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		RecoveryConfig<BlockNumberFor<T>>,
+	>;
+
+	#[storage_alias]
+	pub type BlindedRecoverable<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, [u8; 32], BlindedRecovery<BlockNumberFor<T>>>;
+
+	#[storage_alias]
+	pub type ActiveRecoveries<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		ActiveRecovery<BlockNumberFor<T>>,
+	>;
+
+	impl<BlockNumber> From<RecoveryConfig<BlockNumber>> for crate::RecoveryConfig<BlockNumber> {
+		fn from(old: RecoveryConfig<BlockNumber>) -> Self {
+			crate::RecoveryConfig {
+				delay_period: old.delay_period,
+				groups: old.groups,
+				policy: old.policy,
+				rescuers_merkle_root: old.rescuers_merkle_root,
+				auto_sweep: old.auto_sweep,
+				threshold_key: old.threshold_key,
+				previous_roots: vec![],
+			}
+		}
+	}
+
+	impl<BlockNumber> From<BlindedRecovery<BlockNumber>> for crate::BlindedRecovery<BlockNumber> {
+		fn from(old: BlindedRecovery<BlockNumber>) -> Self {
+			crate::BlindedRecovery {
+				config: old.config.into(),
+				owner_signature: old.owner_signature,
+			}
+		}
+	}
+
+	impl<BlockNumber> ActiveRecovery<BlockNumber> {
+		/// The active recovery, with the approvals of each group checked against the current
+		/// root of the group in `config`, the only one there was.
+		pub fn with_roots_of<B>(self, config: Option<&crate::RecoveryConfig<B>>) -> crate::ActiveRecovery<BlockNumber> {
+			let approval_roots = match config {
+				Some(config) => config
+					.groups()
+					.iter()
+					.take(self.approvals.len())
+					.map(|group| group.friends_merkle_root().to_vec())
+					.collect(),
+				None => vec![],
+			};
+			crate::ActiveRecovery {
+				created: self.created,
+				approvals: self.approvals,
+				key_images: self.key_images,
+				approval_roots,
+			}
+		}
+	}
+
+	impl<BlockNumber> From<ActiveRecovery<BlockNumber>> for crate::ActiveRecovery<BlockNumber> {
+		fn from(old: ActiveRecovery<BlockNumber>) -> Self {
+			old.with_roots_of::<BlockNumber>(None)
+		}
+	}
+}
+
+/// The storage items of the ninth version of the storage, where the pending commitments to
+/// approvals are shared by all committers of a recovery.
+pub mod v9 {
+	use super::*;
+
+	#[storage_alias]
+	pub type ApprovalCommitments<T: Config> = StorageDoubleMap<
+		Pallet<T>,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		Blake2_128Concat,
+		<T as frame_system::Config>::AccountId,
+		Vec<([u8; 32], BlockNumberFor<T>)>,
+	>;
+}
+
+/// Every migration, from the version of the storage on chain up to the current one, to run from
+/// the runtime's `Executive`.
+pub type Migrations<T> = (
+	InitStorageVersion<T>,
+	MigrateToV2<T>,
+	MigrateToV3<T>,
+	MigrateToV4<T>,
+	MigrateToV5<T>,
+	MigrateToV6<T>,
+	MigrateToV7<T>,
+	MigrateToV8<T>,
+	MigrateToV9<T>,
+	MigrateToV10<T>,
+	MigrateToV11<T>,
+);
+
+/// A step of the migrations, run only when the storage is at version `FROM`, which it then moves to
+/// `TO`.
+pub type Versioned<const FROM: u16, const TO: u16, Inner, T> =
+	VersionedMigration<FROM, TO, Inner, Pallet<T>, <T as frame_system::Config>::DbWeight>;
+
+/// The state of a pre-upgrade check, decoded by its post-upgrade check.
+#[cfg(feature = "try-runtime")]
+fn decode_state<S: Decode>(state: Vec<u8>) -> Result<S, TryRuntimeError> {
+	S::decode(&mut &state[..]).map_err(|_| "the state of the pre-upgrade check can't be decoded".into())
+}
+
+/// Move the version of the storage from the pallet's own `legacy::StorageVersion` item to the
+/// FRAME storage version of the pallet, for chains which ran the pallet before it used the latter.
+///
+/// Chains without the legacy item are on V1, or have an empty storage.
+pub struct InitStorageVersion<T>(PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for InitStorageVersion<T> {
+	fn on_runtime_upgrade() -> Weight {
+		if Pallet::<T>::on_chain_storage_version() != StorageVersion::new(0) {
+			return T::DbWeight::get().reads(1);
+		}
+		let release = <legacy::StorageVersion<T>>::take();
+		StorageVersion::new(release.number()).put::<Pallet<T>>();
+		// Both versions are read and written
+		T::DbWeight::get().reads_writes(2, 2)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(_: Vec<u8>) -> Result<(), TryRuntimeError> {
+		ensure!(Pallet::<T>::on_chain_storage_version() >= StorageVersion::new(1), "the storage version is set");
+		ensure!(!<legacy::StorageVersion<T>>::exists(), "the legacy storage version is removed");
+		Ok(())
+	}
+}
+
+/// V1 to V2: the single group of friends of recovery configurations becomes their only group,
+/// which has to approve, and the approvals of active recoveries are made by that group.
+pub struct UncheckedMigrateToV2<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV2<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let mut translated = 0u64;
+		<v2::Recoverable<T>>::translate(|_, old: v1::RecoveryConfig<BlockNumberFor<T>>| {
+			translated += 1;
+			Some(old.into())
+		});
+		<v2::ActiveRecoveries<T>>::translate(|_, _, old: v1::ActiveRecovery<BlockNumberFor<T>, T::AccountId>| {
+			translated += 1;
+			Some(old.into())
+		});
+		// Every recovery config and active recovery is read and written once
+		T::DbWeight::get().reads_writes(translated, translated)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		let configs = <v2::Recoverable<T>>::iter_keys().count() as u32;
+		let recoveries = <v2::ActiveRecoveries<T>>::iter_keys().count() as u32;
+		Ok((configs, recoveries).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let (configs, recoveries): (u32, u32) = decode_state(state)?;
+		ensure!(<v2::Recoverable<T>>::iter().count() as u32 == configs, "every recovery config is migrated");
+		ensure!(<v2::ActiveRecoveries<T>>::iter().count() as u32 == recoveries, "every active recovery is migrated");
+		Ok(())
+	}
+}
+
+/// V1 to V2, once.
+pub type MigrateToV2<T> = Versioned<1, 2, UncheckedMigrateToV2<T>, T>;
+
+/// V2 to V3: recovery configurations get the `auto_sweep` flag, disabled.
+pub struct UncheckedMigrateToV3<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV3<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let mut translated = 0u64;
+		<v4::Recoverable<T>>::translate(|_, old: v2::RecoveryConfig<BlockNumberFor<T>>| {
+			translated += 1;
+			Some(v5::RecoveryConfig {
+				delay_period: old.delay_period,
+				groups: old.groups,
+				policy: old.policy,
+				rescuers_merkle_root: old.rescuers_merkle_root,
+				auto_sweep: false,
+			})
+		});
+		// Every recovery config is read and written once
+		T::DbWeight::get().reads_writes(translated, translated)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		Ok((<v4::Recoverable<T>>::iter_keys().count() as u32).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let configs: u32 = decode_state(state)?;
+		ensure!(<v4::Recoverable<T>>::iter().count() as u32 == configs, "every recovery config is migrated");
+		ensure!(<v4::Recoverable<T>>::iter_values().all(|config| !config.auto_sweep), "auto sweep is disabled");
+		Ok(())
+	}
+}
+
+/// V2 to V3, once.
+pub type MigrateToV3<T> = Versioned<2, 3, UncheckedMigrateToV3<T>, T>;
+
+/// V3 to V4: active recoveries track approvals by leaf position instead of friend account.
+///
+/// The approvals of the recoveries in progress are dropped, their friends approve again.
+pub struct UncheckedMigrateToV4<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV4<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let mut translated = 0u64;
+		<v4::ActiveRecoveries<T>>::translate(|_, _, old: v2::ActiveRecovery<BlockNumberFor<T>, T::AccountId>| {
+			translated += 1;
+			Some(v7::ActiveRecovery {
+				created: old.created,
+				approvals: vec![],
+			})
+		});
+		// Every active recovery is read and written once
+		T::DbWeight::get().reads_writes(translated, translated)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		Ok((<v4::ActiveRecoveries<T>>::iter_keys().count() as u32).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let recoveries: u32 = decode_state(state)?;
+		ensure!(<v4::ActiveRecoveries<T>>::iter().count() as u32 == recoveries, "every active recovery is kept");
+		ensure!(
+			<v4::ActiveRecoveries<T>>::iter_values().all(|recovery| recovery.approvals.is_empty()),
+			"the approvals are dropped"
+		);
+		Ok(())
+	}
+}
+
+/// V3 to V4, once.
+pub type MigrateToV4<T> = Versioned<3, 4, UncheckedMigrateToV4<T>, T>;
+
+/// V4 to V5: the maps keyed by accounts, or by blinded keys anyone can pick, are re-keyed with
+/// `blake2_128_concat`.
+pub struct UncheckedMigrateToV5<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV5<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let recoverable: Vec<_> = <v4::Recoverable<T>>::drain().collect();
+		let active_recoveries: Vec<_> = <v4::ActiveRecoveries<T>>::drain().collect();
+		let blinded_recoverable: Vec<_> = <v4::BlindedRecoverable<T>>::drain().collect();
+		let moved = (recoverable.len() + active_recoveries.len() + blinded_recoverable.len()) as u64;
+		for (who, recovery_config) in recoverable {
+			<v5::Recoverable<T>>::insert(who, recovery_config);
+		}
+		for (lost, rescuer, active_recovery) in active_recoveries {
+			<v7::ActiveRecoveries<T>>::insert(lost, rescuer, active_recovery);
+		}
+		for (blinded_key, blinded_recovery) in blinded_recoverable {
+			<v5::BlindedRecoverable<T>>::insert(blinded_key, blinded_recovery);
+		}
+		// Every entry is read once and written twice, removed then inserted
+		T::DbWeight::get().reads_writes(moved, 2 * moved)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		let configs = <v4::Recoverable<T>>::iter_keys().count() as u32;
+		let recoveries = <v4::ActiveRecoveries<T>>::iter_keys().count() as u32;
+		let blinded = <v4::BlindedRecoverable<T>>::iter_keys().count() as u32;
+		Ok((configs, recoveries, blinded).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let (configs, recoveries, blinded): (u32, u32, u32) = decode_state(state)?;
+		ensure!(<v5::Recoverable<T>>::iter().count() as u32 == configs, "every recovery config is re-keyed");
+		ensure!(<v7::ActiveRecoveries<T>>::iter().count() as u32 == recoveries, "every active recovery is re-keyed");
+		ensure!(<v5::BlindedRecoverable<T>>::iter().count() as u32 == blinded, "every blinded config is re-keyed");
+		Ok(())
+	}
+}
+
+/// V4 to V5, once.
+pub type MigrateToV5<T> = Versioned<4, 5, UncheckedMigrateToV5<T>, T>;
+
+/// V5 to V6: the groups of recovery configurations, blinded or not, get their `padded_depth`,
+/// none.
+pub struct UncheckedMigrateToV6<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV6<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let mut translated = 0u64;
+		<v6::Recoverable<T>>::translate(|_, old: v5::RecoveryConfig<BlockNumberFor<T>>| {
+			translated += 1;
+			Some(old.into())
+		});
+		<v6::BlindedRecoverable<T>>::translate(|_, old: v5::BlindedRecovery<BlockNumberFor<T>>| {
+			translated += 1;
+			Some(old.into())
+		});
+		// Every recovery config is read and written once
+		T::DbWeight::get().reads_writes(translated, translated)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		let configs = <v6::Recoverable<T>>::iter_keys().count() as u32;
+		let blinded = <v6::BlindedRecoverable<T>>::iter_keys().count() as u32;
+		Ok((configs, blinded).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let (configs, blinded): (u32, u32) = decode_state(state)?;
+		ensure!(<v6::Recoverable<T>>::iter().count() as u32 == configs, "every recovery config is migrated");
+		ensure!(<v6::BlindedRecoverable<T>>::iter().count() as u32 == blinded, "every blinded config is migrated");
+		Ok(())
+	}
+}
+
+/// V5 to V6, once.
+pub type MigrateToV6<T> = Versioned<5, 6, UncheckedMigrateToV6<T>, T>;
+
+/// V6 to V7: recovery configurations, blinded or not, get their `threshold_key`, none.
+pub struct UncheckedMigrateToV7<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV7<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let mut translated = 0u64;
+		<v8::Recoverable<T>>::translate(|_, old: v6::RecoveryConfig<BlockNumberFor<T>>| {
+			translated += 1;
+			Some(old.into())
+		});
+		<v8::BlindedRecoverable<T>>::translate(|_, old: v6::BlindedRecovery<BlockNumberFor<T>>| {
+			translated += 1;
+			Some(old.into())
+		});
+		// Every recovery config is read and written once
+		T::DbWeight::get().reads_writes(translated, translated)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		let configs = <v8::Recoverable<T>>::iter_keys().count() as u32;
+		let blinded = <v8::BlindedRecoverable<T>>::iter_keys().count() as u32;
+		Ok((configs, blinded).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let (configs, blinded): (u32, u32) = decode_state(state)?;
+		ensure!(<v8::Recoverable<T>>::iter().count() as u32 == configs, "every recovery config is migrated");
+		ensure!(<v8::BlindedRecoverable<T>>::iter().count() as u32 == blinded, "every blinded config is migrated");
+		Ok(())
+	}
+}
+
+/// V6 to V7, once.
+pub type MigrateToV7<T> = Versioned<6, 7, UncheckedMigrateToV7<T>, T>;
+
+/// V7 to V8: active recoveries get their `key_images`, none.
+pub struct UncheckedMigrateToV8<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV8<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let mut translated = 0u64;
+		<v8::ActiveRecoveries<T>>::translate(|_, _, old: v7::ActiveRecovery<BlockNumberFor<T>>| {
+			translated += 1;
+			Some(old.into())
+		});
+		// Every active recovery is read and written once
+		T::DbWeight::get().reads_writes(translated, translated)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		Ok((<v8::ActiveRecoveries<T>>::iter_keys().count() as u32).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let recoveries: u32 = decode_state(state)?;
+		ensure!(<v8::ActiveRecoveries<T>>::iter().count() as u32 == recoveries, "every active recovery is migrated");
+		Ok(())
+	}
+}
+
+/// V7 to V8, once.
+pub type MigrateToV8<T> = Versioned<7, 8, UncheckedMigrateToV8<T>, T>;
+
+/// V8 to V9: recovery configurations, blinded or not, get their `previous_roots`, none, and
+/// active recoveries the `approval_roots` of their approvals, the current roots of their groups.
+pub struct UncheckedMigrateToV9<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV9<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let mut translated = 0u64;
+		<Recoverable<T>>::translate(|_, old: v8::RecoveryConfig<BlockNumberFor<T>>| {
+			translated += 1;
+			Some(old.into())
+		});
+		<BlindedRecoverable<T>>::translate(|_, old: v8::BlindedRecovery<BlockNumberFor<T>>| {
+			translated += 1;
+			Some(old.into())
+		});
+		let mut recoveries = 0u64;
+		<ActiveRecoveries<T>>::translate(|lost, _, old: v8::ActiveRecovery<BlockNumberFor<T>>| {
+			recoveries += 1;
+			Some(old.with_roots_of(<Recoverable<T>>::get(&lost).as_ref()))
+		});
+		// Every recovery config is read and written once, every active recovery too along with the
+		// config of its account
+		T::DbWeight::get().reads_writes(translated + 2 * recoveries, translated + recoveries)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		let configs = <v8::Recoverable<T>>::iter_keys().count() as u32;
+		let blinded = <v8::BlindedRecoverable<T>>::iter_keys().count() as u32;
+		let recoveries = <v8::ActiveRecoveries<T>>::iter_keys().count() as u32;
+		Ok((configs, blinded, recoveries).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let (configs, blinded, recoveries): (u32, u32, u32) = decode_state(state)?;
+		ensure!(<Recoverable<T>>::iter().count() as u32 == configs, "every recovery config is migrated");
+		ensure!(<BlindedRecoverable<T>>::iter().count() as u32 == blinded, "every blinded config is migrated");
+		ensure!(<ActiveRecoveries<T>>::iter().count() as u32 == recoveries, "every active recovery is migrated");
+		Ok(())
+	}
+}
+
+/// V8 to V9, once.
+pub type MigrateToV9<T> = Versioned<8, 9, UncheckedMigrateToV9<T>, T>;
+
+/// V9 to V10: pending commitments to approvals are kept per committer, with a deposit.
+///
+/// The pending commitments of V9 are dropped, their committers commit again.
+pub struct UncheckedMigrateToV10<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV10<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let removed = <v9::ApprovalCommitments<T>>::clear(u32::MAX, None).unique as u64;
+		// Every commitment is removed
+		T::DbWeight::get().reads_writes(removed, removed)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(_: Vec<u8>) -> Result<(), TryRuntimeError> {
+		ensure!(<crate::ApprovalCommitments<T>>::iter_keys().next().is_none(), "the pending commitments are dropped");
+		Ok(())
+	}
+}
+
+/// V9 to V10, once.
+pub type MigrateToV10<T> = Versioned<9, 10, UncheckedMigrateToV10<T>, T>;
+
+/// V10 to V11: the deposits this pallet holds are tracked per account in `Deposits`.
+///
+/// The deposits of blinded configurations, whose owner isn't known, go to `LegacyBlindedDeposits`.
+pub struct UncheckedMigrateToV11<T>(PhantomData<T>);
+
+impl<T: Config> UncheckedOnRuntimeUpgrade for UncheckedMigrateToV11<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let mut commitments = 0u64;
+		for ((_, _, committer), pending) in <crate::ApprovalCommitments<T>>::iter() {
+			commitments += 1;
+			let deposit = T::CommitmentDeposit::get().saturating_mul((pending.len() as u32).into());
+			<crate::Deposits<T>>::mutate(&committer, |held| *held = held.saturating_add(deposit));
+		}
+		let mut blinded = 0u64;
+		for (blinded_key, deposit) in <crate::BlindedDeposits<T>>::drain() {
+			blinded += 1;
+			<crate::LegacyBlindedDeposits<T>>::insert(blinded_key, deposit);
+		}
+		// Every commitment is read, and the deposits of its committer read and written, every
+		// blinded deposit is read and moved
+		T::DbWeight::get().reads_writes(2 * commitments + blinded, commitments + 2 * blinded)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+		Ok((<crate::BlindedDeposits<T>>::iter_keys().count() as u32).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: Vec<u8>) -> Result<(), TryRuntimeError> {
+		let blinded: u32 = decode_state(state)?;
+		ensure!(<crate::BlindedDeposits<T>>::iter_keys().next().is_none(), "the blinded deposits are moved");
+		ensure!(
+			<crate::LegacyBlindedDeposits<T>>::iter_keys().count() as u32 == blinded,
+			"every blinded deposit is kept"
+		);
+		Ok(())
+	}
+}
+
+/// V10 to V11, once.
+pub type MigrateToV11<T> = Versioned<10, 11, UncheckedMigrateToV11<T>, T>;
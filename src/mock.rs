@@ -1,100 +1,108 @@
 // Creating mock runtime here
 
 use super::*;
-use crate::{Module, Trait};
 use frame_support::{
-	impl_outer_dispatch, impl_outer_event, impl_outer_origin, parameter_types, weights::Weight,
-};
-use sp_core::H256;
-use sp_runtime::{
-	testing::Header,
-	traits::{BlakeTwo256, IdentityLookup, OnFinalize, OnInitialize},
-	Perbill,
+	construct_runtime, derive_impl, parameter_types,
+	storage::unhashed,
+	traits::{ConstU128, Hooks, StorageVersion},
+	weights::ConstantMultiplier,
 };
+use pallet_transaction_payment::FungibleAdapter;
+use rand::{rngs::StdRng, SeedableRng};
+use sp_core::Pair;
+use sp_keystore::{testing::MemoryKeystore, KeystoreExt};
+use sp_runtime::{traits::IdentityLookup, BuildStorage};
 
 use crate as recovery;
-use system as frame_system;
-
-impl_outer_origin! {
-	pub enum Origin for Test where system = frame_system {}
-}
-
-impl_outer_event! {
-	pub enum TestEvent for Test {
-		system<T>,
-		pallet_balances<T>,
-		recovery<T>,
-	}
-}
 
-impl_outer_dispatch! {
-	pub enum Call for Test where origin: Origin {
-		pallet_balances::Balances,
-		recovery::Recovery,
-	}
-}
+type Block = frame_system::mocking::MockBlock<Test>;
 
 // For testing the pallet, we construct most of a mock runtime. This means
 // first constructing a configuration type (`Test`) which `impl`s each of the
 // configuration traits of pallets we want to use.
-#[derive(Clone, Eq, PartialEq)]
-pub struct Test;
-parameter_types! {
-	pub const BlockHashCount: u64 = 250;
-	pub const MaximumBlockWeight: Weight = 1024;
-	pub const MaximumBlockLength: u32 = 2 * 1024;
-	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
-}
-impl frame_system::Trait for Test {
-	type Origin = Origin;
-	type Call = Call;
-	type Index = u64;
-	type BlockNumber = u64;
-	type Hash = H256;
-	type Hashing = BlakeTwo256;
+construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Balances: pallet_balances,
+		TransactionPayment: pallet_transaction_payment,
+		Recovery: recovery,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type DbWeight = frame_support::weights::constants::RocksDbWeight;
 	type AccountId = sr25519::Public;
 	type Lookup = IdentityLookup<Self::AccountId>;
-	type Header = Header;
-	type Event = TestEvent;
-	type BlockHashCount = BlockHashCount;
-	type MaximumBlockWeight = MaximumBlockWeight;
-	type MaximumBlockLength = MaximumBlockLength;
-	type AvailableBlockRatio = AvailableBlockRatio;
-	type Version = ();
-	type ModuleToIndex = ();
 	type AccountData = pallet_balances::AccountData<u128>;
-	type OnNewAccount = ();
-	type OnKilledAccount = ();
 }
 
-impl Trait for Test {
-	type Event = TestEvent;
-	type Call = Call;
+parameter_types! {
+	pub const MaxGroups: u16 = 3;
+	pub const MaxApprovals: u16 = 10;
+	pub const MaxFriends: u32 = 16;
+	pub const RevealDelay: u64 = 5;
+	pub const CommitmentDeposit: u128 = 5;
+	pub const BlindedRecoveryDeposit: u128 = 10;
+	pub const MaxPreviousRoots: u32 = 2;
+	pub const RootGracePeriod: u64 = 10;
+	pub const UnsignedPriority: u64 = 100;
+	pub const UnsignedLongevity: u64 = 64;
+}
+
+impl Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeCall = RuntimeCall;
+	type Currency = Balances;
+	type MaxGroups = MaxGroups;
+	type MaxApprovals = MaxApprovals;
+	type MaxFriends = MaxFriends;
+	type RevealDelay = RevealDelay;
+	type CommitmentDeposit = CommitmentDeposit;
+	type BlindedRecoveryDeposit = BlindedRecoveryDeposit;
+	type MaxPreviousRoots = MaxPreviousRoots;
+	type RootGracePeriod = RootGracePeriod;
+	type UnsignedPriority = UnsignedPriority;
+	type UnsignedLongevity = UnsignedLongevity;
+	type WeightInfo = ();
 }
 
 parameter_types! {
-	pub const ExistentialDeposit: u64 = 1;
+	pub const TransactionByteFee: u128 = 1;
+	pub const WeightFee: u128 = 0;
 }
 
-impl pallet_balances::Trait for Test {
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
 	type Balance = u128;
-	type DustRemoval = ();
-	type Event = TestEvent;
-	type ExistentialDeposit = ExistentialDeposit;
+	type ExistentialDeposit = ConstU128<1>;
 	type AccountStore = System;
 }
 
-pub type Recovery = Module<Test>;
-pub type System = system::Module<Test>;
-pub type Balances = pallet_balances::Module<Test>;
+impl pallet_transaction_payment::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type OnChargeTransaction = FungibleAdapter<Balances, ()>;
+	type OperationalFeeMultiplier = frame_support::traits::ConstU8<5>;
+	type WeightToFee = ConstantMultiplier<u128, WeightFee>;
+	type LengthToFee = ConstantMultiplier<u128, TransactionByteFee>;
+	type FeeMultiplierUpdate = ();
+}
+
 pub type BalancesCall = pallet_balances::Call<Test>;
 
 // This function basically just builds a genesis storage key/value store according to
 // our desired mockup.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	let mut t = frame_system::GenesisConfig::default()
-		.build_storage::<Test>()
-		.unwrap();
+	new_test_ext_with_recovery(vec![], vec![])
+}
+
+// Same as `new_test_ext`, with the given recovery configs and proxies seeded at genesis.
+pub fn new_test_ext_with_recovery(
+	recoverable: Vec<(sr25519::Public, Vec<u8>, u16, u64)>,
+	proxies: Vec<(sr25519::Public, sr25519::Public)>,
+) -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
 
 	// let alice = get_from_seed::<sr25519::Public>("alice");
 	// let bob = get_from_seed::<sr25519::Public>("bob");
@@ -112,10 +120,164 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 			(dave, 100),
 			(eve, 100),
 		],
+		..Default::default()
 	}
 	.assimilate_storage(&mut t)
 	.unwrap();
-	t.into()
+	recovery::GenesisConfig::<Test> {
+		recoverable,
+		proxies,
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	// Events are only deposited from block 1
+	ext.execute_with(|| System::set_block_number(1));
+	// Benchmarks sign approvals with keys generated in the keystore
+	ext.register_extension(KeystoreExt::new(MemoryKeystore::new()));
+	ext
+}
+
+/// The accounts of the storage fixtures.
+pub fn fixture_accounts() -> (sr25519::Public, sr25519::Public, sr25519::Public, sr25519::Public) {
+	(get_from_seed("alice"), get_from_seed("bob"), get_from_seed("charlie"), get_from_seed("dave"))
+}
+
+/// A group of the storage fixtures, before groups could be padded.
+pub fn v5_group(root: u8, threshold: u16) -> migrations::v5::RecoveryGroup {
+	migrations::v5::RecoveryGroup { friends_merkle_root: vec![root; MERKLE_ROOT_LENGTH], threshold }
+}
+
+/// The recovery configs of the v1 storage fixtures, as `(account, config)`.
+pub fn v1_fixture_configs() -> Vec<(sr25519::Public, migrations::v1::RecoveryConfig<u64>)> {
+	let (alice, bob, _, _) = fixture_accounts();
+	vec![
+		(
+			alice,
+			migrations::v1::RecoveryConfig {
+				delay_period: 10,
+				friends_merkle_root: vec![1; MERKLE_ROOT_LENGTH],
+				threshold: 2,
+			},
+		),
+		(
+			bob,
+			migrations::v1::RecoveryConfig {
+				delay_period: 5,
+				friends_merkle_root: vec![2; MERKLE_ROOT_LENGTH],
+				threshold: 1,
+			},
+		),
+	]
+}
+
+// Same as `new_test_ext`, with the storage of a chain which ran the original layout of the
+// pallet: the configs of `v1_fixture_configs`, an active recovery of alice by charlie approved
+// by dave, dave proxying bob, and no storage version.
+pub fn new_test_ext_v1() -> sp_io::TestExternalities {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		let (alice, bob, charlie, dave) = fixture_accounts();
+		for (who, config) in v1_fixture_configs() {
+			unhashed::put(&migrations::v2::Recoverable::<Test>::hashed_key_for(&who), &config);
+		}
+		let active_recovery = migrations::v1::ActiveRecovery {
+			created: 1u64,
+			approved_friends: vec![dave],
+		};
+		unhashed::put(&migrations::v2::ActiveRecoveries::<Test>::hashed_key_for(&alice, &charlie), &active_recovery);
+		Proxy::<Test>::insert(&dave, &bob);
+		put_legacy_storage_version(None);
+	});
+	ext
+}
+
+/// The recovery configs of the v2 storage fixtures, as `(account, config)`.
+pub fn v2_fixture_configs() -> Vec<(sr25519::Public, migrations::v2::RecoveryConfig<u64>)> {
+	let (alice, bob, _, _) = fixture_accounts();
+	vec![
+		(
+			alice,
+			migrations::v2::RecoveryConfig {
+				delay_period: 10,
+				groups: vec![v5_group(1, 2)],
+				policy: GroupPolicy::All,
+				rescuers_merkle_root: None,
+			},
+		),
+		(
+			bob,
+			migrations::v2::RecoveryConfig {
+				delay_period: 5,
+				groups: vec![v5_group(2, 1), v5_group(3, 3)],
+				policy: GroupPolicy::AnyOf(1),
+				rescuers_merkle_root: Some(vec![4; MERKLE_ROOT_LENGTH]),
+			},
+		),
+	]
+}
+
+// Same as `new_test_ext`, with the storage of a chain which ran the v2 layout of the pallet:
+// the configs of `v2_fixture_configs`, an active recovery of alice by charlie approved by dave,
+// dave proxying bob, and the storage version at V2.
+pub fn new_test_ext_v2() -> sp_io::TestExternalities {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		let (alice, bob, charlie, dave) = fixture_accounts();
+		for (who, config) in v2_fixture_configs() {
+			unhashed::put(&migrations::v4::Recoverable::<Test>::hashed_key_for(&who), &config);
+		}
+		let active_recovery = migrations::v2::ActiveRecovery {
+			created: 1u64,
+			approved_friends: vec![(0, dave)],
+		};
+		unhashed::put(&migrations::v4::ActiveRecoveries::<Test>::hashed_key_for(&alice, &charlie), &active_recovery);
+		Proxy::<Test>::insert(&dave, &bob);
+		put_legacy_storage_version(Some(migrations::legacy::Releases::V2));
+	});
+	ext
+}
+
+// Same as `new_test_ext`, with the storage of a chain which ran the v4 layout of the pallet, keyed
+// with `twox_64_concat`: a recovery config of alice, an active recovery of alice by charlie
+// approved by the first friend of a two friends tree, a blinded recovery config under
+// `[1; 32]`, and the storage version at V4.
+pub fn new_test_ext_v4() -> sp_io::TestExternalities {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		let (alice, _, charlie, _) = fixture_accounts();
+		let recovery_config = migrations::v5::RecoveryConfig {
+			delay_period: 10,
+			groups: vec![v5_group(1, 2)],
+			policy: GroupPolicy::All,
+			rescuers_merkle_root: None,
+			auto_sweep: true,
+		};
+		let mut approvals = ApprovalBitmap::default();
+		approvals.insert(2);
+		migrations::v4::Recoverable::<Test>::insert(&alice, &recovery_config);
+		migrations::v4::ActiveRecoveries::<Test>::insert(
+			&alice,
+			&charlie,
+			migrations::v7::ActiveRecovery { created: 1, approvals: vec![approvals] },
+		);
+		migrations::v4::BlindedRecoverable::<Test>::insert(
+			[1; 32],
+			migrations::v5::BlindedRecovery { config: recovery_config, owner_signature: Signature::from_raw([2; 64]) },
+		);
+		put_legacy_storage_version(Some(migrations::legacy::Releases::V4));
+	});
+	ext
+}
+
+/// Rewind the storage version to the one of a chain which ran the pallet before it used the FRAME
+/// storage version: `release`, or none for a chain which ran it before any versioning.
+pub fn put_legacy_storage_version(release: Option<migrations::legacy::Releases>) {
+	unhashed::kill(&StorageVersion::storage_key::<Recovery>());
+	match release {
+		Some(release) => migrations::legacy::StorageVersion::<Test>::put(release),
+		None => migrations::legacy::StorageVersion::<Test>::kill(),
+	}
 }
 
 pub fn get_from_seed(seed: &str) -> sr25519::Public {
@@ -130,6 +292,42 @@ pub fn sign_by_seed(seed: &str, message: &[u8]) -> Signature {
 		.sign(message)
 }
 
+/// The signature of the mock friend `seed` approving, in `group`, the recovery of `lost` by
+/// `rescuer` initiated at block `created`.
+pub fn approve_by_seed(
+	seed: &str,
+	lost: &sr25519::Public,
+	rescuer: &sr25519::Public,
+	group: u16,
+	created: u64,
+) -> Signature {
+	sign_by_seed(seed, &approval_payload(&System::block_hash(0), lost, rescuer, group, &created))
+}
+
+/// The FROST key shares of `signers` mock friends, any `threshold` of which can sign, with the
+/// group key they sign under.
+pub fn frost_friends(threshold: u16, signers: u16) -> ([u8; 32], Vec<frost::KeyShare>) {
+	frost::trusted_dealer_keygen(threshold, signers, &mut StdRng::seed_from_u64(0)).expect("static values are valid; qed")
+}
+
+/// The threshold signature of the recovery of `lost` by `rescuer`, started at `created`, by the
+/// friends holding `shares`.
+pub fn frost_sign(
+	shares: &[frost::KeyShare],
+	lost: &sr25519::Public,
+	rescuer: &sr25519::Public,
+	created: u64,
+) -> ed25519::Signature {
+	frost_sign_payload(shares, &threshold_recovery_payload(&System::block_hash(0), lost, rescuer, &created))
+}
+
+/// The threshold signature of `payload` by the friends holding `shares`. The nonces are drawn from
+/// the payload, so they differ per message.
+pub fn frost_sign_payload(shares: &[frost::KeyShare], payload: &[u8]) -> ed25519::Signature {
+	let mut rng = StdRng::from_seed(sp_io::hashing::blake2_256(payload));
+	ed25519::Signature::from_raw(frost::threshold_sign(shares, payload, &mut rng).expect("shares are valid; qed"))
+}
+
 pub fn run_to_block(n: u64) {
 	while System::block_number() < n {
 		if System::block_number() > 1 {
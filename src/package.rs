@@ -0,0 +1,288 @@
+//! # Approval packages
+//!
+//! A friend approving a recovery hands the rescuer an [`ApprovalPackage`], bundling everything
+//! `approve_recovery` needs: the lost and rescuer accounts, the friend's group and merkle proof,
+//! and the signature. It also carries the genesis hash of the chain it is meant for, which the
+//! friend signs as part of the `approval_payload`.
+//!
+//! A package is self-describing and versioned. Its SCALE encoding starts with the `ssra` magic
+//! bytes and the format version, then the fields in declaration order. With `std`, it can also be
+//! converted to JSON, with every field as hex, or to a compact string of the `ssr-approval:`
+//! prefix followed by the URL-safe base64 of the SCALE encoding, which fits in a QR code.
+
+use codec::{Decode, Encode, Input, Output};
+use sp_std::prelude::*;
+
+use crate::{approval_payload, Call, Config, MerkleProof, Signature};
+use sp_core::sr25519;
+
+/// The magic bytes every encoded approval package starts with.
+pub const APPROVAL_PACKAGE_MAGIC: [u8; 4] = *b"ssra";
+
+/// The current version of the approval package format.
+///
+/// Version 2 signs the `approval_payload` binding the chain and the recovery, version 1 packages
+/// only signed the rescuer and are rejected.
+pub const APPROVAL_PACKAGE_VERSION: u8 = 2;
+
+/// The prefix of the string encoding of approval packages.
+#[cfg(feature = "std")]
+pub const APPROVAL_PACKAGE_PREFIX: &str = "ssr-approval:";
+
+/// The signature scheme used by a friend to sign an approval
+#[derive(Clone, Copy, Eq, PartialEq, Encode, Decode, sp_runtime::RuntimeDebug)]
+pub enum SignatureScheme {
+	/// Schnorrkel signature over the approval payload.
+	Sr25519,
+}
+
+/// Everything needed to submit a friend's approval of a recovery
+#[derive(Clone, Eq, PartialEq, sp_runtime::RuntimeDebug)]
+pub struct ApprovalPackage<AccountId, Hash> {
+	/// The account to be recovered.
+	pub lost: AccountId,
+	/// The account rescuing `lost`.
+	pub rescuer: AccountId,
+	/// The index of the friend's group in the recovery config.
+	pub group: u16,
+	/// The merkle proof of the friend in the group's merkle tree.
+	pub proof: MerkleProof<AccountId>,
+	/// The scheme of `signature`.
+	pub scheme: SignatureScheme,
+	/// The friend's signature on the approval payload.
+	pub signature: Signature,
+	/// The genesis hash of the chain the approval is meant for, covered by `signature`.
+	pub genesis_hash: Hash,
+}
+
+/// The reasons an approval package can be rejected
+#[derive(Clone, Eq, PartialEq, sp_runtime::RuntimeDebug)]
+pub enum PackageError {
+	/// The data doesn't start with the approval package magic bytes or string prefix.
+	NotAnApprovalPackage,
+	/// The package was made with an unknown version of the format.
+	UnsupportedVersion(u8),
+	/// The package couldn't be decoded.
+	Malformed,
+	/// The package is meant for another chain.
+	WrongChain,
+	/// The signature of the friend is invalid.
+	SignatureInvalid,
+	/// The merkle proof of the friend is invalid.
+	MerkleProofInvalid,
+}
+
+impl<AccountId: Encode, Hash: Encode> Encode for ApprovalPackage<AccountId, Hash> {
+	fn encode_to<O: Output>(&self, dest: &mut O) {
+		APPROVAL_PACKAGE_MAGIC.encode_to(dest);
+		APPROVAL_PACKAGE_VERSION.encode_to(dest);
+		self.lost.encode_to(dest);
+		self.rescuer.encode_to(dest);
+		self.group.encode_to(dest);
+		self.proof.encode_to(dest);
+		self.scheme.encode_to(dest);
+		self.signature.encode_to(dest);
+		self.genesis_hash.encode_to(dest);
+	}
+}
+
+impl<AccountId: Decode, Hash: Decode> Decode for ApprovalPackage<AccountId, Hash> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+		if <[u8; 4]>::decode(input)? != APPROVAL_PACKAGE_MAGIC {
+			return Err("Not an approval package".into());
+		}
+		if u8::decode(input)? != APPROVAL_PACKAGE_VERSION {
+			return Err("Unsupported approval package version".into());
+		}
+		Ok(ApprovalPackage {
+			lost: Decode::decode(input)?,
+			rescuer: Decode::decode(input)?,
+			group: Decode::decode(input)?,
+			proof: Decode::decode(input)?,
+			scheme: Decode::decode(input)?,
+			signature: Decode::decode(input)?,
+			genesis_hash: Decode::decode(input)?,
+		})
+	}
+}
+
+impl<AccountId, Hash> ApprovalPackage<AccountId, Hash>
+where
+	AccountId: Encode + Decode,
+	Hash: Encode + Decode + PartialEq,
+{
+	/// Decode a SCALE encoded package, telling apart foreign data and unknown versions.
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, PackageError> {
+		if bytes.len() < 5 || bytes[..4] != APPROVAL_PACKAGE_MAGIC {
+			return Err(PackageError::NotAnApprovalPackage);
+		}
+		if bytes[4] != APPROVAL_PACKAGE_VERSION {
+			return Err(PackageError::UnsupportedVersion(bytes[4]));
+		}
+		let mut input = bytes;
+		let package = Self::decode(&mut input).map_err(|_| PackageError::Malformed)?;
+		// Trailing bytes mean the package was cut or tampered with
+		if !input.is_empty() {
+			return Err(PackageError::Malformed);
+		}
+		Ok(package)
+	}
+
+	/// Check the package is meant for the chain of `genesis_hash` and carries a valid signature
+	/// of the recovery initiated at block `created` and, if the group's `friends_merkle_root` is
+	/// known, a valid merkle proof.
+	///
+	/// These are the same checks `approve_recovery` does, so a rescuer can reject a bad package
+	/// before submitting it.
+	pub fn validate<BlockNumber: Encode>(
+		&self,
+		genesis_hash: &Hash,
+		created: &BlockNumber,
+		friends_merkle_root: Option<&[u8]>,
+	) -> Result<(), PackageError> {
+		if &self.genesis_hash != genesis_hash {
+			return Err(PackageError::WrongChain);
+		}
+		let approver_public: [u8; 32] =
+			self.proof.value.encode().try_into().map_err(|_| PackageError::SignatureInvalid)?;
+		let payload = approval_payload(&self.genesis_hash, &self.lost, &self.rescuer, self.group, created);
+		let valid_signature = match self.scheme {
+			SignatureScheme::Sr25519 => {
+				sp_io::crypto::sr25519_verify(&self.signature, &payload, &sr25519::Public::from_raw(approver_public))
+			}
+		};
+		if !valid_signature {
+			return Err(PackageError::SignatureInvalid);
+		}
+		if let Some(root) = friends_merkle_root {
+			if !self.proof.validate(root) {
+				return Err(PackageError::MerkleProofInvalid);
+			}
+		}
+		Ok(())
+	}
+
+	/// The `approve_recovery` call submitting this approval.
+	pub fn into_call<T>(self) -> Call<T>
+	where
+		T: Config<AccountId = AccountId>,
+	{
+		Call::approve_recovery {
+			lost: self.lost,
+			rescuer: self.rescuer,
+			group: self.group,
+			signature: self.signature,
+			proof: self.proof,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+mod encodings {
+	use super::*;
+	use serde::{Deserialize, Serialize};
+
+	/// The JSON representation of a package, with every field as `0x` prefixed hex.
+	#[derive(Serialize, Deserialize)]
+	#[serde(rename_all = "camelCase", deny_unknown_fields)]
+	struct JsonApprovalPackage {
+		version: u8,
+		scheme: String,
+		lost: String,
+		rescuer: String,
+		group: u16,
+		proof: String,
+		signature: String,
+		genesis_hash: String,
+	}
+
+	fn to_hex<E: Encode>(value: &E) -> String {
+		format!("0x{}", hex::encode(value.encode()))
+	}
+
+	fn from_hex<D: Decode>(value: &str) -> Result<D, PackageError> {
+		let bytes = hex::decode(value.trim_start_matches("0x")).map_err(|_| PackageError::Malformed)?;
+		D::decode(&mut &bytes[..]).map_err(|_| PackageError::Malformed)
+	}
+
+	impl<AccountId, Hash> ApprovalPackage<AccountId, Hash>
+	where
+		AccountId: Encode + Decode,
+		Hash: Encode + Decode + PartialEq,
+	{
+		/// The JSON representation of the package.
+		pub fn to_json(&self) -> String {
+			let json = JsonApprovalPackage {
+				version: APPROVAL_PACKAGE_VERSION,
+				scheme: match self.scheme {
+					SignatureScheme::Sr25519 => "sr25519".into(),
+				},
+				lost: to_hex(&self.lost),
+				rescuer: to_hex(&self.rescuer),
+				group: self.group,
+				proof: to_hex(&self.proof),
+				signature: to_hex(&self.signature),
+				genesis_hash: to_hex(&self.genesis_hash),
+			};
+			serde_json::to_string(&json).expect("a package always serializes to JSON; qed")
+		}
+
+		/// Parse the JSON representation of a package.
+		pub fn from_json(json: &str) -> Result<Self, PackageError> {
+			let json: JsonApprovalPackage =
+				serde_json::from_str(json).map_err(|_| PackageError::NotAnApprovalPackage)?;
+			if json.version != APPROVAL_PACKAGE_VERSION {
+				return Err(PackageError::UnsupportedVersion(json.version));
+			}
+			let scheme = match &json.scheme[..] {
+				"sr25519" => SignatureScheme::Sr25519,
+				_ => return Err(PackageError::Malformed),
+			};
+			Ok(ApprovalPackage {
+				lost: from_hex(&json.lost)?,
+				rescuer: from_hex(&json.rescuer)?,
+				group: json.group,
+				proof: from_hex(&json.proof)?,
+				scheme,
+				signature: from_hex(&json.signature)?,
+				genesis_hash: from_hex(&json.genesis_hash)?,
+			})
+		}
+
+		/// The compact string representation of the package, suitable for QR codes.
+		pub fn to_string_encoding(&self) -> String {
+			format!(
+				"{}{}",
+				APPROVAL_PACKAGE_PREFIX,
+				base64::encode_config(&self.encode(), base64::URL_SAFE_NO_PAD)
+			)
+		}
+
+		/// Parse the compact string representation of a package.
+		pub fn from_string_encoding(s: &str) -> Result<Self, PackageError> {
+			if !s.starts_with(APPROVAL_PACKAGE_PREFIX) {
+				return Err(PackageError::NotAnApprovalPackage);
+			}
+			let bytes = base64::decode_config(&s[APPROVAL_PACKAGE_PREFIX.len()..], base64::URL_SAFE_NO_PAD)
+				.map_err(|_| PackageError::Malformed)?;
+			Self::from_bytes(&bytes)
+		}
+	}
+
+	impl std::fmt::Display for PackageError {
+		fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			match self {
+				PackageError::NotAnApprovalPackage => write!(f, "Not an approval package"),
+				PackageError::UnsupportedVersion(version) => {
+					write!(f, "Unsupported approval package version {}", version)
+				}
+				PackageError::Malformed => write!(f, "Malformed approval package"),
+				PackageError::WrongChain => write!(f, "The approval package is meant for another chain"),
+				PackageError::SignatureInvalid => write!(f, "The signature of the friend is invalid"),
+				PackageError::MerkleProofInvalid => write!(f, "The merkle proof of the friend is invalid"),
+			}
+		}
+	}
+
+	impl std::error::Error for PackageError {}
+}
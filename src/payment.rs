@@ -0,0 +1,166 @@
+//! # Fees of recovered accounts
+//!
+//! A rescuer operating a recovered account through `as_recovered` would normally pay the
+//! transaction fees from its own balance, so a fresh rescuer key would need funding first.
+//!
+//! [`ChargeRecoveredAccount`] is a drop-in replacement of the `ChargeTransactionPayment` signed
+//! extension of `pallet-transaction-payment`. It charges fees exactly the same way, through the
+//! runtime's `OnChargeTransaction`, except for the `as_recovered` and `sweep_recovered` calls of a
+//! rescuer, whose fees are withdrawn from the lost account it is the proxy of. Unused weight is
+//! refunded to whoever paid, and the usual `TransactionFeePaid` event names the payer.
+//!
+//! The rescuer still needs the existential deposit, as `CheckNonce` rejects the transactions of
+//! accounts which don't exist.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	dispatch::{DispatchInfo, PostDispatchInfo},
+	traits::IsSubType,
+};
+use pallet_transaction_payment::{ChargeTransactionPayment, OnChargeTransaction};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, Dispatchable, PostDispatchInfoOf, SignedExtension},
+	transaction_validity::{TransactionValidity, TransactionValidityError, ValidTransaction},
+	DispatchResult, FixedPointOperand,
+};
+
+use crate::{Call, Config, Proxy};
+
+type PaymentBalanceOf<T> = pallet_transaction_payment::BalanceOf<T>;
+
+type LiquidityInfoOf<T> = <<T as pallet_transaction_payment::Config>::OnChargeTransaction as OnChargeTransaction<
+	T,
+>>::LiquidityInfo;
+
+/// Require the transactor to pay the fees, or the lost account for the calls of its rescuer
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ChargeRecoveredAccount<T: Config + pallet_transaction_payment::Config>(
+	#[codec(compact)] PaymentBalanceOf<T>,
+);
+
+impl<T: Config + pallet_transaction_payment::Config> ChargeRecoveredAccount<T>
+where
+	<T as frame_system::Config>::RuntimeCall:
+		Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + IsSubType<Call<T>>,
+	PaymentBalanceOf<T>: Send + Sync + FixedPointOperand,
+{
+	/// Utility constructor, the same as `ChargeTransactionPayment::from`.
+	pub fn from(tip: PaymentBalanceOf<T>) -> Self {
+		Self(tip)
+	}
+
+	/// The account paying the fees of `call` submitted by `who`.
+	///
+	/// This is the lost account for `as_recovered` and `sweep_recovered` when `who` is its proxy,
+	/// and `who` otherwise.
+	pub fn payer(who: &T::AccountId, call: &<T as frame_system::Config>::RuntimeCall) -> T::AccountId {
+		let lost = match call.is_sub_type() {
+			Some(Call::as_recovered { lost, .. }) | Some(Call::sweep_recovered { lost, .. }) => lost,
+			_ => return who.clone(),
+		};
+		match <Proxy<T>>::get(who) {
+			Some(target) if &target == lost => target,
+			_ => who.clone(),
+		}
+	}
+
+	/// Withdraw the fee of `call` from its payer, returning the fee, the payer and what was
+	/// withdrawn.
+	fn withdraw_fee(
+		&self,
+		who: &T::AccountId,
+		call: &<T as frame_system::Config>::RuntimeCall,
+		info: &DispatchInfo,
+		len: usize,
+	) -> Result<(PaymentBalanceOf<T>, T::AccountId, LiquidityInfoOf<T>), TransactionValidityError> {
+		let fee = pallet_transaction_payment::Pallet::<T>::compute_fee(len as u32, info, self.0);
+		let payer = Self::payer(who, call);
+		let liquidity_info =
+			<<T as pallet_transaction_payment::Config>::OnChargeTransaction as OnChargeTransaction<T>>::withdraw_fee(
+				&payer, call, info, fee, self.0,
+			)?;
+		Ok((fee, payer, liquidity_info))
+	}
+}
+
+impl<T: Config + pallet_transaction_payment::Config> sp_std::fmt::Debug for ChargeRecoveredAccount<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "ChargeRecoveredAccount<{:?}>", self.0)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Config + pallet_transaction_payment::Config> SignedExtension for ChargeRecoveredAccount<T>
+where
+	<T as frame_system::Config>::RuntimeCall:
+		Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo> + IsSubType<Call<T>>,
+	PaymentBalanceOf<T>: Send + Sync + FixedPointOperand,
+{
+	const IDENTIFIER: &'static str = "ChargeRecoveredAccount";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::RuntimeCall;
+	type AdditionalSigned = ();
+	/// The tip, the payer and what was withdrawn from it.
+	type Pre = (PaymentBalanceOf<T>, T::AccountId, LiquidityInfoOf<T>);
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		let (fee, _, _) = self.withdraw_fee(who, call, info, len)?;
+		Ok(ValidTransaction {
+			// Same priority as `ChargeTransactionPayment`, whoever pays
+			priority: ChargeTransactionPayment::<T>::get_priority(info, len, self.0, fee),
+			..Default::default()
+		})
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let (_, payer, liquidity_info) = self.withdraw_fee(who, call, info, len)?;
+		Ok((self.0, payer, liquidity_info))
+	}
+
+	fn post_dispatch(
+		maybe_pre: Option<Self::Pre>,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		_result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		if let Some((tip, payer, liquidity_info)) = maybe_pre {
+			let actual_fee =
+				pallet_transaction_payment::Pallet::<T>::compute_actual_fee(len as u32, info, post_info, tip);
+			<<T as pallet_transaction_payment::Config>::OnChargeTransaction as OnChargeTransaction<T>>::correct_and_deposit_fee(
+				&payer,
+				info,
+				post_info,
+				actual_fee,
+				tip,
+				liquidity_info,
+			)?;
+			let event: <T as pallet_transaction_payment::Config>::RuntimeEvent =
+				pallet_transaction_payment::Event::<T>::TransactionFeePaid { who: payer, actual_fee, tip }.into();
+			frame_system::Pallet::<T>::deposit_event(event.into());
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,276 @@
+//! # Merkle proofs
+//!
+//! The friends of an account are committed to with the root of a SHA256 merkle tree, using the
+//! same conventions as the `merkle` crate the pallet was first written against, so roots committed
+//! with it stay valid:
+//! - a leaf is hashed as `SHA256(0x00 || account)`,
+//! - a node is hashed as `SHA256(0x01 || left || right)`,
+//! - when a level has an odd number of nodes, the last one is carried up to the next level as is.
+//!
+//! Unlike the `merkle` crate, this module has no dependency on `ring`, works in `no_std`, and its
+//! proofs have a plain SCALE encoding, so any wallet can build them.
+//!
+//! The leaf of an account is its SCALE encoding, which for 32 byte accounts are their raw bytes.
+//!
+//! Trees can be padded to a fixed depth with dummy leaves, see [`FriendsTree::padded`], so the
+//! depth of a proof doesn't tell how many friends there are.
+//!
+//! The number of leaves of a tree can be proven without revealing them with a [`LeafCountProof`],
+//! the path from its last leaf up to the root, see [`FriendsTree::leaf_count_proof`].
+
+use codec::{Decode, Encode};
+use sha2::{Digest, Sha256};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_io::hashing::blake2_256;
+use sp_std::prelude::*;
+
+/// The hash of a node of a friends merkle tree.
+pub type MerkleHash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// The domain of the values drawn from the seed of a padded tree.
+const PADDING_CONTEXT: &[u8] = b"secret-social-recovery/padding";
+
+fn finish(hasher: Sha256) -> MerkleHash {
+	hasher.finalize().into()
+}
+
+/// The hash of the leaf of `value`.
+pub fn hash_leaf(value: &[u8]) -> MerkleHash {
+	let mut hasher = Sha256::new();
+	hasher.update([LEAF_PREFIX]);
+	hasher.update(value);
+	finish(hasher)
+}
+
+/// The hash of the node with children `left` and `right`.
+pub fn hash_nodes(left: &[u8], right: &[u8]) -> MerkleHash {
+	let mut hasher = Sha256::new();
+	hasher.update([NODE_PREFIX]);
+	hasher.update(left);
+	hasher.update(right);
+	finish(hasher)
+}
+
+/// The sibling of a node on the path from a leaf to the root
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum ProofNode {
+	/// The sibling is on the left of the path.
+	Left(MerkleHash),
+	/// The sibling is on the right of the path.
+	Right(MerkleHash),
+}
+
+/// A proof that `value` is a leaf of a friends merkle tree
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct MerkleProof<AccountId> {
+	/// The proven leaf.
+	pub value: AccountId,
+	/// The siblings on the path from the leaf up to the root.
+	pub path: Vec<ProofNode>,
+}
+
+impl<AccountId: Encode> MerkleProof<AccountId> {
+	/// The root of the tree this proof is valid for.
+	pub fn root(&self) -> MerkleHash {
+		self.path.iter().fold(hash_leaf(&self.value.encode()), |hash, node| match node {
+			ProofNode::Left(sibling) => hash_nodes(sibling, &hash),
+			ProofNode::Right(sibling) => hash_nodes(&hash, sibling),
+		})
+	}
+
+	/// Check that `value` is a leaf of the tree of `root`.
+	pub fn validate(&self, root: &[u8]) -> bool {
+		self.root()[..] == *root
+	}
+}
+
+impl<AccountId> MerkleProof<AccountId> {
+	/// The depth of the proof, i.e. the number of siblings it carries.
+	pub fn depth(&self) -> u32 {
+		self.path.len() as u32
+	}
+
+	/// The position of the proven leaf in the tree: a leading 1 followed by the directions from
+	/// the root down to the leaf, 1 for right and 0 for left.
+	///
+	/// Every leaf of a tree has its own position, and in a tree of `2^depth` leaves the leaf at
+	/// `index` is at `2^depth + index`. Leaves carried up a level have shorter proofs, and so
+	/// lower positions. Proofs deeper than 31 don't have a position and all return 0.
+	pub fn position(&self) -> u32 {
+		if self.depth() >= u32::BITS {
+			return 0;
+		}
+		self.path
+			.iter()
+			.rev()
+			.fold(1, |position, node| (position << 1) | matches!(node, ProofNode::Left(_)) as u32)
+	}
+}
+
+/// A proof that a friends merkle tree has a given number of leaves
+///
+/// It carries the index and hash of the last leaf, and the left siblings on its path up to the
+/// root. The shape of that path only depends on the index: the last node of a level has a left
+/// sibling when it's a right child, and is carried up as is otherwise. No account is revealed,
+/// only hashes.
+///
+/// The hashes aren't opened, so a proof built by taking a node for the last leaf can claim more
+/// leaves than the tree has. Only the owner of the tree can build one, to lock themselves out, so
+/// the proof guards them against a mistaken count, not against themselves.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct LeafCountProof {
+	/// The index of the last leaf of the tree, one less than its number of leaves.
+	pub index: u32,
+	/// The hash of the last leaf of the tree.
+	pub last_leaf: MerkleHash,
+	/// The siblings on the path from the last leaf up to the root.
+	pub path: Vec<ProofNode>,
+}
+
+impl LeafCountProof {
+	/// Check that the tree of `root` has `leaves` leaves.
+	pub fn validate(&self, root: &[u8], leaves: u32) -> bool {
+		if leaves == 0 || self.index != leaves - 1 {
+			return false;
+		}
+		let mut path = self.path.iter();
+		let mut hash = self.last_leaf;
+		// Each bit of the index, from the lowest, is the side of the last node of a level
+		let mut index = self.index;
+		while index > 0 {
+			if index & 1 == 1 {
+				match path.next() {
+					Some(ProofNode::Left(sibling)) => hash = hash_nodes(sibling, &hash),
+					_ => return false,
+				}
+			}
+			index >>= 1;
+		}
+		path.next().is_none() && hash[..] == *root
+	}
+}
+
+/// The merkle tree of a group of friends, or of rescuers
+#[derive(Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct FriendsTree<AccountId> {
+	/// The accounts of the tree, in order.
+	leaves: Vec<AccountId>,
+	/// The index of the leaf of each account.
+	indices: Vec<usize>,
+	/// The hashes of every level, from the leaves up to the root.
+	levels: Vec<Vec<MerkleHash>>,
+}
+
+impl<AccountId: Encode + PartialEq + Clone> FriendsTree<AccountId> {
+	/// Build the tree of `friends`, in order.
+	pub fn new(friends: Vec<AccountId>) -> Self {
+		let hashes = friends.iter().map(|friend| hash_leaf(&friend.encode())).collect();
+		let indices = (0..friends.len()).collect();
+		Self::build(friends, indices, hashes)
+	}
+
+	/// Build the tree of `friends` padded to `2^depth` leaves, so that all their proofs have
+	/// exactly `depth` siblings.
+	///
+	/// The friends are spread over the leaves in an order drawn from `seed`, and the other leaves
+	/// are dummy hashes drawn from it too. Dummies can't be told apart from friends, and have no
+	/// account behind them to approve with. The owner keeps `seed` secret, and builds the same tree
+	/// from it again to hand out proofs. Returns `None` if there are more than `2^depth` friends.
+	pub fn padded(friends: Vec<AccountId>, depth: u32, seed: &[u8; 32]) -> Option<Self> {
+		let width = 1usize.checked_shl(depth)?;
+		if friends.len() > width {
+			return None;
+		}
+		let draw = |purpose: &[u8], slot: usize| blake2_256(&(PADDING_CONTEXT, seed, purpose, slot as u64).encode());
+		// The first slots are the friends, the others dummies, then the slots are shuffled
+		let mut slots: Vec<(MerkleHash, usize)> = (0..width).map(|slot| (draw(b"order", slot), slot)).collect();
+		slots.sort();
+		let mut indices = vec![0; friends.len()];
+		let hashes = slots
+			.iter()
+			.enumerate()
+			.map(|(index, (_, slot))| match friends.get(*slot) {
+				Some(friend) => {
+					indices[*slot] = index;
+					hash_leaf(&friend.encode())
+				}
+				None => draw(b"dummy", *slot),
+			})
+			.collect();
+		Some(Self::build(friends, indices, hashes))
+	}
+
+	/// Build the levels of the tree over the leaf `hashes`.
+	fn build(leaves: Vec<AccountId>, indices: Vec<usize>, hashes: Vec<MerkleHash>) -> Self {
+		let mut levels = vec![hashes];
+		while levels[levels.len() - 1].len() > 1 {
+			let next = levels[levels.len() - 1]
+				.chunks(2)
+				.map(|pair| match pair {
+					[left, right] => hash_nodes(left, right),
+					[last] => *last,
+					_ => unreachable!("chunks have one or two elements; qed"),
+				})
+				.collect();
+			levels.push(next);
+		}
+		FriendsTree {
+			leaves,
+			indices,
+			levels,
+		}
+	}
+
+	/// The root to commit with `create_recovery`, `create_group_recovery` or as the rescuers root.
+	pub fn root(&self) -> Vec<u8> {
+		match self.levels[self.levels.len() - 1].first() {
+			Some(root) => root.to_vec(),
+			// Same as the root of an empty tree of the `merkle` crate
+			None => finish(Sha256::new()).to_vec(),
+		}
+	}
+
+	/// The accounts of the tree, in order.
+	pub fn leaves(&self) -> &[AccountId] {
+		&self.leaves
+	}
+
+	/// The proof of `friend` to submit with `approve_recovery` or `initiate_recovery`.
+	pub fn proof_for(&self, friend: &AccountId) -> Option<MerkleProof<AccountId>> {
+		let index = self.indices[self.leaves.iter().position(|leaf| leaf == friend)?];
+		Some(MerkleProof {
+			value: friend.clone(),
+			path: self.path(index),
+		})
+	}
+
+	/// The proof of the number of leaves of the tree, dummies included, to submit with
+	/// `create_recovery`. Returns `None` for an empty tree.
+	pub fn leaf_count_proof(&self) -> Option<LeafCountProof> {
+		let last = self.levels[0].len().checked_sub(1)?;
+		Some(LeafCountProof {
+			index: last as u32,
+			last_leaf: self.levels[0][last],
+			path: self.path(last),
+		})
+	}
+
+	/// The siblings on the path from the leaf at `index` up to the root.
+	fn path(&self, mut index: usize) -> Vec<ProofNode> {
+		let mut path = Vec::new();
+		for level in &self.levels[..self.levels.len() - 1] {
+			if index % 2 == 1 {
+				path.push(ProofNode::Left(level[index - 1]));
+			} else if let Some(sibling) = level.get(index + 1) {
+				path.push(ProofNode::Right(*sibling));
+			}
+			// Otherwise the node is carried up as is
+			index /= 2;
+		}
+		path
+	}
+}
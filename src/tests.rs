@@ -1,12 +1,28 @@
 // Tests to be written here
 use super::*;
-use crate::mock::Call;
 use crate::{mock::*, Error};
 use frame_support::{assert_noop, assert_ok};
-use merkle::MerkleTree;
-use ring::digest::SHA256;
-use sp_core::{sr25519, Pair};
-use sp_runtime::traits::BadOrigin;
+use sp_core::{ed25519, sr25519, Pair};
+use rand::SeedableRng;
+use sp_core::H256;
+use frame_support::dispatch::GetDispatchInfo;
+use frame_support::traits::{
+	Get, GetStorageVersion, Hooks, LockableCurrency, OnRuntimeUpgrade, ReservableCurrency, StorageVersion,
+	WithdrawReasons,
+};
+use frame_support::unsigned::ValidateUnsigned;
+use frame_support::weights::Weight;
+use sp_runtime::traits::{BadOrigin, Dispatchable, SignedExtension};
+use sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource, ValidTransaction};
+use crate::invitations::{self, Invitation, InvitationError};
+use crate::package::{ApprovalPackage, PackageError, SignatureScheme};
+
+/// The approvals of a recovery by the friends at `positions` of its first group.
+fn approvals(positions: &[u32]) -> Vec<ApprovalBitmap> {
+	let mut bitmap = ApprovalBitmap::default();
+	positions.iter().for_each(|position| bitmap.insert(*position));
+	vec![bitmap]
+}
 
 #[test]
 fn basic_setup_works() {
@@ -30,14 +46,14 @@ fn set_recovered_works() {
 		let bob = get_from_seed("bob");
 		let charlie = get_from_seed("charlie");
 		assert_noop!(
-			Recovery::set_recovered(Origin::signed(charlie), alice, bob),
+			Recovery::set_recovered(RuntimeOrigin::signed(charlie), alice, bob),
 			BadOrigin
 		);
 		// Root can set a recovered account though
-		assert_ok!(Recovery::set_recovered(Origin::ROOT, alice, bob));
+		assert_ok!(Recovery::set_recovered(RuntimeOrigin::root(), alice, bob));
 		// Account 1 should now be able to make a call through account 5
-		let call = Box::new(Call::Balances(BalancesCall::transfer(charlie, 10)));
-		assert_ok!(Recovery::as_recovered(Origin::signed(bob), alice, call));
+		let call = Box::new(RuntimeCall::Balances(BalancesCall::transfer_allow_death { dest: charlie, value: 10 }));
+		assert_ok!(Recovery::as_recovered(RuntimeOrigin::signed(bob), alice, call));
 		// Account 1 has successfully drained the funds from account 5
 		assert_eq!(Balances::free_balance(charlie), 110);
 		assert_eq!(Balances::free_balance(alice), 90);
@@ -53,20 +69,25 @@ fn create_recovery_works() {
 		let dave = get_from_seed("dave");
 		let eve = get_from_seed("eve");
 
-		let merkle_tree = MerkleTree::from_vec(&SHA256, vec![charlie, dave, eve]);
-		let friends_merkle_root = merkle_tree.root_hash();
+		let merkle_tree = FriendsTree::new(vec![charlie, dave, eve]);
+		let friends_merkle_root = merkle_tree.root();
 		let threshold = 2;
 		let delay_period = 5;
 		assert_ok!(Recovery::create_recovery(
-			Origin::signed(alice),
+			RuntimeOrigin::signed(alice),
 			friends_merkle_root.to_vec(),
 			threshold,
 			delay_period,
+			FriendCount::new(threshold.into()),
 		));
 		let recovery_config = RecoveryConfig {
-			friends_merkle_root: friends_merkle_root.to_vec(),
-			threshold: threshold,
 			delay_period: delay_period,
+			groups: vec![RecoveryGroup::new(friends_merkle_root.to_vec(), threshold)],
+			policy: GroupPolicy::All,
+			rescuers_merkle_root: None,
+			auto_sweep: false,
+			threshold_key: None,
+			previous_roots: vec![],
 		};
 		assert_eq!(Recovery::recovery_config(alice), Some(recovery_config));
 	});
@@ -81,29 +102,32 @@ fn initiate_recovery_works() {
 		let dave = get_from_seed("dave");
 		let eve = get_from_seed("eve");
 
-		let merkle_tree = MerkleTree::from_vec(&SHA256, vec![charlie, dave, eve]);
+		let merkle_tree = FriendsTree::new(vec![charlie, dave, eve]);
 
-		let friends_merkle_tree = merkle_tree.root_hash();
+		let friends_merkle_tree = merkle_tree.root();
 		assert_noop!(
-			Recovery::initiate_recovery(Origin::signed(bob), alice),
+			Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None),
 			Error::<Test>::NotRecoverable
 		);
 		assert_ok!(Recovery::create_recovery(
-			Origin::signed(alice),
+			RuntimeOrigin::signed(alice),
 			friends_merkle_tree.to_vec(),
 			2,
 			5,
+			FriendCount::new(2),
 		));
-		assert_ok!(Recovery::initiate_recovery(Origin::signed(bob), alice));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
 		assert_noop!(
-			Recovery::initiate_recovery(Origin::signed(bob), alice),
+			Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None),
 			Error::<Test>::AlreadyStarted
 		);
 		assert_eq!(
 			Recovery::active_recovery(alice, bob),
 			Some(ActiveRecovery {
 				created: 1,
-				approved_friends: vec![]
+				approvals: vec![],
+				key_images: vec![],
+				approval_roots: vec![]
 			})
 		);
 	});
@@ -118,38 +142,41 @@ fn approve_recovery_works() {
 		let dave = get_from_seed("dave");
 		let eve = get_from_seed("eve");
 
-		let merkle_tree = MerkleTree::from_vec(&SHA256, vec![charlie, dave, eve]);
-		let merkle_tree2 = MerkleTree::from_vec(&SHA256, vec![charlie, dave]);
+		let merkle_tree = FriendsTree::new(vec![charlie, dave, eve]);
+		let merkle_tree2 = FriendsTree::new(vec![charlie, dave]);
 
-		let friends_merkle_tree = merkle_tree.root_hash();
+		let friends_merkle_tree = merkle_tree.root();
 		assert_ok!(Recovery::create_recovery(
-			Origin::signed(alice),
+			RuntimeOrigin::signed(alice),
 			friends_merkle_tree.to_vec(),
 			2,
 			10,
+			FriendCount::new(2),
 		));
-		assert_ok!(Recovery::initiate_recovery(Origin::signed(bob), alice));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
 
-		let charlie_proof = merkle_tree.gen_proof(charlie).unwrap();
-		let charlie_signature = sign_by_seed("charlie", &bob);
+		let charlie_proof = merkle_tree.proof_for(&charlie).unwrap();
+		let charlie_signature = approve_by_seed("charlie", &alice, &bob, 0, 1);
 		// charlie is not recoverable
 		assert_noop!(
 			Recovery::approve_recovery(
-				Origin::signed(bob),
+				RuntimeOrigin::signed(bob),
 				charlie,
 				bob,
+				0,
 				charlie_signature.clone(),
 				charlie_proof.clone()
 			),
 			Error::<Test>::NotRecoverable
 		);
-		let malicious_signature = sign_by_seed("malicious", &bob);
+		let malicious_signature = approve_by_seed("malicious", &alice, &bob, 0, 1);
 		// malicious signature is invalid, even with charlie's valid proof
 		assert_noop!(
 			Recovery::approve_recovery(
-				Origin::signed(bob),
+				RuntimeOrigin::signed(bob),
 				alice,
 				bob,
+				0,
 				malicious_signature,
 				charlie_proof.clone()
 			),
@@ -157,14 +184,15 @@ fn approve_recovery_works() {
 		);
 
 		// malicious proof is invalid, even with charlie's valid signature
-		let malicious_proof = merkle_tree2.gen_proof(charlie).unwrap();
+		let malicious_proof = merkle_tree2.proof_for(&charlie).unwrap();
 		// assert_eq!(charlie_proof.clone(), malicious_proof.clone());
-		assert_eq!(false, malicious_proof.validate(friends_merkle_tree));
+		assert_eq!(false, malicious_proof.validate(&friends_merkle_tree));
 		assert_noop!(
 			Recovery::approve_recovery(
-				Origin::signed(bob),
+				RuntimeOrigin::signed(bob),
 				alice,
 				bob,
+				0,
 				charlie_signature.clone(),
 				malicious_proof
 			),
@@ -173,9 +201,10 @@ fn approve_recovery_works() {
 
 		// a valid approve by charlie
 		assert_ok!(Recovery::approve_recovery(
-			Origin::signed(bob),
+			RuntimeOrigin::signed(bob),
 			alice,
 			bob,
+			0,
 			charlie_signature.clone(),
 			charlie_proof.clone()
 		));
@@ -183,15 +212,18 @@ fn approve_recovery_works() {
 			Recovery::active_recovery(alice, bob),
 			Some(ActiveRecovery {
 				created: 1,
-				approved_friends: vec![charlie]
+				approvals: approvals(&[charlie_proof.position()]),
+				key_images: vec![],
+				approval_roots: vec![friends_merkle_tree.to_vec()]
 			})
 		);
 		// charlie can't approve twice on the same recovery process
 		assert_noop!(
 			Recovery::approve_recovery(
-				Origin::signed(bob),
+				RuntimeOrigin::signed(bob),
 				alice,
 				bob,
+				0,
 				charlie_signature.clone(),
 				charlie_proof.clone()
 			),
@@ -201,96 +233,2783 @@ fn approve_recovery_works() {
 }
 
 #[test]
-fn claim_recovery_works() {
+fn approvals_are_bound_to_the_chain_and_the_recovery() {
 	new_test_ext().execute_with(|| {
 		let alice = get_from_seed("alice");
 		let bob = get_from_seed("bob");
 		let charlie = get_from_seed("charlie");
 		let dave = get_from_seed("dave");
-		let eve = get_from_seed("eve");
-		let merkle_tree = MerkleTree::from_vec(&SHA256, vec![charlie, dave, eve]);
+		let merkle_tree = FriendsTree::new(vec![charlie, dave]);
+		let charlie_proof = merkle_tree.proof_for(&charlie).unwrap();
+		for owner in [alice, dave] {
+			assert_ok!(Recovery::create_group_recovery(
+				RuntimeOrigin::signed(owner),
+				vec![RecoveryGroup::new(merkle_tree.root(), 1), RecoveryGroup::new(merkle_tree.root(), 1)],
+				GroupPolicy::All,
+				0,
+				None,
+				vec![FriendCount::new(2), FriendCount::new(2)],
+			));
+		}
+		run_to_block(2);
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), dave, None));
+
+		let approve = |lost, group, signature| {
+			Recovery::approve_recovery(RuntimeOrigin::signed(bob), lost, bob, group, signature, charlie_proof.clone())
+		};
+		// the friend's approval of another chain, lost account, group or recovery is rejected
+		let other_chain = approval_payload(&H256::repeat_byte(1), &alice, &bob, 0, &2u64);
+		assert_noop!(approve(alice, 0, sign_by_seed("charlie", &other_chain)), Error::<Test>::SignatureInvalid);
+		assert_noop!(approve(dave, 0, approve_by_seed("charlie", &alice, &bob, 0, 2)), Error::<Test>::SignatureInvalid);
+		assert_noop!(approve(alice, 1, approve_by_seed("charlie", &alice, &bob, 0, 2)), Error::<Test>::SignatureInvalid);
+		assert_noop!(approve(alice, 0, approve_by_seed("charlie", &alice, &bob, 0, 1)), Error::<Test>::SignatureInvalid);
+		assert_ok!(approve(alice, 0, approve_by_seed("charlie", &alice, &bob, 0, 2)));
+		assert_eq!(
+			Recovery::approval_payload(&alice, &bob, 1),
+			Some(approval_payload(&System::block_hash(0), &alice, &bob, 1, &2u64))
+		);
+		assert_eq!(Recovery::approval_payload(&alice, &dave, 0), None);
+	});
+}
 
-		let friends_merkle_tree = merkle_tree.root_hash();
+#[test]
+fn unsigned_approvals_work() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let merkle_tree = FriendsTree::new(vec![charlie, dave]);
 		assert_ok!(Recovery::create_recovery(
-			Origin::signed(alice),
-			friends_merkle_tree.to_vec(),
+			RuntimeOrigin::signed(alice),
+			merkle_tree.root(),
 			2,
-			10,
+			0,
+			FriendCount::new(2),
 		));
-		assert_ok!(Recovery::initiate_recovery(Origin::signed(bob), alice));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
 
-		let charlie_proof = merkle_tree.gen_proof(charlie).unwrap();
-		let charlie_signature = sign_by_seed("charlie", &bob);
+		let charlie_proof = merkle_tree.proof_for(&charlie).unwrap();
+		let call = crate::Call::<Test>::approve_recovery {
+			lost: alice,
+			rescuer: bob,
+			group: 0,
+			signature: approve_by_seed("charlie", &alice, &bob, 0, 1),
+			proof: charlie_proof.clone(),
+		};
+		// a valid approval gets into the pool
+		assert_eq!(
+			<Recovery as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &call),
+			Ok(ValidTransaction {
+				priority: UnsignedPriority::get(),
+				requires: vec![],
+				provides: vec![(b"approve_recovery", alice, bob, 0u16, charlie).encode()],
+				longevity: UnsignedLongevity::get(),
+				propagate: true,
+			})
+		);
+		// a forged one doesn't
+		let forged = crate::Call::<Test>::approve_recovery {
+			lost: alice,
+			rescuer: bob,
+			group: 0,
+			signature: approve_by_seed("dave", &alice, &bob, 0, 1),
+			proof: charlie_proof.clone(),
+		};
+		assert_eq!(
+			<Recovery as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &forged),
+			Err(InvalidTransaction::BadProof.into())
+		);
+		// nor a call other than an approval
+		assert_eq!(
+			<Recovery as ValidateUnsigned>::validate_unsigned(
+				TransactionSource::External,
+				&crate::Call::<Test>::claim_recovery { lost: alice }
+			),
+			Err(InvalidTransaction::Call.into())
+		);
 
-		// a valid approve by charlie
-		assert_ok!(Recovery::approve_recovery(
-			Origin::signed(bob),
+		// charlie has no funds and submits the approval unsigned
+		assert_ok!(RuntimeCall::from(call.clone()).dispatch(RuntimeOrigin::none()));
+		assert_eq!(Recovery::recovery_status(&alice, &bob).unwrap().approvals, vec![1]);
+		// the same approval is now stale
+		assert_eq!(
+			<Recovery as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &call),
+			Err(InvalidTransaction::Stale.into())
+		);
+
+		// root isn't a valid origin for approvals
+		assert_noop!(
+			Recovery::approve_recovery(
+				RuntimeOrigin::root(),
+				alice,
+				bob,
+				0,
+				approve_by_seed("dave", &alice, &bob, 0, 1),
+				merkle_tree.proof_for(&dave).unwrap()
+			),
+			BadOrigin
+		);
+	});
+}
+
+#[test]
+fn unused_weight_is_refunded() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let merkle_tree = FriendsTree::new(vec![charlie]);
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			merkle_tree.root(),
+			1,
+			0,
+			FriendCount::new(1),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+
+		// the first approval is charged as if the recovery held no other approval
+		let proof = merkle_tree.proof_for(&charlie).unwrap();
+		let approval = Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
 			alice,
 			bob,
-			charlie_signature.clone(),
-			charlie_proof.clone()
-		));
+			0,
+			approve_by_seed("charlie", &alice, &bob, 0, 1),
+			proof.clone(),
+		);
+		assert_eq!(
+			approval.unwrap().actual_weight,
+			Some(<() as WeightInfo>::approve_recovery(proof.depth(), 0))
+		);
+
+		// claiming without auto sweep doesn't pay for the sweep
+		let claim = Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice);
+		assert_eq!(claim.unwrap().actual_weight, Some(<() as WeightInfo>::claim_recovery(1)));
 
+		// a recovered call is charged for what it used
+		let transfer = Box::new(RuntimeCall::Balances(BalancesCall::transfer_allow_death { dest: charlie, value: 10 }));
+		let transfer_weight = transfer.get_dispatch_info().weight;
+		let recovered = Recovery::as_recovered(RuntimeOrigin::signed(bob), alice, transfer);
 		assert_eq!(
-			Recovery::active_recovery(alice, bob),
-			Some(ActiveRecovery {
+			recovered.unwrap().actual_weight,
+			Some(<() as WeightInfo>::as_recovered().saturating_add(transfer_weight))
+		);
+	});
+}
+
+#[test]
+fn approvals_are_keyed_by_leaf_position() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let eve = get_from_seed("eve");
+
+		// every leaf has its own position, even the ones carried up a level
+		let merkle_tree = FriendsTree::new(vec![charlie, dave, eve]);
+		let positions: Vec<u32> =
+			[charlie, dave, eve].iter().map(|friend| merkle_tree.proof_for(friend).unwrap().position()).collect();
+		assert_eq!(positions, vec![4, 5, 3]);
+		// in a full tree, the positions follow the leaves
+		let full_tree = FriendsTree::new((0..8).map(|i| get_from_seed(&format!("friend{}", i))).collect());
+		for (index, friend) in full_tree.leaves().iter().enumerate() {
+			assert_eq!(full_tree.proof_for(friend).unwrap().position(), 8 + index as u32);
+		}
+
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			merkle_tree.root(),
+			3,
+			0,
+			FriendCount::new(3),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		for (seed, friend) in [("charlie", charlie), ("dave", dave), ("eve", eve)] {
+			assert_ok!(Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				approve_by_seed(seed, &alice, &bob, 0, 1),
+				merkle_tree.proof_for(&friend).unwrap()
+			));
+		}
+		// the friends' accounts aren't stored, only a byte of bitmap
+		let active_recovery = Recovery::active_recovery(alice, bob).unwrap();
+		assert_eq!(
+			active_recovery,
+			ActiveRecovery {
 				created: 1,
-				approved_friends: vec![charlie]
-			})
+				approvals: approvals(&positions),
+				key_images: vec![],
+				approval_roots: vec![merkle_tree.root()]
+			}
 		);
+		assert_eq!(active_recovery.approvals.encode().len(), 3);
+		assert_eq!(Recovery::recovery_status(&alice, &bob).map(|status| status.approvals), Some(vec![3]));
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+	});
+}
+
+#[test]
+fn approval_bitmaps_are_bounded() {
+	let last = MAX_APPROVAL_BITMAP_LEN * 8 - 1;
+	let mut bitmap = ApprovalBitmap::default();
+	assert!(bitmap.insert(last));
+	assert!(!bitmap.insert(last + 1));
+	assert!(bitmap.contains(last) && !bitmap.contains(last + 1));
+	assert_eq!(bitmap.count(), 1);
+	// longer bitmaps can't be decoded
+	assert_eq!(ApprovalBitmap::decode(&mut &bitmap.encode()[..]).ok(), Some(bitmap));
+	let too_long = vec![0u8; MAX_APPROVAL_BITMAP_LEN as usize + 1];
+	assert!(ApprovalBitmap::decode(&mut &too_long.encode()[..]).is_err());
+	// the mock's `MaxFriends` fits
+	<Recovery as Hooks<u64>>::integrity_test();
+}
+
+#[test]
+fn approvals_deeper_than_max_friends_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		// MaxFriends is 16, so proofs can be 4 deep
+		assert_eq!(Recovery::max_proof_depth(), 4);
+		let mut friends: Vec<_> = (0..16).map(|i| get_from_seed(&format!("friend{}", i))).collect();
+		friends.push(charlie);
+		let merkle_tree = FriendsTree::new(friends);
+		let charlie_proof = merkle_tree.proof_for(&charlie).unwrap();
+		assert_eq!(charlie_proof.depth(), 1);
+		let first_proof = merkle_tree.proof_for(&merkle_tree.leaves()[0]).unwrap();
+		assert_eq!(first_proof.depth(), 5);
 
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			merkle_tree.root(),
+			1,
+			0,
+			FriendCount::new(1),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
 		assert_noop!(
-			Recovery::claim_recovery(Origin::signed(bob), alice),
-			Error::<Test>::DelayPeriod
+			Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				approve_by_seed("friend0", &alice, &bob, 0, 1),
+				first_proof
+			),
+			Error::<Test>::ProofTooDeep
 		);
-		run_to_block(11);
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			approve_by_seed("charlie", &alice, &bob, 0, 1),
+			charlie_proof
+		));
+	});
+}
+
+#[test]
+fn padded_trees_hide_the_number_of_friends() {
+	let charlie = get_from_seed("charlie");
+	let dave = get_from_seed("dave");
+	let seed = [7; 32];
+	let tree = FriendsTree::padded(vec![charlie, dave], 3, &seed).unwrap();
+	// every proof has the padded depth, wherever the friend ended up
+	for friend in [charlie, dave] {
+		let proof = tree.proof_for(&friend).unwrap();
+		assert_eq!(proof.depth(), 3);
+		assert!(proof.validate(&tree.root()));
+	}
+	// the same seed builds the same tree, another one doesn't
+	assert_eq!(FriendsTree::padded(vec![charlie, dave], 3, &seed).unwrap().root(), tree.root());
+	assert_ne!(FriendsTree::padded(vec![charlie, dave], 3, &[8; 32]).unwrap().root(), tree.root());
+	assert_ne!(FriendsTree::padded(vec![charlie], 3, &seed).unwrap().root(), tree.root());
+	assert_eq!(FriendsTree::padded(vec![charlie, dave], 0, &seed), None);
+}
+
+#[test]
+fn padded_groups_only_accept_proofs_of_their_depth() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let padded_tree = FriendsTree::padded(vec![charlie, dave], 3, &[7; 32]).unwrap();
+
+		// MaxFriends is 16, so trees can be padded up to depth 4
 		assert_noop!(
-			Recovery::claim_recovery(Origin::signed(bob), alice),
-			Error::<Test>::UnderThreshold
+			Recovery::create_group_recovery(
+				RuntimeOrigin::signed(alice),
+				vec![RecoveryGroup::padded(padded_tree.root(), 1, 5)],
+				GroupPolicy::All,
+				0,
+				None,
+				vec![FriendCount::new(2)],
+			),
+			Error::<Test>::InvalidPaddedDepth
 		);
-		let dave_proof = merkle_tree.gen_proof(dave).unwrap();
-		let dave_signature = sign_by_seed("dave", &bob);
+		assert_ok!(Recovery::create_group_recovery(
+			RuntimeOrigin::signed(alice),
+			vec![RecoveryGroup::padded(padded_tree.root(), 2, 3)],
+			GroupPolicy::All,
+			0,
+			None,
+			vec![FriendCount::new(2)],
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
 
-		// a valid approve by dave
-		assert_ok!(Recovery::approve_recovery(
-			Origin::signed(bob),
+		// a proof of another depth is rejected before its merkle path is even checked
+		let unpadded_proof = FriendsTree::new(vec![charlie, dave]).proof_for(&charlie).unwrap();
+		assert_noop!(
+			Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				approve_by_seed("charlie", &alice, &bob, 0, 1),
+				unpadded_proof
+			),
+			Error::<Test>::ProofDepthMismatch
+		);
+		for (seed, friend) in [("charlie", charlie), ("dave", dave)] {
+			assert_ok!(Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				approve_by_seed(seed, &alice, &bob, 0, 1),
+				padded_tree.proof_for(&friend).unwrap()
+			));
+		}
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+	});
+}
+
+/// The opening of charlie's approval of the recovery of alice by bob, in a tree of charlie and dave.
+fn charlie_opening(salt: u8) -> (Vec<u8>, ApprovalOpening<sr25519::Public>) {
+	let alice = get_from_seed("alice");
+	let bob = get_from_seed("bob");
+	let charlie = get_from_seed("charlie");
+	let merkle_tree = FriendsTree::new(vec![charlie, get_from_seed("dave")]);
+	let opening = ApprovalOpening {
+		group: 0,
+		signature: approve_by_seed("charlie", &alice, &bob, 0, 1),
+		proof: merkle_tree.proof_for(&charlie).unwrap(),
+		salt: [salt; 32],
+	};
+	(merkle_tree.root(), opening)
+}
+
+#[test]
+fn committed_approvals_are_revealed_together() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let dave = get_from_seed("dave");
+		let (root, charlie_opening) = charlie_opening(1);
+		let merkle_tree = FriendsTree::new(vec![get_from_seed("charlie"), dave]);
+		let dave_opening = ApprovalOpening {
+			group: 0,
+			signature: approve_by_seed("dave", &alice, &bob, 0, 1),
+			proof: merkle_tree.proof_for(&dave).unwrap(),
+			salt: [2; 32],
+		};
+		let charlie_commitment = approval_commitment(&alice, &bob, &charlie_opening);
+		let dave_commitment = approval_commitment(&alice, &bob, &dave_opening);
+
+		assert_noop!(
+			Recovery::commit_approval(RuntimeOrigin::signed(bob), alice, bob, charlie_commitment),
+			Error::<Test>::NotStarted
+		);
+		assert_ok!(Recovery::create_recovery(RuntimeOrigin::signed(alice), root, 2, 10, FriendCount::new(2)));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		// the rescuer commits on behalf of charlie and dave, with a deposit for each commitment
+		assert_ok!(Recovery::commit_approval(RuntimeOrigin::signed(bob), alice, bob, charlie_commitment));
+		assert_ok!(Recovery::commit_approval(RuntimeOrigin::signed(bob), alice, bob, dave_commitment));
+		assert_noop!(
+			Recovery::commit_approval(RuntimeOrigin::signed(bob), alice, bob, dave_commitment),
+			Error::<Test>::AlreadyCommitted
+		);
+		assert_eq!(Balances::reserved_balance(bob), 10);
+		// the commitments don't tell who approved
+		assert_eq!(Recovery::active_recovery(alice, bob).unwrap().total_approvals(), 0);
+
+		run_to_block(6);
+		// only the committer can reveal its commitments
+		assert_ok!(Recovery::reveal_approvals(
+			RuntimeOrigin::signed(dave),
 			alice,
 			bob,
-			dave_signature.clone(),
-			dave_proof.clone()
+			vec![charlie_opening.clone(), dave_opening.clone()]
+		));
+		System::assert_last_event(Event::ApprovalsRevealed(alice, bob, 0, 2).into());
+		assert_ok!(Recovery::reveal_approvals(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			vec![charlie_opening, dave_opening]
 		));
+		System::assert_last_event(Event::ApprovalsRevealed(alice, bob, 2, 0).into());
+		assert_eq!(Recovery::active_recovery(alice, bob).unwrap().total_approvals(), 2);
+		assert_eq!(ApprovalCommitments::<Test>::get((alice, bob, bob)), vec![]);
+		assert_eq!(Balances::reserved_balance(bob), 0);
 
-		let mut approved_friends = vec![charlie, dave];
-		approved_friends.sort_unstable();
-		assert_eq!(
-			Recovery::active_recovery(alice, bob),
-			Some(ActiveRecovery {
-				created: 1,
-				approved_friends: approved_friends
-			})
-		);
+		run_to_block(11);
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+	});
+}
+
+#[test]
+fn only_matured_and_valid_openings_are_counted() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let (root, opening) = charlie_opening(1);
+		let mut forged = opening.clone();
+		forged.signature = approve_by_seed("eve", &alice, &bob, 0, 1);
+		forged.salt = [3; 32];
+		let mut uncommitted = opening.clone();
+		uncommitted.salt = [4; 32];
 
-		assert_ok!(Recovery::claim_recovery(Origin::signed(bob), alice));
+		assert_ok!(Recovery::create_recovery(RuntimeOrigin::signed(alice), root, 1, 0, FriendCount::new(1)));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		for commitment in [&opening, &forged] {
+			assert_ok!(Recovery::commit_approval(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				approval_commitment(&alice, &bob, commitment)
+			));
+		}
 
-		let call = Box::new(Call::Balances(BalancesCall::transfer(charlie, 10)));
-		assert_ok!(Recovery::as_recovered(Origin::signed(bob), alice, call));
-		// Account bob has successfully drained the funds from account alice
-		assert_eq!(Balances::free_balance(charlie), 110);
-		assert_eq!(Balances::free_balance(alice), 90);
+		// too early: nothing is counted, and the commitments stay pending
+		run_to_block(5);
+		assert_ok!(Recovery::reveal_approvals(RuntimeOrigin::signed(bob), alice, bob, vec![opening.clone()]));
+		System::assert_last_event(Event::ApprovalsRevealed(alice, bob, 0, 1).into());
+		assert_eq!(ApprovalCommitments::<Test>::get((alice, bob, bob)).len(), 2);
+		assert_eq!(Balances::reserved_balance(bob), 10);
+
+		// the forged signature and the opening of no commitment are rejected
+		run_to_block(6);
+		assert_ok!(Recovery::reveal_approvals(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			vec![forged.clone(), uncommitted, opening.clone()]
+		));
+		System::assert_last_event(Event::ApprovalsRevealed(alice, bob, 1, 2).into());
+		assert_eq!(Recovery::active_recovery(alice, bob).unwrap().total_approvals(), 1);
+		// opened commitments are used up, valid or not, and their deposits returned
+		assert_eq!(ApprovalCommitments::<Test>::get((alice, bob, bob)), vec![]);
+		assert_eq!(Balances::reserved_balance(bob), 0);
+		assert_ok!(Recovery::reveal_approvals(RuntimeOrigin::signed(bob), alice, bob, vec![forged, opening]));
+		System::assert_last_event(Event::ApprovalsRevealed(alice, bob, 0, 2).into());
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
 	});
 }
 
 #[test]
-fn merkle_tree_proof() {
+fn commitments_are_capped_per_committer() {
 	new_test_ext().execute_with(|| {
 		let alice = get_from_seed("alice");
 		let bob = get_from_seed("bob");
+		let dave = get_from_seed("dave");
 		let eve = get_from_seed("eve");
-		let tree = MerkleTree::from_vec(&SHA256, vec![alice.clone(), bob.clone(), eve.clone()]);
+		let (root, opening) = charlie_opening(1);
 
-		let proof = tree.gen_proof(alice.clone()).unwrap();
-		assert_eq!(true, proof.validate(tree.root_hash()));
-		assert_eq!(alice, proof.value);
+		assert_ok!(Recovery::create_recovery(RuntimeOrigin::signed(alice), root, 1, 0, FriendCount::new(1)));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		// eve fills up their own commitments with junk, paying a deposit for each
+		for i in 0..MaxApprovals::get() {
+			assert_ok!(Recovery::commit_approval(RuntimeOrigin::signed(eve), alice, bob, [i as u8; 32]));
+		}
+		assert_eq!(Balances::reserved_balance(eve), 50);
+		assert_noop!(
+			Recovery::commit_approval(RuntimeOrigin::signed(eve), alice, bob, [0xff; 32]),
+			Error::<Test>::TooManyCommitments
+		);
+		// which leaves the commitments of others alone
+		assert_ok!(Recovery::commit_approval(
+			RuntimeOrigin::signed(dave),
+			alice,
+			bob,
+			approval_commitment(&alice, &bob, &opening)
+		));
+		// accounts that can't pay the deposit can't commit
+		let nobody = get_from_seed("nobody");
+		assert_noop!(
+			Recovery::commit_approval(RuntimeOrigin::signed(nobody), alice, bob, [0; 32]),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+
+		run_to_block(6);
+		assert_ok!(Recovery::reveal_approvals(RuntimeOrigin::signed(dave), alice, bob, vec![opening]));
+		System::assert_last_event(Event::ApprovalsRevealed(alice, bob, 1, 0).into());
+	});
+}
+
+#[test]
+fn claiming_clears_the_pending_commitments() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let dave = get_from_seed("dave");
+		let eve = get_from_seed("eve");
+		let (root, opening) = charlie_opening(1);
+		let commitment = approval_commitment(&alice, &bob, &opening);
+
+		assert_ok!(Recovery::create_recovery(RuntimeOrigin::signed(alice), root, 1, 0, FriendCount::new(1)));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		assert_ok!(Recovery::commit_approval(RuntimeOrigin::signed(bob), alice, bob, commitment));
+		assert_ok!(Recovery::commit_approval(RuntimeOrigin::signed(dave), alice, bob, commitment));
+		assert_ok!(Recovery::commit_approval(RuntimeOrigin::signed(eve), alice, bob, [0; 32]));
+		assert_ok!(Recovery::commit_approval(RuntimeOrigin::signed(eve), alice, bob, [1; 32]));
+
+		run_to_block(6);
+		assert_ok!(Recovery::reveal_approvals(RuntimeOrigin::signed(bob), alice, bob, vec![opening]));
+		assert_eq!(Balances::reserved_balance(dave), 5);
+		assert_eq!(Balances::reserved_balance(eve), 10);
+
+		let call = RuntimeCall::Recovery(crate::Call::claim_recovery { lost: alice });
+		let call_weight = call.get_dispatch_info().weight;
+		let post_info = Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice).unwrap();
+		// the commitments left pending are dropped, and their deposits returned
+		assert_eq!(ApprovalCommitments::<Test>::iter_prefix((alice, bob)).count(), 0);
+		assert_eq!(Balances::reserved_balance(dave), 0);
+		assert_eq!(Balances::reserved_balance(eve), 0);
+		assert_eq!(Balances::free_balance(eve), 100);
+		// the committers the recovery didn't have are refunded
+		assert!(post_info.actual_weight.unwrap().all_lt(call_weight));
+	});
+}
+
+#[test]
+fn frost_signatures_are_ed25519_signatures() {
+	let (group_key, shares) = frost_friends(2, 3);
+	let message = b"recover alice";
+	let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+	for signers in [&shares[..2], &shares[1..], &[shares[0].clone(), shares[2].clone()][..], &shares[..]] {
+		let signature = frost::threshold_sign(signers, message, &mut rng).unwrap();
+		assert!(ed25519::Pair::verify(
+			&ed25519::Signature::from_raw(signature),
+			&message[..],
+			&ed25519::Public::from_raw(group_key)
+		));
+	}
+	// a single friend can't sign alone
+	let signature = frost::threshold_sign(&shares[..1], message, &mut rng).unwrap();
+	assert!(!ed25519::Pair::verify(&ed25519::Signature::from_raw(signature), &message[..], &ed25519::Public::from_raw(group_key)));
+	assert_eq!(frost::trusted_dealer_keygen(4, 3, &mut rng).map(|_| ()), Err(frost::FrostError::InvalidThreshold));
+}
+
+#[test]
+fn claim_with_threshold_signature_works() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let (group_key, shares) = frost_friends(2, 3);
+		let threshold_key = ThresholdKey { group_key, threshold: 2, signers: 3 };
+
+		assert_noop!(
+			Recovery::set_threshold_key(RuntimeOrigin::signed(alice), Some(threshold_key.clone())),
+			Error::<Test>::NotRecoverable
+		);
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			vec![1; MERKLE_ROOT_LENGTH],
+			2,
+			10,
+			FriendCount::new(2),
+		));
+		assert_noop!(
+			Recovery::set_threshold_key(
+				RuntimeOrigin::signed(alice),
+				Some(ThresholdKey { group_key, threshold: 4, signers: 3 })
+			),
+			Error::<Test>::InvalidThresholdKey
+		);
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		run_to_block(11);
+		assert_noop!(
+			Recovery::claim_with_threshold_signature(RuntimeOrigin::signed(bob), alice, frost_sign(&shares[..2], &alice, &bob, 1)),
+			Error::<Test>::NoThresholdKey
+		);
+		assert_ok!(Recovery::set_threshold_key(RuntimeOrigin::signed(alice), Some(threshold_key.clone())));
+		System::assert_last_event(Event::ThresholdKeySet(alice, Some(group_key)).into());
+		assert_eq!(Recovery::recovery_config(alice).unwrap().threshold_key(), Some(&threshold_key));
+
+		// below the threshold, or for another recovery or chain, the signature doesn't verify
+		for signature in [
+			frost_sign(&shares[..1], &alice, &bob, 1),
+			frost_sign(&shares[..2], &alice, &charlie, 1),
+			frost_sign(&shares[..2], &alice, &bob, 2),
+			frost_sign_payload(&shares[..2], &threshold_recovery_payload(&H256::repeat_byte(1), &alice, &bob, &1u64)),
+		] {
+			assert_noop!(
+				Recovery::claim_with_threshold_signature(RuntimeOrigin::signed(bob), alice, signature),
+				Error::<Test>::ThresholdSignatureInvalid
+			);
+		}
+		// no approvals needed, any two friends sign for all of them
+		assert_ok!(Recovery::claim_with_threshold_signature(
+			RuntimeOrigin::signed(bob),
+			alice,
+			frost_sign(&shares[1..], &alice, &bob, 1)
+		));
+		System::assert_last_event(Event::AccountRecovered(alice, bob).into());
+		assert_eq!(Recovery::proxy(bob), Some(alice));
+		assert_noop!(
+			Recovery::claim_with_threshold_signature(RuntimeOrigin::signed(bob), alice, frost_sign(&shares[1..], &alice, &bob, 1)),
+			Error::<Test>::AlreadyProxied
+		);
+	});
+}
+
+#[test]
+fn threshold_signatures_wait_for_the_delay_period() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let (group_key, shares) = frost_friends(2, 3);
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			vec![1; MERKLE_ROOT_LENGTH],
+			2,
+			10,
+			FriendCount::new(2),
+		));
+		assert_ok!(Recovery::set_threshold_key(
+			RuntimeOrigin::signed(alice),
+			Some(ThresholdKey { group_key, threshold: 2, signers: 3 })
+		));
+		assert_noop!(
+			Recovery::claim_with_threshold_signature(RuntimeOrigin::signed(bob), alice, frost_sign(&shares, &alice, &bob, 1)),
+			Error::<Test>::NotStarted
+		);
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		run_to_block(10);
+		assert_noop!(
+			Recovery::claim_with_threshold_signature(RuntimeOrigin::signed(bob), alice, frost_sign(&shares, &alice, &bob, 1)),
+			Error::<Test>::DelayPeriod
+		);
+		run_to_block(11);
+		assert_ok!(Recovery::claim_with_threshold_signature(RuntimeOrigin::signed(bob), alice, frost_sign(&shares, &alice, &bob, 1)));
+	});
+}
+
+/// The BLS key of a mock friend.
+fn bls_friend(seed: &str) -> bls::BlsSecretKey {
+	bls::BlsSecretKey::from_seed(&sp_io::hashing::blake2_256(seed.as_bytes()))
+}
+
+#[test]
+fn bls_keys_prove_possession() {
+	let charlie = bls_friend("charlie");
+	let dave = bls_friend("dave");
+	assert!(bls::verify_possession(&charlie.public_key(), &charlie.proof_of_possession()));
+	assert!(!bls::verify_possession(&dave.public_key(), &charlie.proof_of_possession()));
+	// a signature isn't a proof of possession
+	assert!(!bls::verify_possession(&charlie.public_key(), &charlie.sign(&charlie.public_key())));
+	assert!(!bls::verify_possession(&[0; 48], &charlie.proof_of_possession()));
+}
+
+#[test]
+fn aggregated_approvals_work() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let friends: Vec<_> = ["charlie", "dave", "eve"].iter().map(|seed| bls_friend(seed)).collect();
+		let merkle_tree = FriendsTree::new(friends.iter().map(bls::BlsSecretKey::public_key).collect());
+		let proofs: Vec<_> =
+			friends.iter().map(|friend| merkle_tree.proof_for(&friend.public_key()).unwrap()).collect();
+		let payload = bls_approval_payload(&System::block_hash(0), &alice, &bob, 0, &1u64);
+		let signatures: Vec<_> = friends.iter().map(|friend| friend.sign(&payload)).collect();
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			merkle_tree.root(),
+			3,
+			0,
+			FriendCount::new(3),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+
+		// the signature must cover exactly the proven friends
+		let two_signatures = bls::aggregate(&signatures[..2]).unwrap();
+		assert_noop!(
+			Recovery::approve_recovery_aggregated(RuntimeOrigin::signed(bob), alice, bob, 0, proofs.clone(), two_signatures),
+			Error::<Test>::SignatureInvalid
+		);
+		let other_payload = bls_approval_payload(&System::block_hash(0), &alice, &get_from_seed("eve"), 0, &1u64);
+		let other_signature =
+			bls::aggregate(&friends.iter().map(|friend| friend.sign(&other_payload)).collect::<Vec<_>>()).unwrap();
+		assert_noop!(
+			Recovery::approve_recovery_aggregated(RuntimeOrigin::signed(bob), alice, bob, 0, proofs.clone(), other_signature),
+			Error::<Test>::SignatureInvalid
+		);
+		// the same friend can't be counted twice
+		assert_noop!(
+			Recovery::approve_recovery_aggregated(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				vec![proofs[0].clone(), proofs[0].clone()],
+				bls::aggregate(&[signatures[0], signatures[0]]).unwrap()
+			),
+			Error::<Test>::AlreadyApproved
+		);
+		// keys outside the tree aren't friends
+		let stranger = bls_friend("stranger");
+		let mut stranger_proof = proofs[0].clone();
+		stranger_proof.value = stranger.public_key();
+		assert_noop!(
+			Recovery::approve_recovery_aggregated(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				vec![stranger_proof],
+				stranger.sign(&payload)
+			),
+			Error::<Test>::MerkleProofInvalid
+		);
+
+		assert_ok!(Recovery::approve_recovery_aggregated(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			proofs[..2].to_vec(),
+			two_signatures
+		));
+		System::assert_last_event(Event::AggregatedApproval(alice, bob, 2).into());
+		assert_noop!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice), Error::<Test>::UnderThreshold);
+		// the last friend approves on its own, on top of the others
+		assert_ok!(Recovery::approve_recovery_aggregated(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			proofs[2..].to_vec(),
+			signatures[2]
+		));
+		assert_eq!(Recovery::active_recovery(alice, bob).unwrap().approvals, approvals(&[4, 5, 3]));
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+	});
+}
+
+#[test]
+fn aggregated_approvals_of_a_closed_recovery_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let friends: Vec<_> = ["charlie", "dave"].iter().map(|seed| bls_friend(seed)).collect();
+		let merkle_tree = FriendsTree::new(friends.iter().map(bls::BlsSecretKey::public_key).collect());
+		let proofs: Vec<_> =
+			friends.iter().map(|friend| merkle_tree.proof_for(&friend.public_key()).unwrap()).collect();
+		let sign = |payload: Vec<u8>| {
+			bls::aggregate(&friends.iter().map(|friend| friend.sign(&payload)).collect::<Vec<_>>()).unwrap()
+		};
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			merkle_tree.root(),
+			2,
+			0,
+			FriendCount::new(2),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		let first_signature = sign(bls_approval_payload(&System::block_hash(0), &alice, &bob, 0, &1u64));
+
+		// alice closes the recovery, and bob starts it over later
+		ActiveRecoveries::<Test>::remove(&alice, &bob);
+		run_to_block(5);
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		assert_noop!(
+			Recovery::approve_recovery_aggregated(RuntimeOrigin::signed(bob), alice, bob, 0, proofs.clone(), first_signature),
+			Error::<Test>::SignatureInvalid
+		);
+		// signatures for another chain or another group don't count either
+		let other_chain = sign(bls_approval_payload(&H256::repeat_byte(1), &alice, &bob, 0, &5u64));
+		assert_noop!(
+			Recovery::approve_recovery_aggregated(RuntimeOrigin::signed(bob), alice, bob, 0, proofs.clone(), other_chain),
+			Error::<Test>::SignatureInvalid
+		);
+		let other_group = sign(bls_approval_payload(&System::block_hash(0), &alice, &bob, 1, &5u64));
+		assert_noop!(
+			Recovery::approve_recovery_aggregated(RuntimeOrigin::signed(bob), alice, bob, 0, proofs.clone(), other_group),
+			Error::<Test>::SignatureInvalid
+		);
+
+		let signature = sign(bls_approval_payload(&System::block_hash(0), &alice, &bob, 0, &5u64));
+		assert_ok!(Recovery::approve_recovery_aggregated(RuntimeOrigin::signed(bob), alice, bob, 0, proofs, signature));
+	});
+}
+
+/// The ring signature of the friend of `seed` approving, in `group`, the recovery of `lost` by
+/// `rescuer` initiated at block `created`.
+fn ring_approval(
+	seed: &str,
+	ring: &[sr25519::Public],
+	lost: &sr25519::Public,
+	rescuer: &sr25519::Public,
+	group: u16,
+	created: u64,
+) -> lsag::RingSignature {
+	let pair = <sr25519::Pair>::from_string(&format!("//{}", seed), None).unwrap();
+	let members: Vec<[u8; 32]> = ring.iter().map(|member| member.0).collect();
+	let payload = ring_approval_payload(&System::block_hash(0), lost, rescuer, group, &created);
+	lsag::sign_with_pair(&members, &pair, &payload).unwrap()
+}
+
+#[test]
+fn ring_signatures_link_only_within_a_recovery() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let ring = vec![get_from_seed("charlie"), get_from_seed("dave"), get_from_seed("eve")];
+		let members: Vec<[u8; 32]> = ring.iter().map(|member| member.0).collect();
+		let payload = ring_approval_payload(&System::block_hash(0), &alice, &bob, 0, &1u64);
+
+		let signature = ring_approval("charlie", &ring, &alice, &bob, 0, 1);
+		assert!(lsag::verify(&members, &payload, &signature));
+		let other_rescuer = ring_approval_payload(&System::block_hash(0), &alice, &alice, 0, &1u64);
+		assert!(!lsag::verify(&members, &other_rescuer, &signature));
+		assert!(!lsag::verify(&members[..2], &payload, &signature));
+		// the same friend always has the same image in a recovery, another one elsewhere
+		let reordered: Vec<_> = ring.iter().rev().cloned().collect();
+		let other_signature = ring_approval("charlie", &reordered, &alice, &bob, 0, 1);
+		assert_ne!(other_signature, signature);
+		assert_eq!(other_signature.key_image, signature.key_image);
+		assert_ne!(ring_approval("dave", &ring, &alice, &bob, 0, 1).key_image, signature.key_image);
+		assert_ne!(ring_approval("charlie", &ring, &bob, &alice, 0, 1).key_image, signature.key_image);
+		assert_ne!(ring_approval("charlie", &ring, &alice, &bob, 1, 1).key_image, signature.key_image);
+		assert_ne!(ring_approval("charlie", &ring, &alice, &bob, 0, 2).key_image, signature.key_image);
+		let pair = <sr25519::Pair>::from_string("//charlie", None).unwrap();
+		let other_chain = ring_approval_payload(&H256::repeat_byte(1), &alice, &bob, 0, &1u64);
+		assert_ne!(lsag::sign_with_pair(&members, &pair, &other_chain).unwrap().key_image, signature.key_image);
+		// only members can sign
+		let outsider = <sr25519::Pair>::from_string("//bob", None).unwrap();
+		assert_eq!(lsag::sign_with_pair(&members, &outsider, &payload), None);
+	});
+}
+
+#[test]
+fn anonymous_approvals_of_a_closed_recovery_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let ring = vec![get_from_seed("charlie"), get_from_seed("dave"), get_from_seed("eve")];
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			FriendsTree::new(ring.clone()).root(),
+			2,
+			0,
+			FriendCount::new(3),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		let first_signature = ring_approval("charlie", &ring, &alice, &bob, 0, 1);
+		assert_ok!(Recovery::approve_recovery_anonymously(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			ring.clone(),
+			first_signature.clone()
+		));
+
+		// alice closes the recovery, and bob starts it over later
+		ActiveRecoveries::<Test>::remove(&alice, &bob);
+		run_to_block(5);
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		assert_noop!(
+			Recovery::approve_recovery_anonymously(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				ring.clone(),
+				first_signature.clone()
+			),
+			Error::<Test>::SignatureInvalid
+		);
+		// charlie approves again, with an image unlinkable to its first one
+		let signature = ring_approval("charlie", &ring, &alice, &bob, 0, 5);
+		assert_ne!(signature.key_image, first_signature.key_image);
+		assert_ok!(Recovery::approve_recovery_anonymously(RuntimeOrigin::signed(bob), alice, bob, 0, ring, signature));
+	});
+}
+
+#[test]
+fn ring_secrets_are_the_account_scalars() {
+	let payload = ring_approval_payload(&H256::zero(), &get_from_seed("alice"), &get_from_seed("bob"), 0, &1u64);
+	for i in 0..32u8 {
+		let pair = <sr25519::Pair>::from_seed(&[i; 32]);
+		let secret = lsag::secret_scalar(&pair).expect("the reduced scalar is the account's");
+		let members = vec![get_from_seed("charlie").0, pair.public().0];
+		let signature = lsag::sign(&members, &secret.to_bytes(), &payload).unwrap();
+		assert!(lsag::verify(&members, &payload, &signature));
+		assert_eq!(lsag::sign_with_pair(&members, &pair, &payload), Some(signature));
+	}
+}
+
+#[test]
+fn anonymous_approvals_work() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let ring = vec![get_from_seed("charlie"), get_from_seed("dave"), get_from_seed("eve")];
+		let merkle_tree = FriendsTree::new(ring.clone());
+		let others = FriendsTree::new(vec![get_from_seed("ferdie"), get_from_seed("charlie")]);
+		assert_ok!(Recovery::create_group_recovery(
+			RuntimeOrigin::signed(alice),
+			vec![RecoveryGroup::new(merkle_tree.root(), 2), RecoveryGroup::new(others.root(), 1)],
+			GroupPolicy::All,
+			0,
+			None,
+			vec![FriendCount::new(3), FriendCount::new(2)]
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+
+		// the ring must be the whole group
+		assert_noop!(
+			Recovery::approve_recovery_anonymously(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				ring[..2].to_vec(),
+				ring_approval("charlie", &ring[..2], &alice, &bob, 0, 1)
+			),
+			Error::<Test>::MerkleProofInvalid
+		);
+		assert_noop!(
+			Recovery::approve_recovery_anonymously(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				ring.clone(),
+				ring_approval("charlie", &ring, &alice, &alice, 0, 1)
+			),
+			Error::<Test>::SignatureInvalid
+		);
+		for seed in ["charlie", "eve"] {
+			assert_ok!(Recovery::approve_recovery_anonymously(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				ring.clone(),
+				ring_approval(seed, &ring, &alice, &bob, 0, 1)
+			));
+			System::assert_last_event(Event::AnonymousApproval(alice, bob, 0).into());
+		}
+		assert_noop!(
+			Recovery::approve_recovery_anonymously(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				ring.clone(),
+				ring_approval("charlie", &ring, &alice, &bob, 0, 1)
+			),
+			Error::<Test>::AlreadyApproved
+		);
+		// nor can a friend who approved anonymously approve again with its proof
+		assert_noop!(
+			Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				approve_by_seed("charlie", &alice, &bob, 0, 1),
+				merkle_tree.proof_for(&get_from_seed("charlie")).unwrap()
+			),
+			Error::<Test>::MixedApprovals
+		);
+		// and the other way round in a group approved with proofs
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			1,
+			approve_by_seed("charlie", &alice, &bob, 1, 1),
+			others.proof_for(&get_from_seed("charlie")).unwrap()
+		));
+		let others_ring = others.leaves().to_vec();
+		assert_noop!(
+			Recovery::approve_recovery_anonymously(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				1,
+				others_ring.clone(),
+				ring_approval("ferdie", &others_ring, &alice, &bob, 1, 1)
+			),
+			Error::<Test>::MixedApprovals
+		);
+
+		assert_eq!(Recovery::recovery_status(&alice, &bob).map(|status| status.approvals), Some(vec![2, 1]));
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+	});
+}
+
+#[test]
+fn claim_recovery_works() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let eve = get_from_seed("eve");
+		let merkle_tree = FriendsTree::new(vec![charlie, dave, eve]);
+
+		let friends_merkle_tree = merkle_tree.root();
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			friends_merkle_tree.to_vec(),
+			2,
+			10,
+			FriendCount::new(2),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+
+		let charlie_proof = merkle_tree.proof_for(&charlie).unwrap();
+		let charlie_signature = approve_by_seed("charlie", &alice, &bob, 0, 1);
+
+		// a valid approve by charlie
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			charlie_signature.clone(),
+			charlie_proof.clone()
+		));
+
+		assert_eq!(
+			Recovery::active_recovery(alice, bob),
+			Some(ActiveRecovery {
+				created: 1,
+				approvals: approvals(&[charlie_proof.position()]),
+				key_images: vec![],
+				approval_roots: vec![friends_merkle_tree.to_vec()]
+			})
+		);
+
+		assert_noop!(
+			Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice),
+			Error::<Test>::DelayPeriod
+		);
+		run_to_block(11);
+		assert_noop!(
+			Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice),
+			Error::<Test>::UnderThreshold
+		);
+		let dave_proof = merkle_tree.proof_for(&dave).unwrap();
+		let dave_signature = approve_by_seed("dave", &alice, &bob, 0, 1);
+
+		// a valid approve by dave
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			dave_signature.clone(),
+			dave_proof.clone()
+		));
+
+		assert_eq!(
+			Recovery::active_recovery(alice, bob),
+			Some(ActiveRecovery {
+				created: 1,
+				approvals: approvals(&[charlie_proof.position(), dave_proof.position()]),
+				key_images: vec![],
+				approval_roots: vec![friends_merkle_tree.to_vec()]
+			})
+		);
+
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+
+		let call = Box::new(RuntimeCall::Balances(BalancesCall::transfer_allow_death { dest: charlie, value: 10 }));
+		assert_ok!(Recovery::as_recovered(RuntimeOrigin::signed(bob), alice, call));
+		// Account bob has successfully drained the funds from account alice
+		assert_eq!(Balances::free_balance(charlie), 110);
+		assert_eq!(Balances::free_balance(alice), 90);
+	});
+}
+
+#[test]
+fn create_group_recovery_checks_input() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let eve = get_from_seed("eve");
+
+		let family_tree = FriendsTree::new(vec![charlie, dave]);
+		let custodian_tree = FriendsTree::new(vec![eve]);
+		let family = RecoveryGroup::new(family_tree.root(), 2);
+		let custodian = RecoveryGroup::new(custodian_tree.root(), 1);
+
+		assert_noop!(
+			Recovery::create_group_recovery(RuntimeOrigin::signed(alice), vec![], GroupPolicy::All, 10, None, vec![]),
+			Error::<Test>::NoGroups
+		);
+		assert_noop!(
+			Recovery::create_group_recovery(
+				RuntimeOrigin::signed(alice),
+				vec![family.clone(); 4],
+				GroupPolicy::All,
+				10,
+				None,
+				vec![FriendCount::new(2); 4]
+			),
+			Error::<Test>::TooManyGroups
+		);
+		assert_noop!(
+			Recovery::create_group_recovery(
+				RuntimeOrigin::signed(alice),
+				vec![family.clone(), RecoveryGroup::new(custodian_tree.root(), 0)],
+				GroupPolicy::All,
+				10,
+				None,
+				vec![FriendCount::new(2), FriendCount::new(1)]
+			),
+			Error::<Test>::ZeroThreshold
+		);
+		// thresholds add up to more than `MaxApprovals`
+		assert_noop!(
+			Recovery::create_group_recovery(
+				RuntimeOrigin::signed(alice),
+				vec![family.clone(), RecoveryGroup::new(custodian_tree.root(), 9)],
+				GroupPolicy::All,
+				10,
+				None,
+				vec![FriendCount::new(2), FriendCount::new(9)]
+			),
+			Error::<Test>::ThresholdTooHigh
+		);
+		assert_noop!(
+			Recovery::create_group_recovery(
+				RuntimeOrigin::signed(alice),
+				vec![family.clone(), custodian.clone()],
+				GroupPolicy::AnyOf(3),
+				10,
+				None,
+				vec![FriendCount::new(2), FriendCount::new(1)]
+			),
+			Error::<Test>::InvalidPolicy
+		);
+		assert_ok!(Recovery::create_group_recovery(
+			RuntimeOrigin::signed(alice),
+			vec![family.clone(), custodian.clone()],
+			GroupPolicy::AnyOf(1),
+			10,
+			None,
+			vec![FriendCount::new(2), FriendCount::new(1)]
+		));
+		assert_eq!(
+			Recovery::recovery_config(alice),
+			Some(RecoveryConfig {
+				delay_period: 10,
+				groups: vec![family, custodian],
+				policy: GroupPolicy::AnyOf(1),
+				rescuers_merkle_root: None,
+				auto_sweep: false,
+				threshold_key: None,
+				previous_roots: vec![],
+			})
+		);
+	});
+}
+
+#[test]
+fn sweep_recovered_works() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let eve = get_from_seed("eve");
+		assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&alice, 20));
+
+		// only the proxy of alice can sweep it
+		assert_noop!(
+			Recovery::sweep_recovered(RuntimeOrigin::signed(bob), alice, bob),
+			Error::<Test>::NotAllowed
+		);
+		assert_ok!(Recovery::set_recovered(RuntimeOrigin::root(), alice, bob));
+		assert_noop!(
+			Recovery::sweep_recovered(RuntimeOrigin::signed(bob), charlie, bob),
+			Error::<Test>::NotAllowed
+		);
+
+		// the reserve is left alone, and everything else but the existential deposit, which keeps
+		// alice alive while funds are reserved, goes to eve
+		assert_ok!(Recovery::sweep_recovered(RuntimeOrigin::signed(bob), alice, eve));
+		assert_eq!(Balances::free_balance(eve), 179);
+		assert_eq!(Balances::free_balance(alice), 1);
+		assert_eq!(Balances::reserved_balance(alice), 20);
+		assert_eq!(
+			System::events().last().map(|record| record.event.clone()),
+			Some(RuntimeEvent::Recovery(Event::AccountSwept(alice, eve, 79, 0, 21)))
+		);
+
+		// once the reserve is released, everything but the locked funds goes to eve
+		<Balances as ReservableCurrency<_>>::unreserve(&alice, 20);
+		<Balances as LockableCurrency<_>>::set_lock(*b"testlock", &alice, 15, WithdrawReasons::all());
+		assert_ok!(Recovery::sweep_recovered(RuntimeOrigin::signed(bob), alice, eve));
+		assert_eq!(Balances::free_balance(eve), 185);
+		assert_eq!(Balances::free_balance(alice), 15);
+		assert_eq!(
+			System::events().last().map(|record| record.event.clone()),
+			Some(RuntimeEvent::Recovery(Event::AccountSwept(alice, eve, 6, 0, 15)))
+		);
+	});
+}
+
+#[test]
+fn sweep_releases_the_deposits_of_the_pallet() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		// alice committed to an approval of the recovery of charlie by bob
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(charlie),
+			FriendsTree::new(vec![alice]).root(),
+			1,
+			0,
+			FriendCount::new(1),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), charlie, None));
+		assert_ok!(Recovery::commit_approval(RuntimeOrigin::signed(alice), charlie, bob, [1; 32]));
+		assert_eq!(Balances::reserved_balance(alice), 5);
+		assert_eq!(Deposits::<Test>::get(alice), 5);
+
+		// sweeping alice releases the deposit and sweeps it along
+		assert_ok!(Recovery::set_recovered(RuntimeOrigin::root(), alice, bob));
+		assert_ok!(Recovery::sweep_recovered(RuntimeOrigin::signed(bob), alice, bob));
+		assert_eq!(Balances::free_balance(bob), 200);
+		assert_eq!(Balances::total_balance(&alice), 0);
+		assert_eq!(Deposits::<Test>::get(alice), 0);
+		assert_eq!(
+			System::events().last().map(|record| record.event.clone()),
+			Some(RuntimeEvent::Recovery(Event::AccountSwept(alice, bob, 100, 5, 0)))
+		);
+
+		// clearing the commitment later doesn't unreserve anything else of alice
+		assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(dave), alice, 10));
+		assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&alice, 3));
+		assert_eq!(Recovery::clear_commitments(&charlie, &bob), 1);
+		assert_eq!(Balances::reserved_balance(alice), 3);
+	});
+}
+
+#[test]
+fn failed_auto_sweeps_are_reported() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let merkle_tree = FriendsTree::new(vec![charlie]);
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			merkle_tree.root(),
+			1,
+			0,
+			FriendCount::new(1),
+		));
+		assert_ok!(Recovery::set_auto_sweep(RuntimeOrigin::signed(alice), true));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			approve_by_seed("charlie", &alice, &bob, 0, 1),
+			merkle_tree.proof_for(&charlie).unwrap()
+		));
+		// bob can't receive the funds of alice
+		let _ = Balances::make_free_balance_be(&bob, u128::MAX - 10);
+
+		// the account is recovered, and nothing is swept
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+		assert_eq!(Recovery::proxy(bob), Some(alice));
+		assert_eq!(Balances::free_balance(alice), 100);
+		assert!(matches!(
+			System::events().last().map(|record| record.event.clone()),
+			Some(RuntimeEvent::Recovery(Event::SweepFailed(lost, rescuer, _))) if lost == alice && rescuer == bob
+		));
+		assert!(!System::events()
+			.iter()
+			.any(|record| matches!(record.event, RuntimeEvent::Recovery(Event::AccountSwept(..)))));
+	});
+}
+
+#[test]
+fn auto_sweep_works() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let merkle_tree = FriendsTree::new(vec![charlie]);
+		assert_noop!(
+			Recovery::set_auto_sweep(RuntimeOrigin::signed(alice), true),
+			Error::<Test>::NotRecoverable
+		);
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			merkle_tree.root(),
+			1,
+			0,
+			FriendCount::new(1),
+		));
+		assert_ok!(Recovery::set_auto_sweep(RuntimeOrigin::signed(alice), true));
+		assert!(Recovery::recovery_config(alice).unwrap().auto_sweep());
+
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			approve_by_seed("charlie", &alice, &bob, 0, 1),
+			merkle_tree.proof_for(&charlie).unwrap()
+		));
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+		// alice is emptied into bob on claim
+		assert_eq!(Balances::free_balance(alice), 0);
+		assert_eq!(Balances::free_balance(bob), 200);
+		assert_eq!(Recovery::proxy(bob), Some(alice));
+	});
+}
+
+#[test]
+fn rescuer_fees_are_charged_to_the_lost_account() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let dave = get_from_seed("dave");
+		let ferdie = get_from_seed("ferdie");
+		// ferdie is a fresh rescuer key
+		assert_ok!(Recovery::set_recovered(RuntimeOrigin::root(), alice, ferdie));
+		let transfer = Box::new(RuntimeCall::Balances(BalancesCall::transfer_allow_death { dest: bob, value: 10 }));
+		let call = RuntimeCall::Recovery(crate::Call::as_recovered { lost: alice, call: transfer });
+		let info = call.get_dispatch_info();
+		let extra = |tip: u128| {
+			let nonce = System::account_nonce(ferdie);
+			(frame_system::CheckNonce::<Test>::from(nonce), ChargeRecoveredAccount::<Test>::from(tip))
+		};
+
+		// the nonce check rejects signers which don't exist, so ferdie needs the existential deposit
+		assert_eq!(
+			extra(0).pre_dispatch(&ferdie, &call, &info, 10).map(|_| ()),
+			Err(InvalidTransaction::Payment.into())
+		);
+		assert_ok!(Balances::transfer_allow_death(RuntimeOrigin::signed(dave), ferdie, 1));
+
+		// fees are 1 per byte, weight is free in the mock, and are withdrawn from alice
+		assert_eq!(ChargeRecoveredAccount::<Test>::payer(&ferdie, &call), alice);
+		assert_ok!(extra(0).pre_dispatch(&ferdie, &call, &info, 10));
+		assert_eq!(Balances::free_balance(alice), 90);
+		assert_eq!(Balances::free_balance(ferdie), 1);
+		assert_ok!(call.clone().dispatch(RuntimeOrigin::signed(ferdie)));
+		assert_eq!(Balances::free_balance(alice), 80);
+		assert_eq!(Balances::free_balance(bob), 110);
+
+		// so is the sweep, with the tip
+		let sweep = RuntimeCall::Recovery(crate::Call::sweep_recovered { lost: alice, dest: ferdie });
+		let info = sweep.get_dispatch_info();
+		assert_ok!(extra(5).pre_dispatch(&ferdie, &sweep, &info, 10));
+		assert_eq!(Balances::free_balance(alice), 65);
+		assert_ok!(sweep.dispatch(RuntimeOrigin::signed(ferdie)));
+		assert_eq!(Balances::free_balance(ferdie), 66);
+
+		// ferdie pays for everything else, and can't on behalf of another account
+		let transfer = Box::new(RuntimeCall::Balances(BalancesCall::transfer_allow_death { dest: ferdie, value: 10 }));
+		let other = RuntimeCall::Recovery(crate::Call::as_recovered { lost: bob, call: transfer });
+		assert_eq!(ChargeRecoveredAccount::<Test>::payer(&ferdie, &other), ferdie);
+		let create = RuntimeCall::Recovery(crate::Call::create_recovery {
+			friends_merkle_root: vec![0; MERKLE_ROOT_LENGTH],
+			threshold: 1,
+			delay_period: 0,
+			friend_count: FriendCount::new(1),
+		});
+		assert_eq!(ChargeRecoveredAccount::<Test>::payer(&ferdie, &create), ferdie);
+		let info = create.get_dispatch_info();
+		assert_ok!(extra(0).pre_dispatch(&ferdie, &create, &info, 10));
+		assert_eq!(Balances::free_balance(ferdie), 56);
+		assert_eq!(System::account_nonce(ferdie), 3);
+		// bob has no recovered account, so pays the fees itself
+		assert_eq!(ChargeRecoveredAccount::<Test>::payer(&bob, &call), bob);
+	});
+}
+
+#[test]
+fn group_recovery_works() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let eve = get_from_seed("eve");
+
+		// 2-of-family AND 1-of-custodian
+		let family_tree = FriendsTree::new(vec![charlie, dave]);
+		let custodian_tree = FriendsTree::new(vec![eve]);
+		assert_ok!(Recovery::create_group_recovery(
+			RuntimeOrigin::signed(alice),
+			vec![
+				RecoveryGroup::new(family_tree.root(), 2),
+				RecoveryGroup::new(custodian_tree.root(), 1),
+			],
+			GroupPolicy::All,
+			10,
+			None,
+			vec![FriendCount::new(2), FriendCount::new(1)],
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+
+		let charlie_proof = family_tree.proof_for(&charlie).unwrap();
+		let dave_proof = family_tree.proof_for(&dave).unwrap();
+		let eve_proof = custodian_tree.proof_for(&eve).unwrap();
+
+		// the group must exist
+		assert_noop!(
+			Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				2,
+				approve_by_seed("eve", &alice, &bob, 0, 1),
+				eve_proof.clone()
+			),
+			Error::<Test>::InvalidGroup
+		);
+		// eve's proof is only valid for the custodian group
+		assert_noop!(
+			Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				approve_by_seed("eve", &alice, &bob, 0, 1),
+				eve_proof.clone()
+			),
+			Error::<Test>::MerkleProofInvalid
+		);
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			approve_by_seed("charlie", &alice, &bob, 0, 1),
+			charlie_proof
+		));
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			1,
+			approve_by_seed("eve", &alice, &bob, 1, 1),
+			eve_proof
+		));
+
+		run_to_block(11);
+		// the family group is still under its threshold
+		assert_noop!(
+			Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice),
+			Error::<Test>::UnderThreshold
+		);
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			approve_by_seed("dave", &alice, &bob, 0, 1),
+			dave_proof
+		));
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+		assert_eq!(Recovery::proxy(bob), Some(alice));
+	});
+}
+
+#[test]
+fn initiate_recovery_with_committed_rescuers_works() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let eve = get_from_seed("eve");
+
+		let friends_tree = FriendsTree::new(vec![charlie, dave]);
+		let rescuers_tree = FriendsTree::new(vec![bob, eve]);
+		let other_tree = FriendsTree::new(vec![charlie, dave]);
+		assert_ok!(Recovery::create_group_recovery(
+			RuntimeOrigin::signed(alice),
+			vec![RecoveryGroup::new(friends_tree.root(), 1)],
+			GroupPolicy::All,
+			10,
+			Some(rescuers_tree.root()),
+			vec![FriendCount::new(2)],
+		));
+
+		// a proof is required once the owner committed to the rescuers
+		assert_noop!(
+			Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None),
+			Error::<Test>::RescuerProofMissing
+		);
+		// the proof must be for the caller
+		assert_noop!(
+			Recovery::initiate_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				Some(rescuers_tree.proof_for(&eve).unwrap())
+			),
+			Error::<Test>::RescuerNotAllowed
+		);
+		// and against the committed rescuers merkle root
+		assert_noop!(
+			Recovery::initiate_recovery(
+				RuntimeOrigin::signed(charlie),
+				alice,
+				Some(other_tree.proof_for(&charlie).unwrap())
+			),
+			Error::<Test>::RescuerNotAllowed
+		);
+		assert_ok!(Recovery::initiate_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			Some(rescuers_tree.proof_for(&bob).unwrap())
+		));
+		assert_eq!(
+			Recovery::active_recovery(alice, bob),
+			Some(ActiveRecovery {
+				created: 1,
+				approvals: vec![],
+				key_images: vec![],
+				approval_roots: vec![]
+			})
+		);
+	});
+}
+
+/// The signature of `seed` on the blinded recovery of a single group of `friends_merkle_root`.
+fn sign_blinded_recovery(seed: &str, blinded_key: &[u8; 32], friends_merkle_root: Vec<u8>) -> Signature {
+	let groups = vec![RecoveryGroup::new(friends_merkle_root, 1)];
+	sign_by_seed(seed, &blinded_recovery_payload(blinded_key, &groups, GroupPolicy::All, &10u64, &None))
+}
+
+#[test]
+fn blinded_recovery_works() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let eve = get_from_seed("eve");
+		let friends_tree = FriendsTree::new(vec![charlie]);
+		let salt = [7; 32];
+		let key = blinded_key(&alice, &salt);
+
+		// only alice can store her blinded config, which doesn't name her
+		assert_noop!(
+			Recovery::create_blinded_recovery(
+				RuntimeOrigin::signed(charlie),
+				key,
+				vec![RecoveryGroup::new(friends_tree.root(), 1)],
+				GroupPolicy::All,
+				10,
+				None,
+				vec![FriendCount::new(1)],
+				sign_blinded_recovery("alice", &key, friends_tree.root()),
+			),
+			Error::<Test>::OwnerSignatureInvalid
+		);
+		assert_ok!(Recovery::create_blinded_recovery(
+			RuntimeOrigin::signed(alice),
+			key,
+			vec![RecoveryGroup::new(friends_tree.root(), 1)],
+			GroupPolicy::All,
+			10,
+			None,
+			vec![FriendCount::new(1)],
+			sign_blinded_recovery("alice", &key, friends_tree.root()),
+		));
+		assert_eq!(Recovery::recovery_config(alice), None);
+		assert_eq!(BlindedRecoverable::<Test>::iter_keys().collect::<Vec<_>>(), vec![blinded_storage_key(&alice, &key)]);
+		assert_eq!(Balances::reserved_balance(alice), 10);
+		assert_noop!(
+			Recovery::create_blinded_recovery(
+				RuntimeOrigin::signed(alice),
+				key,
+				vec![RecoveryGroup::new(friends_tree.root(), 1)],
+				GroupPolicy::All,
+				10,
+				None,
+				vec![FriendCount::new(1)],
+				sign_blinded_recovery("alice", &key, friends_tree.root()),
+			),
+			Error::<Test>::AlreadyRecoverable
+		);
+
+		// eve can store a config under a key of alice, for a deposit she doesn't get back, but it
+		// is hers and can't be revealed as alice's
+		let eve_salt = [8; 32];
+		let eve_key = blinded_key(&alice, &eve_salt);
+		let eve_tree = FriendsTree::new(vec![eve]);
+		assert_ok!(Recovery::create_blinded_recovery(
+			RuntimeOrigin::signed(eve),
+			eve_key,
+			vec![RecoveryGroup::new(eve_tree.root(), 1)],
+			GroupPolicy::All,
+			10,
+			None,
+			vec![FriendCount::new(1)],
+			sign_blinded_recovery("eve", &eve_key, eve_tree.root()),
+		));
+		assert_noop!(
+			Recovery::initiate_blinded_recovery(RuntimeOrigin::signed(eve), alice, eve_salt, None),
+			Error::<Test>::NotRecoverable
+		);
+		assert_eq!(Balances::reserved_balance(eve), 10);
+
+		// the owner must afford the deposit
+		let ferdie = get_from_seed("ferdie");
+		let ferdie_key = blinded_key(&ferdie, &salt);
+		assert_noop!(
+			Recovery::create_blinded_recovery(
+				RuntimeOrigin::signed(ferdie),
+				ferdie_key,
+				vec![RecoveryGroup::new(friends_tree.root(), 1)],
+				GroupPolicy::All,
+				10,
+				None,
+				vec![FriendCount::new(1)],
+				sign_blinded_recovery("ferdie", &ferdie_key, friends_tree.root()),
+			),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+
+		// the config can only be found with the salt
+		assert_noop!(
+			Recovery::initiate_blinded_recovery(RuntimeOrigin::signed(bob), alice, [0; 32], None),
+			Error::<Test>::NotRecoverable
+		);
+		assert_noop!(
+			Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None),
+			Error::<Test>::NotRecoverable
+		);
+		assert_ok!(Recovery::initiate_blinded_recovery(RuntimeOrigin::signed(bob), alice, salt, None));
+		System::assert_has_event(RuntimeEvent::Recovery(Event::RecoveryRevealed(alice)));
+		assert_eq!(Recovery::blinded_recovery(&alice, key), None);
+		assert_eq!(Balances::reserved_balance(alice), 0);
+		assert_eq!(Recovery::recovery_config(alice).map(|config| config.groups().len()), Some(1));
+		assert_eq!(
+			Recovery::active_recovery(alice, bob),
+			Some(ActiveRecovery {
+				created: 1,
+				approvals: vec![],
+				key_images: vec![],
+				approval_roots: vec![]
+			})
+		);
+
+		// the recovery goes on as usual
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			approve_by_seed("charlie", &alice, &bob, 0, 1),
+			friends_tree.proof_for(&charlie).unwrap()
+		));
+		run_to_block(11);
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+	});
+}
+
+#[test]
+fn blinded_keys_cannot_be_taken_first() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let eve = get_from_seed("eve");
+		let friends_tree = FriendsTree::new(vec![charlie]);
+		let eve_tree = FriendsTree::new(vec![eve]);
+		let salt = [7; 32];
+		let key = blinded_key(&alice, &salt);
+
+		// eve sees the key of alice in the pool, and stores a config of hers under it first
+		assert_ok!(Recovery::create_blinded_recovery(
+			RuntimeOrigin::signed(eve),
+			key,
+			vec![RecoveryGroup::new(eve_tree.root(), 1)],
+			GroupPolicy::All,
+			10,
+			None,
+			vec![FriendCount::new(1)],
+			sign_blinded_recovery("eve", &key, eve_tree.root()),
+		));
+		// which doesn't keep alice from storing hers, nor her rescuer from revealing it
+		assert_ok!(Recovery::create_blinded_recovery(
+			RuntimeOrigin::signed(alice),
+			key,
+			vec![RecoveryGroup::new(friends_tree.root(), 1)],
+			GroupPolicy::All,
+			10,
+			None,
+			vec![FriendCount::new(1)],
+			sign_blinded_recovery("alice", &key, friends_tree.root()),
+		));
+		assert_ok!(Recovery::initiate_blinded_recovery(RuntimeOrigin::signed(bob), alice, salt, None));
+		assert_eq!(
+			Recovery::recovery_config(alice).map(|config| config.groups()[0].friends_merkle_root().to_vec()),
+			Some(friends_tree.root())
+		);
+		assert_eq!(Balances::reserved_balance(alice), 0);
+		assert_eq!(Balances::reserved_balance(eve), 10);
+	});
+}
+
+#[test]
+fn existing_recoveries_can_be_blinded() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let friends_tree = FriendsTree::new(vec![charlie]);
+		let salt = [7; 32];
+		let key = blinded_key(&alice, &salt);
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			friends_tree.root(),
+			1,
+			10,
+			FriendCount::new(1),
+		));
+
+		// the signature must be alice's, on her current config
+		assert_noop!(
+			Recovery::blind_recovery(
+				RuntimeOrigin::signed(alice),
+				key,
+				sign_blinded_recovery("bob", &key, friends_tree.root())
+			),
+			Error::<Test>::OwnerSignatureInvalid
+		);
+		assert_noop!(
+			Recovery::blind_recovery(
+				RuntimeOrigin::signed(alice),
+				key,
+				sign_blinded_recovery("alice", &key, vec![0; MERKLE_ROOT_LENGTH])
+			),
+			Error::<Test>::OwnerSignatureInvalid
+		);
+		// recoveries in progress can't be blinded
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(charlie), alice, None));
+		assert_noop!(
+			Recovery::blind_recovery(
+				RuntimeOrigin::signed(alice),
+				key,
+				sign_blinded_recovery("alice", &key, friends_tree.root())
+			),
+			Error::<Test>::AlreadyStarted
+		);
+		ActiveRecoveries::<Test>::remove(&alice, &charlie);
+
+		assert_ok!(Recovery::blind_recovery(
+			RuntimeOrigin::signed(alice),
+			key,
+			sign_blinded_recovery("alice", &key, friends_tree.root())
+		));
+		assert_eq!(Recovery::recovery_config(alice), None);
+		assert_eq!(Balances::reserved_balance(alice), 10);
+		assert_noop!(
+			Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None),
+			Error::<Test>::NotRecoverable
+		);
+		assert_ok!(Recovery::initiate_blinded_recovery(RuntimeOrigin::signed(bob), alice, salt, None));
+		assert!(Recovery::recovery_config(alice).is_some());
+		assert_eq!(Balances::reserved_balance(alice), 0);
+	});
+}
+
+#[test]
+fn genesis_config_works() {
+	let alice = get_from_seed("alice");
+	let bob = get_from_seed("bob");
+	let charlie = get_from_seed("charlie");
+	let dave = get_from_seed("dave");
+	let eve = get_from_seed("eve");
+	let merkle_tree = FriendsTree::new(vec![charlie, dave, eve]);
+	let friends_merkle_root = merkle_tree.root();
+
+	new_test_ext_with_recovery(
+		vec![(alice, friends_merkle_root.clone(), 2, 10)],
+		vec![(charlie, bob)],
+	)
+	.execute_with(|| {
+		assert_eq!(
+			Recovery::recovery_config(alice),
+			Some(RecoveryConfig {
+				delay_period: 10,
+				groups: vec![RecoveryGroup::new(friends_merkle_root, 2)],
+				policy: GroupPolicy::All,
+				rescuers_merkle_root: None,
+				auto_sweep: false,
+				threshold_key: None,
+				previous_roots: vec![],
+			})
+		);
+		assert_eq!(Recovery::proxy(charlie), Some(bob));
+
+		// the pre-seeded recovery can be used as usual
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		let call = Box::new(RuntimeCall::Balances(BalancesCall::transfer_allow_death { dest: dave, value: 10 }));
+		assert_ok!(Recovery::as_recovered(RuntimeOrigin::signed(charlie), bob, call));
+		assert_eq!(Balances::free_balance(bob), 90);
+	});
+}
+
+#[test]
+#[should_panic(expected = "Recovery threshold must be greater than zero")]
+fn genesis_config_rejects_zero_threshold() {
+	let alice = get_from_seed("alice");
+	new_test_ext_with_recovery(vec![(alice, vec![0; MERKLE_ROOT_LENGTH], 0, 10)], vec![]);
+}
+
+#[test]
+#[should_panic(expected = "Friends merkle root must be 32 bytes long")]
+fn genesis_config_rejects_invalid_root() {
+	let alice = get_from_seed("alice");
+	new_test_ext_with_recovery(vec![(alice, vec![0; 20], 1, 10)], vec![]);
+}
+
+#[test]
+fn runtime_api_helpers_work() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let eve = get_from_seed("eve");
+		let merkle_tree = FriendsTree::new(vec![charlie, dave]);
+
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			merkle_tree.root(),
+			2,
+			10,
+			FriendCount::new(2),
+		));
+		assert_eq!(Recovery::active_recoveries_for(&alice), vec![]);
+		assert_eq!(Recovery::recovery_status(&alice, &bob), None);
+
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(eve), alice, None));
+		let mut rescuers = Recovery::active_recoveries_for(&alice);
+		rescuers.sort();
+		let mut expected = vec![bob, eve];
+		expected.sort();
+		assert_eq!(rescuers, expected);
+
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			approve_by_seed("charlie", &alice, &bob, 0, 1),
+			merkle_tree.proof_for(&charlie).unwrap()
+		));
+		assert_eq!(
+			Recovery::recovery_status(&alice, &bob),
+			Some(RecoveryStatus {
+				approvals: vec![1],
+				thresholds: vec![2],
+				threshold_reached: false,
+				claimable_at: 11,
+			})
+		);
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			approve_by_seed("dave", &alice, &bob, 0, 1),
+			merkle_tree.proof_for(&dave).unwrap()
+		));
+		assert_eq!(
+			Recovery::recovery_status(&alice, &bob).map(|status| status.threshold_reached),
+			Some(true)
+		);
+	});
+}
+
+#[test]
+fn approval_package_round_trips_and_submits() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let merkle_tree = FriendsTree::new(vec![charlie, dave]);
+		let friends_merkle_root = merkle_tree.root();
+		let genesis_hash = System::block_hash(0);
+
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			friends_merkle_root.clone(),
+			1,
+			0,
+			FriendCount::new(1),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+
+		// charlie hands the package to bob
+		let package = ApprovalPackage {
+			lost: alice,
+			rescuer: bob,
+			group: 0,
+			proof: merkle_tree.proof_for(&charlie).unwrap(),
+			scheme: SignatureScheme::Sr25519,
+			signature: approve_by_seed("charlie", &alice, &bob, 0, 1),
+			genesis_hash,
+		};
+		let bytes = package.encode();
+		assert_eq!(ApprovalPackage::from_bytes(&bytes), Ok(package.clone()));
+		assert_eq!(ApprovalPackage::from_json(&package.to_json()), Ok(package.clone()));
+		assert_eq!(
+			ApprovalPackage::from_string_encoding(&package.to_string_encoding()),
+			Ok(package.clone())
+		);
+
+		// bob checks it, then submits it
+		let received = ApprovalPackage::<_, H256>::from_string_encoding(&package.to_string_encoding()).unwrap();
+		assert_eq!(received.validate(&genesis_hash, &1u64, Some(&friends_merkle_root[..])), Ok(()));
+		assert_ok!(RuntimeCall::from(received.into_call::<Test>()).dispatch(RuntimeOrigin::signed(bob)));
+		assert_eq!(
+			Recovery::active_recovery(alice, bob),
+			Some(ActiveRecovery {
+				created: 1,
+				approvals: approvals(&[merkle_tree.proof_for(&charlie).unwrap().position()]),
+				key_images: vec![],
+				approval_roots: vec![friends_merkle_root.clone()]
+			})
+		);
+	});
+}
+
+#[test]
+fn approval_package_golden_vectors() {
+	let lost = sr25519::Public::from_raw([1; 32]);
+	let rescuer = sr25519::Public::from_raw([2; 32]);
+	let merkle_tree = FriendsTree::new(vec![sr25519::Public::from_raw([3; 32]), sr25519::Public::from_raw([4; 32])]);
+	let proof = merkle_tree.proof_for(&sr25519::Public::from_raw([3; 32])).unwrap();
+	let package = ApprovalPackage {
+		lost,
+		rescuer,
+		group: 1,
+		proof: proof.clone(),
+		scheme: SignatureScheme::Sr25519,
+		signature: Signature::from_raw([5; 64]),
+		genesis_hash: H256::repeat_byte(6),
+	};
+
+	// magic, version, then the fields in order
+	let mut expected = b"ssra".to_vec();
+	expected.push(2);
+	expected.extend_from_slice(&[1; 32]);
+	expected.extend_from_slice(&[2; 32]);
+	expected.extend_from_slice(&[1, 0]);
+	// the proof: the friend, then one sibling on the right
+	expected.extend_from_slice(&[3; 32]);
+	expected.extend_from_slice(&[4, 1]);
+	expected.extend_from_slice(&proof::hash_leaf(&[4; 32]));
+	expected.push(0);
+	expected.extend_from_slice(&[5; 64]);
+	expected.extend_from_slice(&[6; 32]);
+	assert_eq!(package.encode(), expected);
+
+	let expected_json = format!(
+		concat!(
+			r#"{{"version":2,"scheme":"sr25519","lost":"0x{}","rescuer":"0x{}","group":1,"#,
+			r#""proof":"0x{}","signature":"0x{}","genesisHash":"0x{}"}}"#
+		),
+		"01".repeat(32),
+		"02".repeat(32),
+		hex::encode(proof.encode()),
+		"05".repeat(64),
+		"06".repeat(32),
+	);
+	assert_eq!(package.to_json(), expected_json);
+
+	let expected_string = format!(
+		"ssr-approval:{}",
+		base64::encode_config(&expected, base64::URL_SAFE_NO_PAD)
+	);
+	assert_eq!(package.to_string_encoding(), expected_string);
+	assert!(expected_string.starts_with("ssr-approval:c3NyYQI"));
+}
+
+#[test]
+fn approval_package_rejects_invalid_input() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let merkle_tree = FriendsTree::new(vec![charlie, dave]);
+		let other_tree = FriendsTree::new(vec![charlie]);
+		let genesis_hash = H256::repeat_byte(1);
+		let package = ApprovalPackage {
+			lost: alice,
+			rescuer: bob,
+			group: 0,
+			proof: merkle_tree.proof_for(&charlie).unwrap(),
+			scheme: SignatureScheme::Sr25519,
+			signature: sign_by_seed("charlie", &approval_payload(&genesis_hash, &alice, &bob, 0, &1u64)),
+			genesis_hash,
+		};
+		let bytes = package.encode();
+
+		let mut foreign = bytes.clone();
+		foreign[0] = b'x';
+		assert_eq!(
+			ApprovalPackage::<sr25519::Public, H256>::from_bytes(&foreign),
+			Err(PackageError::NotAnApprovalPackage)
+		);
+		let mut newer = bytes.clone();
+		newer[4] = 3;
+		assert_eq!(
+			ApprovalPackage::<sr25519::Public, H256>::from_bytes(&newer),
+			Err(PackageError::UnsupportedVersion(3))
+		);
+		// packages of the first version didn't sign the chain and the recovery
+		let mut older = bytes.clone();
+		older[4] = 1;
+		assert_eq!(
+			ApprovalPackage::<sr25519::Public, H256>::from_bytes(&older),
+			Err(PackageError::UnsupportedVersion(1))
+		);
+		let mut trailing = bytes.clone();
+		trailing.push(0);
+		assert_eq!(
+			ApprovalPackage::<sr25519::Public, H256>::from_bytes(&trailing),
+			Err(PackageError::Malformed)
+		);
+		assert_eq!(
+			ApprovalPackage::<sr25519::Public, H256>::from_bytes(&bytes[..bytes.len() - 1]),
+			Err(PackageError::Malformed)
+		);
+		assert_eq!(
+			ApprovalPackage::<sr25519::Public, H256>::from_string_encoding("ssr:abc"),
+			Err(PackageError::NotAnApprovalPackage)
+		);
+
+		assert_eq!(
+			package.validate(&H256::repeat_byte(2), &1u64, None),
+			Err(PackageError::WrongChain)
+		);
+		assert_eq!(
+			package.validate(&genesis_hash, &1u64, Some(&other_tree.root()[..])),
+			Err(PackageError::MerkleProofInvalid)
+		);
+		let forged = ApprovalPackage {
+			signature: sign_by_seed("dave", &approval_payload(&genesis_hash, &alice, &bob, 0, &1u64)),
+			..package.clone()
+		};
+		assert_eq!(
+			forged.validate(&genesis_hash, &1u64, None),
+			Err(PackageError::SignatureInvalid)
+		);
+		// the signature is only valid for the chain and the recovery it was made for
+		assert_eq!(package.validate(&genesis_hash, &1u64, None), Ok(()));
+		let other_chain = H256::repeat_byte(2);
+		let replayed = ApprovalPackage { genesis_hash: other_chain, ..package.clone() };
+		assert_eq!(
+			replayed.validate(&other_chain, &1u64, None),
+			Err(PackageError::SignatureInvalid)
+		);
+		assert_eq!(
+			package.validate(&genesis_hash, &2u64, None),
+			Err(PackageError::SignatureInvalid)
+		);
+	});
+}
+
+#[test]
+fn invitations_round_trip() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let charlie_pair = sr25519::Pair::from_string("//charlie", None).unwrap();
+		let dave_pair = sr25519::Pair::from_string("//dave", None).unwrap();
+
+		let (friends_merkle_root, bundles) =
+			invitations::invite_friends(alice, 0, 2, 10u64, &FriendsTree::new(vec![charlie, dave])).unwrap();
+		assert_eq!(bundles.len(), 2);
+
+		// each friend opens their own invitation
+		let charlie_invitation: Invitation<sr25519::Public, u64> =
+			invitations::open(&charlie_pair, &bundles[0]).unwrap();
+		assert_eq!(charlie_invitation.lost, alice);
+		assert_eq!(charlie_invitation.group, 0);
+		assert_eq!(charlie_invitation.threshold, 2);
+		assert_eq!(charlie_invitation.delay_period, 10);
+		assert_eq!(charlie_invitation.proof.value, charlie);
+		assert!(charlie_invitation.proof.validate(&friends_merkle_root));
+		let dave_invitation: Invitation<sr25519::Public, u64> =
+			invitations::open(&dave_pair, &bundles[1]).unwrap();
+		assert_eq!(dave_invitation.proof.value, dave);
+
+		// but not someone else's
+		assert_eq!(
+			invitations::open::<sr25519::Public, u64>(&charlie_pair, &bundles[1]),
+			Err(InvitationError::DecryptionFailed)
+		);
+		let mut tampered = bundles[0].clone();
+		tampered.ciphertext[0] ^= 1;
+		assert_eq!(
+			invitations::open::<sr25519::Public, u64>(&charlie_pair, &tampered),
+			Err(InvitationError::DecryptionFailed)
+		);
+
+		// the invitation holds everything charlie needs to approve the recovery
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			friends_merkle_root,
+			charlie_invitation.threshold,
+			charlie_invitation.delay_period,
+			FriendCount::new(charlie_invitation.threshold.into()),
+		));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			charlie_invitation.lost,
+			bob,
+			charlie_invitation.group,
+			charlie_pair.sign(&Recovery::approval_payload(&alice, &bob, 0).unwrap()),
+			charlie_invitation.proof
+		));
+	});
+}
+
+#[test]
+fn invitations_carry_the_proofs_of_padded_trees() {
+	let alice = get_from_seed("alice");
+	let charlie = get_from_seed("charlie");
+	let dave = get_from_seed("dave");
+	let dave_pair = sr25519::Pair::from_string("//dave", None).unwrap();
+	let tree = FriendsTree::padded(vec![charlie, dave], 3, &[7; 32]).unwrap();
+
+	let (friends_merkle_root, bundles) = invitations::invite_friends(alice, 0, 1, 10u64, &tree).unwrap();
+	assert_eq!(friends_merkle_root, tree.root());
+	assert_eq!(bundles.len(), 2);
+	let invitation: Invitation<sr25519::Public, u64> = invitations::open(&dave_pair, &bundles[1]).unwrap();
+	assert_eq!(invitation.proof, tree.proof_for(&dave).unwrap());
+	assert_eq!(invitation.proof.depth(), 3);
+	assert!(invitation.proof.validate(&friends_merkle_root));
+}
+
+#[test]
+fn invitations_never_reuse_a_one_time_key() {
+	// All bundles are sealed with the same nonce, which is only safe if no two share a key
+	let alice = get_from_seed("alice");
+	let charlie = get_from_seed("charlie");
+	let charlie_pair = sr25519::Pair::from_string("//charlie", None).unwrap();
+	let (_, bundles) = invitations::invite_friends(alice, 0, 1, 10u64, &FriendsTree::new(vec![charlie])).unwrap();
+	let invitation: Invitation<sr25519::Public, u64> = invitations::open(&charlie_pair, &bundles[0]).unwrap();
+
+	let resealed: Vec<_> = (0..8).map(|_| invitations::seal(&charlie, &invitation).unwrap()).collect();
+	let mut one_time_keys: Vec<_> =
+		bundles.iter().chain(&resealed).map(|bundle| bundle.ephemeral_public).collect();
+	one_time_keys.sort();
+	one_time_keys.dedup();
+	assert_eq!(one_time_keys.len(), resealed.len() + 1);
+	// the same invitation under different keys gives unrelated ciphertexts
+	assert!(resealed.iter().all(|bundle| bundle.ciphertext != bundles[0].ciphertext));
+	assert!(resealed.iter().all(|bundle| invitations::open::<sr25519::Public, u64>(&charlie_pair, bundle)
+		== Ok(invitation.clone())));
+}
+
+#[test]
+fn merkle_tree_proof() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let eve = get_from_seed("eve");
+		let tree = FriendsTree::new(vec![alice.clone(), bob.clone(), eve.clone()]);
+
+		let proof = tree.proof_for(&alice).unwrap();
+		assert_eq!(true, proof.validate(&tree.root()));
+		assert_eq!(alice, proof.value);
+	});
+}
+
+#[test]
+fn friends_tree_matches_legacy_merkle_crate() {
+	use merkle::MerkleTree;
+	use ring::digest::SHA256;
+
+	for size in 1..=9u8 {
+		let accounts: Vec<sr25519::Public> = (0..size).map(|i| sr25519::Public::from_raw([i; 32])).collect();
+		let legacy = MerkleTree::from_vec(&SHA256, accounts.clone());
+		let tree = FriendsTree::new(accounts.clone());
+		assert_eq!(&tree.root(), legacy.root_hash());
+		for account in &accounts {
+			let proof = tree.proof_for(account).unwrap();
+			let legacy_proof = legacy.gen_proof(*account).unwrap();
+			assert_eq!(proof.root()[..], legacy_proof.root_hash[..]);
+			assert!(legacy_proof.validate(&proof.root()));
+			assert!(proof.validate(legacy.root_hash()));
+		}
+		assert_eq!(tree.proof_for(&sr25519::Public::from_raw([size; 32])), None);
+	}
+	// the empty tree is not usable, but has the same root
+	let legacy = MerkleTree::<sr25519::Public>::from_vec(&SHA256, vec![]);
+	assert_eq!(&FriendsTree::<sr25519::Public>::new(vec![]).root(), legacy.root_hash());
+}
+
+#[test]
+fn merkle_proofs_reject_other_leaves() {
+	let tree = FriendsTree::new(vec![sr25519::Public::from_raw([1; 32]), sr25519::Public::from_raw([2; 32])]);
+	let mut proof = tree.proof_for(&sr25519::Public::from_raw([1; 32])).unwrap();
+	assert!(proof.validate(&tree.root()));
+	proof.value = sr25519::Public::from_raw([3; 32]);
+	assert!(!proof.validate(&tree.root()));
+}
+
+#[test]
+fn honest_leaf_count_proofs_never_claim_more_leaves() {
+	assert_eq!(FriendsTree::<sr25519::Public>::new(vec![]).leaf_count_proof(), None);
+	for size in 1..=9u8 {
+		let tree = FriendsTree::new((0..size).map(|i| sr25519::Public::from_raw([i; 32])).collect());
+		let proof = tree.leaf_count_proof().unwrap();
+		assert!(proof.validate(&tree.root(), size as u32));
+		assert!(!proof.validate(&tree.root(), 0));
+		for more in size as u32 + 1..=16 {
+			assert!(!proof.validate(&tree.root(), more));
+		}
+	}
+	// the path must have the shape of the claimed index
+	let tree = FriendsTree::new((0..4u8).map(|i| sr25519::Public::from_raw([i; 32])).collect());
+	let mut proof = tree.leaf_count_proof().unwrap();
+	proof.index = 4;
+	assert!(!proof.validate(&tree.root(), 5));
+	// padded trees count their dummies
+	let padded = FriendsTree::padded(vec![sr25519::Public::from_raw([1; 32])], 3, &[7; 32]).unwrap();
+	assert!(padded.leaf_count_proof().unwrap().validate(&padded.root(), 8));
+}
+
+#[test]
+fn forged_leaf_count_proofs_can_claim_more_leaves() {
+	// the last leaf isn't opened, so a node of the tree can pass for it
+	let leaves: Vec<_> = (0..2u8).map(|i| sr25519::Public::from_raw([i; 32])).collect();
+	let tree = FriendsTree::new(leaves.clone());
+	let forged = LeafCountProof {
+		index: 2,
+		last_leaf: proof::hash_leaf(&leaves[1].encode()),
+		path: vec![proof::ProofNode::Left(proof::hash_leaf(&leaves[0].encode()))],
+	};
+	assert!(forged.validate(&tree.root(), 3));
+}
+
+#[test]
+fn thresholds_cannot_exceed_the_friend_count() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let tree = FriendsTree::new(vec![get_from_seed("charlie"), get_from_seed("dave"), get_from_seed("eve")]);
+		let proof = tree.leaf_count_proof().unwrap();
+
+		assert_noop!(
+			Recovery::create_recovery(RuntimeOrigin::signed(alice), tree.root(), 4, 10, FriendCount::new(3)),
+			Error::<Test>::ThresholdAboveFriendCount
+		);
+		// the proof only reveals hashes, but can't claim a fourth friend
+		assert_noop!(
+			Recovery::create_recovery(
+				RuntimeOrigin::signed(alice),
+				tree.root(),
+				4,
+				10,
+				FriendCount::proven(4, proof.clone())
+			),
+			Error::<Test>::FriendCountProofInvalid
+		);
+		assert_noop!(
+			Recovery::create_group_recovery(
+				RuntimeOrigin::signed(alice),
+				vec![RecoveryGroup::new(tree.root(), 2); 2],
+				GroupPolicy::All,
+				10,
+				None,
+				vec![FriendCount::new(3)]
+			),
+			Error::<Test>::FriendCountsMismatch
+		);
+		assert_ok!(Recovery::create_recovery(
+			RuntimeOrigin::signed(alice),
+			tree.root(),
+			3,
+			10,
+			FriendCount::proven(3, proof),
+		));
+		assert_eq!(Recovery::recovery_config(alice).unwrap().groups()[0].threshold(), 3);
+	});
+}
+
+#[test]
+fn rotated_roots_are_accepted_during_the_grace_period() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let ferdie = get_from_seed("ferdie");
+		let (charlie, dave, eve) = (get_from_seed("charlie"), get_from_seed("dave"), get_from_seed("eve"));
+		let old_tree = FriendsTree::new(vec![charlie, dave, eve]);
+		let new_tree = FriendsTree::new(vec![dave, eve]);
+		assert_ok!(Recovery::create_recovery(RuntimeOrigin::signed(alice), old_tree.root(), 2, 0, FriendCount::new(3)));
+		// charlie approves the recoveries of bob and ferdie
+		for rescuer in [bob, ferdie] {
+			assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(rescuer), alice, None));
+			assert_ok!(Recovery::approve_recovery(
+				RuntimeOrigin::signed(rescuer),
+				alice,
+				rescuer,
+				0,
+				approve_by_seed("charlie", &alice, &rescuer, 0, 1),
+				old_tree.proof_for(&charlie).unwrap()
+			));
+		}
+
+		// alice drops charlie at block 2
+		run_to_block(2);
+		assert_ok!(Recovery::rotate_friends_merkle_root(
+			RuntimeOrigin::signed(alice),
+			0,
+			new_tree.root(),
+			FriendCount::proven(2, new_tree.leaf_count_proof().unwrap())
+		));
+		System::assert_last_event(Event::FriendsMerkleRootRotated(alice, 0).into());
+		assert_eq!(
+			Recovery::recovery_config(alice).unwrap().previous_roots(),
+			&[PreviousRoot { group: 0, friends_merkle_root: old_tree.root(), replaced: 2 }][..]
+		);
+
+		// bob's recovery goes on with the old tree, whose positions can't be mixed with the new one
+		assert_noop!(
+			Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				approve_by_seed("dave", &alice, &bob, 0, 1),
+				new_tree.proof_for(&dave).unwrap()
+			),
+			Error::<Test>::MixedRoots
+		);
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			approve_by_seed("dave", &alice, &bob, 0, 1),
+			old_tree.proof_for(&dave).unwrap()
+		));
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+
+		// once the grace period is over, ferdie's recovery moves on to the new tree and starts over
+		run_to_block(12);
+		assert_noop!(
+			Recovery::approve_recovery(
+				RuntimeOrigin::signed(ferdie),
+				alice,
+				ferdie,
+				0,
+				approve_by_seed("eve", &alice, &ferdie, 0, 1),
+				old_tree.proof_for(&eve).unwrap()
+			),
+			Error::<Test>::MerkleProofInvalid
+		);
+		let eve_proof = new_tree.proof_for(&eve).unwrap();
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(ferdie),
+			alice,
+			ferdie,
+			0,
+			approve_by_seed("eve", &alice, &ferdie, 0, 1),
+			eve_proof.clone()
+		));
+		assert_eq!(
+			Recovery::active_recovery(alice, ferdie),
+			Some(ActiveRecovery {
+				created: 1,
+				approvals: approvals(&[eve_proof.position()]),
+				key_images: vec![],
+				approval_roots: vec![new_tree.root()]
+			})
+		);
+	});
+}
+
+#[test]
+fn approvals_against_expired_roots_dont_count() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let (charlie, dave, eve) = (get_from_seed("charlie"), get_from_seed("dave"), get_from_seed("eve"));
+		let old_tree = FriendsTree::new(vec![charlie, dave, eve]);
+		let new_tree = FriendsTree::new(vec![dave, eve]);
+		assert_ok!(Recovery::create_recovery(RuntimeOrigin::signed(alice), old_tree.root(), 2, 0, FriendCount::new(3)));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		for (seed, friend) in [("charlie", charlie), ("dave", dave)] {
+			assert_ok!(Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				approve_by_seed(seed, &alice, &bob, 0, 1),
+				old_tree.proof_for(&friend).unwrap()
+			));
+		}
+
+		// alice drops charlie at block 2, before bob claims
+		run_to_block(2);
+		assert_ok!(Recovery::rotate_friends_merkle_root(
+			RuntimeOrigin::signed(alice),
+			0,
+			new_tree.root(),
+			FriendCount::proven(2, new_tree.leaf_count_proof().unwrap())
+		));
+		assert!(Recovery::recovery_status(&alice, &bob).unwrap().threshold_reached);
+
+		// once the grace period is over, the approvals against the old tree no longer count
+		run_to_block(12);
+		let status = Recovery::recovery_status(&alice, &bob).unwrap();
+		assert_eq!(status.approvals, vec![0]);
+		assert!(!status.threshold_reached);
+		assert_noop!(
+			Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice),
+			Error::<Test>::UnderThreshold
+		);
+
+		// the friends left in the group approve again against the new tree
+		for (seed, friend) in [("dave", dave), ("eve", eve)] {
+			assert_ok!(Recovery::approve_recovery(
+				RuntimeOrigin::signed(bob),
+				alice,
+				bob,
+				0,
+				approve_by_seed(seed, &alice, &bob, 0, 1),
+				new_tree.proof_for(&friend).unwrap()
+			));
+		}
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+	});
+}
+
+#[test]
+fn only_the_last_roots_are_kept() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let root = |i: u8| vec![i; MERKLE_ROOT_LENGTH];
+		assert_noop!(
+			Recovery::rotate_friends_merkle_root(RuntimeOrigin::signed(alice), 0, root(1), FriendCount::new(1)),
+			Error::<Test>::NotRecoverable
+		);
+		assert_ok!(Recovery::create_recovery(RuntimeOrigin::signed(alice), root(0), 2, 10, FriendCount::new(2)));
+		assert_noop!(
+			Recovery::rotate_friends_merkle_root(RuntimeOrigin::signed(alice), 1, root(1), FriendCount::new(2)),
+			Error::<Test>::InvalidGroup
+		);
+		assert_noop!(
+			Recovery::rotate_friends_merkle_root(RuntimeOrigin::signed(alice), 0, root(1), FriendCount::new(1)),
+			Error::<Test>::ThresholdAboveFriendCount
+		);
+		for i in 1..=3 {
+			run_to_block(i as u64 + 1);
+			let origin = RuntimeOrigin::signed(alice);
+			assert_ok!(Recovery::rotate_friends_merkle_root(origin, 0, root(i), FriendCount::new(2)));
+		}
+		// `MaxPreviousRoots` is 2
+		let config = Recovery::recovery_config(alice).unwrap();
+		assert_eq!(config.groups()[0].friends_merkle_root(), &root(3)[..]);
+		assert_eq!(
+			config.previous_roots(),
+			&[
+				PreviousRoot { group: 0, friends_merkle_root: root(1), replaced: 3 },
+				PreviousRoot { group: 0, friends_merkle_root: root(2), replaced: 4 },
+			][..]
+		);
+	});
+}
+
+#[cfg(feature = "client")]
+#[test]
+fn client_approvals_are_accepted_on_chain() {
+	use crate::client::ApprovalPayload;
+
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		let dave = get_from_seed("dave");
+		let eve = get_from_seed("eve");
+		let charlie_pair = sr25519::Pair::from_string("//charlie", None).unwrap();
+		let tree = FriendsTree::new(vec![charlie, dave, eve]);
+
+		assert_ok!(Recovery::create_recovery(RuntimeOrigin::signed(alice), tree.root(), 1, 0, FriendCount::new(1)));
+		assert_ok!(Recovery::initiate_recovery(RuntimeOrigin::signed(bob), alice, None));
+		let payload = ApprovalPayload::new(System::block_hash(0), alice, bob, 0, 1u64);
+		assert_eq!(Some(payload.payload()), Recovery::approval_payload(&alice, &bob, 0));
+		assert_ok!(Recovery::approve_recovery(
+			RuntimeOrigin::signed(bob),
+			alice,
+			bob,
+			0,
+			payload.sign(&charlie_pair),
+			tree.proof_for(&charlie).unwrap()
+		));
+		assert_ok!(Recovery::claim_recovery(RuntimeOrigin::signed(bob), alice));
+	});
+}
+
+/// The weight of the migrations of a storage at the current version, where each of them only reads
+/// the storage version.
+fn up_to_date_migrations_weight() -> Weight {
+	<Test as frame_system::Config>::DbWeight::get().reads(11)
+}
+
+#[test]
+fn new_chains_start_at_the_current_storage_version() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Recovery::on_chain_storage_version(), STORAGE_VERSION);
+		assert_eq!(Recovery::on_chain_storage_version(), Recovery::in_code_storage_version());
+		assert_eq!(migrations::Migrations::<Test>::on_runtime_upgrade(), up_to_date_migrations_weight());
+	});
+}
+
+#[test]
+fn migration_from_v1_works() {
+	new_test_ext_v1().execute_with(|| {
+		let (alice, bob, charlie, dave) = fixture_accounts();
+		assert_eq!(Recovery::on_chain_storage_version(), StorageVersion::new(0));
+		assert!(!migrations::legacy::StorageVersion::<Test>::exists());
+		// v1 configs can't be decoded with the current layout
+		assert_eq!(Recovery::recovery_config(alice), None);
+
+		// the weight is the one of the items migrated, not of a whole block
+		let weight = migrations::Migrations::<Test>::on_runtime_upgrade();
+		assert!(weight.any_gt(Weight::zero()));
+		assert!(weight.all_lt(<Test as frame_system::Config>::BlockWeights::get().max_block));
+		assert_eq!(Recovery::on_chain_storage_version(), STORAGE_VERSION);
+		// the single group of friends is the only group, which has to approve
+		for (who, old) in v1_fixture_configs() {
+			assert_eq!(
+				Recovery::recovery_config(who),
+				Some(RecoveryConfig {
+					delay_period: old.delay_period,
+					groups: vec![RecoveryGroup::new(old.friends_merkle_root, old.threshold)],
+					policy: GroupPolicy::All,
+					rescuers_merkle_root: None,
+					auto_sweep: false,
+					threshold_key: None,
+					previous_roots: vec![],
+				})
+			);
+		}
+		// active recoveries are kept, but dave needs to approve again
+		assert_eq!(
+			Recovery::active_recovery(alice, charlie),
+			Some(ActiveRecovery {
+				created: 1,
+				approvals: vec![],
+				key_images: vec![],
+				approval_roots: vec![]
+			})
+		);
+		assert_eq!(Recovery::proxy(dave), Some(bob));
+
+		// running the upgrade again does nothing
+		assert_eq!(migrations::Migrations::<Test>::on_runtime_upgrade(), up_to_date_migrations_weight());
+	});
+}
+
+#[test]
+fn migration_from_v2_works() {
+	new_test_ext_v2().execute_with(|| {
+		let (alice, bob, charlie, dave) = fixture_accounts();
+		assert_eq!(Recovery::on_chain_storage_version(), StorageVersion::new(0));
+		assert_eq!(migrations::legacy::StorageVersion::<Test>::get(), migrations::legacy::Releases::V2);
+		// v2 configs can't be decoded with the current layout
+		assert_eq!(Recovery::recovery_config(alice), None);
+
+		assert!(migrations::Migrations::<Test>::on_runtime_upgrade().any_gt(Weight::zero()));
+		assert_eq!(Recovery::on_chain_storage_version(), STORAGE_VERSION);
+		for (who, old) in v2_fixture_configs() {
+			assert_eq!(
+				Recovery::recovery_config(who),
+				Some(RecoveryConfig {
+					delay_period: old.delay_period,
+					groups: old.groups.into_iter().map(Into::into).collect(),
+					policy: old.policy,
+					rescuers_merkle_root: old.rescuers_merkle_root,
+					auto_sweep: false,
+					threshold_key: None,
+					previous_roots: vec![],
+				})
+			);
+		}
+		// active recoveries are kept, but dave needs to approve again
+		assert_eq!(
+			Recovery::active_recovery(alice, charlie),
+			Some(ActiveRecovery {
+				created: 1,
+				approvals: vec![],
+				key_images: vec![],
+				approval_roots: vec![]
+			})
+		);
+		// the other items are untouched
+		assert_eq!(Recovery::proxy(dave), Some(bob));
+
+		// running the upgrade again does nothing
+		assert_eq!(migrations::Migrations::<Test>::on_runtime_upgrade(), up_to_date_migrations_weight());
+		assert_eq!(Recovery::recovery_config(bob).map(|config| config.groups().len()), Some(2));
+	});
+}
+
+#[test]
+fn migration_from_v4_rekeys_storage() {
+	new_test_ext_v4().execute_with(|| {
+		let (alice, _, charlie, _) = fixture_accounts();
+		// the twox keyed entries can't be found with the current hashers
+		assert_eq!(Recovery::recovery_config(alice), None);
+		assert_eq!(Recovery::active_recovery(alice, charlie), None);
+		assert_eq!(Recovery::blinded_recovery(&alice, [1; 32]), None);
+		let old_recovery_config = migrations::v4::Recoverable::<Test>::get(&alice);
+		let old_active_recovery = migrations::v4::ActiveRecoveries::<Test>::get(&alice, &charlie);
+		let old_blinded_recovery = migrations::v4::BlindedRecoverable::<Test>::get([1; 32]);
+		assert!(old_recovery_config.is_some() && old_active_recovery.is_some() && old_blinded_recovery.is_some());
+
+		assert!(migrations::Migrations::<Test>::on_runtime_upgrade().any_gt(Weight::zero()));
+		assert_eq!(Recovery::on_chain_storage_version(), STORAGE_VERSION);
+		let recovery_config = Recovery::recovery_config(alice);
+		assert_eq!(recovery_config, old_recovery_config.clone().map(Into::into));
+		// the approvals were checked against the only root there was
+		assert_eq!(
+			Recovery::active_recovery(alice, charlie),
+			old_active_recovery
+				.map(|old| migrations::v8::ActiveRecovery::from(old).with_roots_of(recovery_config.as_ref()))
+		);
+		assert_eq!(Recovery::blinded_recovery(&alice, [1; 32]), old_blinded_recovery.map(Into::into));
+		assert_eq!(Recovery::active_recoveries_for(&alice), vec![charlie]);
+		// nothing is left under the old keys, nor anywhere else in the maps
+		assert_eq!(migrations::v4::Recoverable::<Test>::get(&alice), None);
+		assert_eq!(migrations::v4::ActiveRecoveries::<Test>::get(&alice, &charlie), None);
+		assert_eq!(migrations::v4::BlindedRecoverable::<Test>::get([1; 32]), None);
+		assert_eq!(Recoverable::<Test>::iter().count(), 1);
+		assert_eq!(ActiveRecoveries::<Test>::iter().count(), 1);
+		assert_eq!(BlindedRecoverable::<Test>::iter().count(), 1);
+
+		// running the upgrade again does nothing
+		assert_eq!(migrations::Migrations::<Test>::on_runtime_upgrade(), up_to_date_migrations_weight());
+		assert_eq!(Recovery::recovery_config(alice), old_recovery_config.map(Into::into));
+	});
+}
+
+#[test]
+fn migration_from_v9_drops_the_shared_commitments() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		StorageVersion::new(9).put::<Recovery>();
+		migrations::v9::ApprovalCommitments::<Test>::insert(&alice, &bob, vec![([1; 32], 1)]);
+
+		// the commitment is removed, and the storage version read and written
+		assert_eq!(
+			migrations::MigrateToV10::<Test>::on_runtime_upgrade(),
+			<Test as frame_system::Config>::DbWeight::get().reads_writes(2, 2)
+		);
+		assert_eq!(Recovery::on_chain_storage_version(), StorageVersion::new(10));
+		assert_eq!(migrations::v9::ApprovalCommitments::<Test>::get(&alice, &bob), None);
+		assert_eq!(ApprovalCommitments::<Test>::iter().count(), 0);
+	});
+}
+
+#[test]
+fn migration_from_v10_tracks_the_deposits() {
+	new_test_ext().execute_with(|| {
+		let alice = get_from_seed("alice");
+		let bob = get_from_seed("bob");
+		let charlie = get_from_seed("charlie");
+		StorageVersion::new(10).put::<Recovery>();
+		// charlie committed twice, and alice blinded a config, before the deposits were tracked
+		assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&charlie, 10));
+		ApprovalCommitments::<Test>::insert((&alice, &bob, &charlie), vec![([1; 32], 1), ([2; 32], 1)]);
+		assert_ok!(<Balances as ReservableCurrency<_>>::reserve(&alice, 10));
+		BlindedDeposits::<Test>::insert([1; 32], 10);
+
+		assert!(migrations::MigrateToV11::<Test>::on_runtime_upgrade().any_gt(Weight::zero()));
+		assert_eq!(Recovery::on_chain_storage_version(), STORAGE_VERSION);
+		assert_eq!(Deposits::<Test>::get(charlie), 10);
+		assert_eq!(BlindedDeposits::<Test>::get([1; 32]), 0);
+		assert_eq!(LegacyBlindedDeposits::<Test>::get([1; 32]), 10);
+
+		// the deposits of the commitments come back when they are cleared
+		assert_eq!(Recovery::clear_commitments(&alice, &bob), 1);
+		assert_eq!(Balances::reserved_balance(charlie), 0);
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn migrations_pass_their_try_runtime_checks() {
+	for mut ext in [new_test_ext_v1(), new_test_ext_v2(), new_test_ext_v4(), new_test_ext()] {
+		ext.execute_with(|| {
+			assert_ok!(migrations::Migrations::<Test>::try_on_runtime_upgrade(true));
+			assert_eq!(Recovery::on_chain_storage_version(), STORAGE_VERSION);
+		});
+	}
+}
+
+#[test]
+fn blinded_signatures_survive_the_padded_groups_migration() {
+	new_test_ext_v4().execute_with(|| {
+		let (alice, bob, _, _) = fixture_accounts();
+		let salt = [7; 32];
+		let key = blinded_key(&alice, &salt);
+		// alice signed her blinded config before groups could be padded
+		let config = migrations::v5::RecoveryConfig {
+			delay_period: 10u64,
+			groups: vec![v5_group(1, 2)],
+			policy: GroupPolicy::All,
+			rescuers_merkle_root: None,
+			auto_sweep: false,
+		};
+		let old_payload = (BLINDED_RECOVERY_CONTEXT, key, &config.groups, config.policy, 10u64, None::<Vec<u8>>).encode();
+		let owner_signature = sign_by_seed("alice", &old_payload);
+		migrations::v4::BlindedRecoverable::<Test>::insert(key, migrations::v5::BlindedRecovery { config, owner_signature });
+
+		assert!(migrations::Migrations::<Test>::on_runtime_upgrade().any_gt(Weight::zero()));
+		let groups = vec![RecoveryGroup::new(vec![1; MERKLE_ROOT_LENGTH], 2)];
+		assert_eq!(blinded_recovery_payload(&key, &groups, GroupPolicy::All, &10u64, &None), old_payload);
+		assert_ok!(Recovery::initiate_blinded_recovery(RuntimeOrigin::signed(bob), alice, salt, None));
 	});
 }
 
@@ -0,0 +1,287 @@
+//! Weights for the secret social recovery pallet.
+//!
+//! These are hand-written estimates, not measurements: the computation is rounded up from the
+//! cost of the hashes, signature checks and curve operations of each call in WASM, the storage
+//! accesses are charged with `DbWeight`, and the proof sizes are bounded by the largest encoding
+//! of the items read. Runtimes should replace them with weights generated on their reference
+//! hardware from the benchmarks in `benchmarking.rs`:
+//! `frame-omni-bencher v1 benchmark pallet --runtime <runtime.wasm> --pallet secret_social_recovery --extrinsic '*' --steps 50 --repeat 20 --output weights.rs`
+//!
+//! Components:
+//! - `g`: the number of groups in a recovery config
+//! - `d`: the depth of a merkle proof
+//! - `a`: the number of approvals of an active recovery
+//! - `n`: the number of revealed approvals, or of friends approving together
+//! - `r`: the number of accounts in a ring
+//! - `c`: the number of committers whose pending commitments are cleared
+//!
+//! Storage, as the largest proof size of one read:
+//! - `Recoverable` and `BlindedRecoverable`: 3_500 bytes, for `MaxGroups` groups with their
+//! 	previous roots
+//! - `ActiveRecoveries`: 3_000 bytes, for `MaxApprovals` approvals or key images
+//! - `ApprovalCommitments`: 1_500 bytes, for `MaxApprovals` commitments
+//! - `BlindedDeposits`, `LegacyBlindedDeposits` and `Deposits`: 100 bytes
+//! - `Proxy`: 100 bytes
+//! - `System::Account`: 3_600 bytes
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed by the pallet.
+pub trait WeightInfo {
+	fn set_recovered() -> Weight;
+	fn as_recovered() -> Weight;
+	fn create_recovery() -> Weight;
+	fn create_group_recovery(g: u32) -> Weight;
+	fn initiate_recovery(d: u32) -> Weight;
+	fn approve_recovery(d: u32, a: u32) -> Weight;
+	fn claim_recovery(a: u32) -> Weight;
+	fn set_auto_sweep() -> Weight;
+	fn sweep_recovered() -> Weight;
+	fn create_blinded_recovery(g: u32) -> Weight;
+	fn blind_recovery() -> Weight;
+	fn initiate_blinded_recovery(d: u32) -> Weight;
+	fn commit_approval() -> Weight;
+	fn reveal_approvals(n: u32, d: u32) -> Weight;
+	fn set_threshold_key() -> Weight;
+	fn claim_with_threshold_signature() -> Weight;
+	fn approve_recovery_aggregated(n: u32, d: u32) -> Weight;
+	fn approve_recovery_anonymously(r: u32) -> Weight;
+	fn rotate_friends_merkle_root() -> Weight;
+	fn clear_commitments(c: u32) -> Weight;
+}
+
+/// The estimated weights of the pallet, with the database weights of the runtime.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `Proxy` (w:1)
+	fn set_recovered() -> Weight {
+		Weight::from_parts(20_000_000, 0).saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Storage: `Proxy` (r:1)
+	fn as_recovered() -> Weight {
+		Weight::from_parts(25_000_000, 100).saturating_add(T::DbWeight::get().reads(1))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn create_recovery() -> Weight {
+		Weight::from_parts(40_000_000, 3_500).saturating_add(T::DbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn create_group_recovery(g: u32) -> Weight {
+		Weight::from_parts(40_000_000, 3_500)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(g as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1)
+	fn initiate_recovery(d: u32) -> Weight {
+		Weight::from_parts(45_000_000, 6_500)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(d as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(2, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1)
+	fn approve_recovery(d: u32, a: u32) -> Weight {
+		Weight::from_parts(150_000_000, 6_500)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(d as u64))
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(a as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(2, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1), `Proxy` (r:1 w:1),
+	/// `System::Account` (r:1 w:1)
+	fn claim_recovery(a: u32) -> Weight {
+		Weight::from_parts(60_000_000, 10_200)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(a as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(4, 2))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn set_auto_sweep() -> Weight {
+		Weight::from_parts(35_000_000, 3_500).saturating_add(T::DbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `Proxy` (r:1), `Deposits` (r:1 w:1), `System::Account` (r:2 w:2), `Balances::Locks` (r:1),
+	/// `Balances::Freezes` (r:1), and `MAX_SWEEP_PROBES` withdrawal checks
+	fn sweep_recovered() -> Weight {
+		Weight::from_parts(270_000_000, 12_100).saturating_add(T::DbWeight::get().reads_writes(6, 3))
+	}
+	/// Storage: `BlindedRecoverable` (r:2 w:1), `BlindedDeposits` (w:1), `Deposits` (r:1 w:1),
+	/// `System::Account` (r:1 w:1)
+	fn create_blinded_recovery(g: u32) -> Weight {
+		Weight::from_parts(150_000_000, 10_700)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(g as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(4, 4))
+	}
+	/// Storage: `Recoverable` (r:1 w:1), `ActiveRecoveries` (r:1), `BlindedRecoverable` (r:2 w:1),
+	/// `BlindedDeposits` (w:1), `Deposits` (r:1 w:1), `System::Account` (r:1 w:1)
+	fn blind_recovery() -> Weight {
+		Weight::from_parts(170_000_000, 17_200).saturating_add(T::DbWeight::get().reads_writes(6, 5))
+	}
+	/// Storage: `BlindedRecoverable` (r:2 w:1), `BlindedDeposits` (r:1 w:1), `LegacyBlindedDeposits` (r:1 w:1),
+	/// `Deposits` (r:1 w:1), `System::Account` (r:1 w:1), `Recoverable` (r:1 w:1), `ActiveRecoveries` (r:1 w:1)
+	fn initiate_blinded_recovery(d: u32) -> Weight {
+		Weight::from_parts(180_000_000, 17_400)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(d as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(8, 7))
+	}
+	/// Storage: `ActiveRecoveries` (r:1), `ApprovalCommitments` (r:1 w:1), `Deposits` (r:1 w:1),
+	/// `System::Account` (r:1 w:1)
+	fn commit_approval() -> Weight {
+		Weight::from_parts(50_000_000, 8_200).saturating_add(T::DbWeight::get().reads_writes(4, 3))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1), `ApprovalCommitments` (r:1 w:1),
+	/// `Deposits` (r:1 w:1), `System::Account` (r:1 w:1)
+	fn reveal_approvals(n: u32, d: u32) -> Weight {
+		Weight::from_parts(70_000_000, 11_700)
+			.saturating_add(Weight::from_parts(160_000_000, 0).saturating_mul(n as u64))
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(n as u64).saturating_mul(d as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(5, 4))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn set_threshold_key() -> Weight {
+		Weight::from_parts(35_000_000, 3_500).saturating_add(T::DbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1), `Proxy` (r:1 w:1),
+	/// `System::Account` (r:1 w:1)
+	fn claim_with_threshold_signature() -> Weight {
+		Weight::from_parts(160_000_000, 10_200).saturating_add(T::DbWeight::get().reads_writes(4, 2))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1)
+	fn approve_recovery_aggregated(n: u32, d: u32) -> Weight {
+		Weight::from_parts(25_000_000_000, 6_500)
+			.saturating_add(Weight::from_parts(1_500_000_000, 0).saturating_mul(n as u64))
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(n as u64).saturating_mul(d as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(2, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1)
+	fn approve_recovery_anonymously(r: u32) -> Weight {
+		Weight::from_parts(300_000_000, 6_500)
+			.saturating_add(Weight::from_parts(1_000_000_000, 0).saturating_mul(r as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(2, 1))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn rotate_friends_merkle_root() -> Weight {
+		Weight::from_parts(40_000_000, 3_500).saturating_add(T::DbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `ApprovalCommitments` (r:c w:c), `Deposits` (r:c w:c), `System::Account` (r:c w:c)
+	fn clear_commitments(c: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(30_000_000, 5_200).saturating_mul(c as u64))
+			.saturating_add(T::DbWeight::get().reads_writes(3 * c as u64, 3 * c as u64))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: `Proxy` (w:1)
+	fn set_recovered() -> Weight {
+		Weight::from_parts(20_000_000, 0).saturating_add(RocksDbWeight::get().writes(1))
+	}
+	/// Storage: `Proxy` (r:1)
+	fn as_recovered() -> Weight {
+		Weight::from_parts(25_000_000, 100).saturating_add(RocksDbWeight::get().reads(1))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn create_recovery() -> Weight {
+		Weight::from_parts(40_000_000, 3_500).saturating_add(RocksDbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn create_group_recovery(g: u32) -> Weight {
+		Weight::from_parts(40_000_000, 3_500)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(g as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1)
+	fn initiate_recovery(d: u32) -> Weight {
+		Weight::from_parts(45_000_000, 6_500)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(d as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(2, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1)
+	fn approve_recovery(d: u32, a: u32) -> Weight {
+		Weight::from_parts(150_000_000, 6_500)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(d as u64))
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(a as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(2, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1), `Proxy` (r:1 w:1),
+	/// `System::Account` (r:1 w:1)
+	fn claim_recovery(a: u32) -> Weight {
+		Weight::from_parts(60_000_000, 10_200)
+			.saturating_add(Weight::from_parts(500_000, 0).saturating_mul(a as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(4, 2))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn set_auto_sweep() -> Weight {
+		Weight::from_parts(35_000_000, 3_500).saturating_add(RocksDbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `Proxy` (r:1), `Deposits` (r:1 w:1), `System::Account` (r:2 w:2), `Balances::Locks` (r:1),
+	/// `Balances::Freezes` (r:1), and `MAX_SWEEP_PROBES` withdrawal checks
+	fn sweep_recovered() -> Weight {
+		Weight::from_parts(270_000_000, 12_100).saturating_add(RocksDbWeight::get().reads_writes(6, 3))
+	}
+	/// Storage: `BlindedRecoverable` (r:2 w:1), `BlindedDeposits` (w:1), `Deposits` (r:1 w:1),
+	/// `System::Account` (r:1 w:1)
+	fn create_blinded_recovery(g: u32) -> Weight {
+		Weight::from_parts(150_000_000, 10_700)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(g as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(4, 4))
+	}
+	/// Storage: `Recoverable` (r:1 w:1), `ActiveRecoveries` (r:1), `BlindedRecoverable` (r:2 w:1),
+	/// `BlindedDeposits` (w:1), `Deposits` (r:1 w:1), `System::Account` (r:1 w:1)
+	fn blind_recovery() -> Weight {
+		Weight::from_parts(170_000_000, 17_200).saturating_add(RocksDbWeight::get().reads_writes(6, 5))
+	}
+	/// Storage: `BlindedRecoverable` (r:2 w:1), `BlindedDeposits` (r:1 w:1), `LegacyBlindedDeposits` (r:1 w:1),
+	/// `Deposits` (r:1 w:1), `System::Account` (r:1 w:1), `Recoverable` (r:1 w:1), `ActiveRecoveries` (r:1 w:1)
+	fn initiate_blinded_recovery(d: u32) -> Weight {
+		Weight::from_parts(180_000_000, 17_400)
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(d as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(8, 7))
+	}
+	/// Storage: `ActiveRecoveries` (r:1), `ApprovalCommitments` (r:1 w:1), `Deposits` (r:1 w:1),
+	/// `System::Account` (r:1 w:1)
+	fn commit_approval() -> Weight {
+		Weight::from_parts(50_000_000, 8_200).saturating_add(RocksDbWeight::get().reads_writes(4, 3))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1), `ApprovalCommitments` (r:1 w:1),
+	/// `Deposits` (r:1 w:1), `System::Account` (r:1 w:1)
+	fn reveal_approvals(n: u32, d: u32) -> Weight {
+		Weight::from_parts(70_000_000, 11_700)
+			.saturating_add(Weight::from_parts(160_000_000, 0).saturating_mul(n as u64))
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(n as u64).saturating_mul(d as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(5, 4))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn set_threshold_key() -> Weight {
+		Weight::from_parts(35_000_000, 3_500).saturating_add(RocksDbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1), `Proxy` (r:1 w:1),
+	/// `System::Account` (r:1 w:1)
+	fn claim_with_threshold_signature() -> Weight {
+		Weight::from_parts(160_000_000, 10_200).saturating_add(RocksDbWeight::get().reads_writes(4, 2))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1)
+	fn approve_recovery_aggregated(n: u32, d: u32) -> Weight {
+		Weight::from_parts(25_000_000_000, 6_500)
+			.saturating_add(Weight::from_parts(1_500_000_000, 0).saturating_mul(n as u64))
+			.saturating_add(Weight::from_parts(5_000_000, 0).saturating_mul(n as u64).saturating_mul(d as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(2, 1))
+	}
+	/// Storage: `Recoverable` (r:1), `ActiveRecoveries` (r:1 w:1)
+	fn approve_recovery_anonymously(r: u32) -> Weight {
+		Weight::from_parts(300_000_000, 6_500)
+			.saturating_add(Weight::from_parts(1_000_000_000, 0).saturating_mul(r as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(2, 1))
+	}
+	/// Storage: `Recoverable` (r:1 w:1)
+	fn rotate_friends_merkle_root() -> Weight {
+		Weight::from_parts(40_000_000, 3_500).saturating_add(RocksDbWeight::get().reads_writes(1, 1))
+	}
+	/// Storage: `ApprovalCommitments` (r:c w:c), `Deposits` (r:c w:c), `System::Account` (r:c w:c)
+	fn clear_commitments(c: u32) -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(Weight::from_parts(30_000_000, 5_200).saturating_mul(c as u64))
+			.saturating_add(RocksDbWeight::get().reads_writes(3 * c as u64, 3 * c as u64))
+	}
+}